@@ -4,16 +4,138 @@
    :start-line: 1
 """
 
+import json
+import warnings
+import xml.etree.ElementTree as ET
+from collections.abc import Callable, Iterator, Mapping
+from datetime import datetime, timezone
+from functools import partial
+from pathlib import Path
+from typing import Any, BinaryIO, Literal, TypedDict, Unpack
+
 import polars as pl
+from polars.io.plugins import register_io_source
 
+from polarsfit._internal import MessageBatchReader as _MessageBatchReader
+from polarsfit._internal import RecordBatchReader as _RecordBatchReader
+from polarsfit._internal import concat_frames as _concat_frames
+from polarsfit._internal import decode_manufacturer as _decode_manufacturer
+from polarsfit._internal import decode_product as _decode_product
+from polarsfit._internal import get_message_counts as _get_message_counts
+from polarsfit._internal import get_field_numbers as _get_field_numbers
 from polarsfit._internal import get_message_types as _get_message_types
+from polarsfit._internal import get_schema as _get_schema
+from polarsfit._internal import read_all_messages as _read_all_messages
 from polarsfit._internal import read_data as _read_data
-from polarsfit._internal import read_recordmesgs as _read_recordmesgs
+from polarsfit._internal import read_data_arrow_ipc as _read_data_arrow_ipc
+from polarsfit._internal import read_data_by_type as _read_data_by_type
+from polarsfit._internal import read_data_from_bytes as _read_data_from_bytes
+from polarsfit._internal import read_header as _read_header
+from polarsfit._internal import read_many_data as _read_many_data
+from polarsfit._internal import write_data_to_parquet as _write_data_to_parquet
+from polarsfit._internal import write_records_to_fit as _write_records_to_fit
+from polarsfit.expressions import (
+    bearing,
+    elevation_gain,
+    find_peaks,
+    grade,
+    grade_adjusted_pace,
+    haversine_cumulative,
+    hr_zone,
+    interpolate_gps,
+    is_moving,
+    moving_time,
+    normalized_power,
+    pace,
+    rolling_smooth,
+    semicircle_to_degrees,
+    simplify_track,
+    vertical_speed,
+    wprime_balance,
+)
+from polarsfit.analysis import (
+    ASCENT_THRESHOLD,
+    BEST_EFFORT_DISTANCES,
+    CYCLING_EFFICIENCY,
+    POWER_CURVE_DURATIONS,
+)
+from polarsfit.analysis import ascent_descent as _ascent_descent
+from polarsfit.analysis import best_efforts as _best_efforts
+from polarsfit.analysis import decoupling as _decoupling
+from polarsfit.analysis import detect_stops as _detect_stops
+from polarsfit.analysis import estimate_calories as _estimate_calories
+from polarsfit.analysis import power_curve as _power_curve
+from polarsfit.analysis import time_in_zones as _time_in_zones
+from polarsfit.events import timer_segments as _timer_segments
+from polarsfit.export import to_geojson as _to_geojson
+from polarsfit.export import to_gpx as _to_gpx
+from polarsfit.export import to_tcx as _to_tcx
 from polarsfit.fields import (
     MessageType,
     get_available_message_types,
     get_field_mapping,
+    get_field_units,
 )
+from polarsfit.fields import get_profile_field_info as _profile_field_info
+from polarsfit.laps import assign_laps as _assign_laps
+from polarsfit.localtime import utc_offset as _utc_offset
+from polarsfit.localtime import with_local_time as _local_time
+from polarsfit.metadata import file_metadata as _file_metadata
+from polarsfit.resample import Aggregation
+from polarsfit.resample import resample as _resample
+from polarsfit.sessions import split_by_session as _split_by_session
+from polarsfit.summary import summarize_course_points as _summarize_course_points
+from polarsfit.summary import summarize_lengths as _summarize_lengths
+from polarsfit.summary import summarize_sessions as _summarize_sessions
+from polarsfit.summary import summarize_workout_steps as _summarize_workout_steps
+
+
+class ReadOptions(TypedDict, total=False):
+    """
+    Keyword options of ``read_data``, shared by the functions reading like it.
+
+    See ``read_data`` for what each option does.
+    """
+
+    apply_default_mapping: bool
+    fields: list[str] | None
+    schema: dict[str, str] | None
+    start_time: datetime | float | None
+    end_time: datetime | float | None
+    add_index: bool
+    decode_timestamps: bool
+    apply_scale: bool
+    convert_gps: bool
+    decode_enums: bool
+    categorical_enums: bool
+    verify_crc: bool
+    mask_invalid: bool
+    progress: Callable[[int], object] | None
+    truncated_ok: bool
+    limit: int | None
+    sort_by_time: bool
+    dedup_time: bool
+    dedup_keep: Literal["first", "last"]
+    all_float: bool
+    keep_raw: bool
+    encoding: Literal["utf-8", "latin-1"]
+    temperature_unit: Literal["C", "F"]
+
+
+class RecordOptions(ReadOptions, total=False):
+    """
+    Keyword options of ``read_recordmesgs``: those of ``read_data`` and more.
+
+    See ``read_recordmesgs`` for what each option does.
+    """
+
+    prefer_enhanced: bool
+    local_time: bool
+    double_running_cadence: bool
+    fill_distance: bool
+    force: bool
+    altitude_source: Literal["auto", "enhanced", "baro", "gps"]
+    altitude_offset: float
 
 
 def read_recordmesgs(
@@ -21,6 +143,35 @@ def read_recordmesgs(
     field_mapping: dict[str, str] | None = None,
     *,
     apply_default_mapping: bool = True,
+    prefer_enhanced: bool = True,
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    start_time: datetime | float | None = None,
+    end_time: datetime | float | None = None,
+    add_index: bool = False,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    categorical_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    progress: Callable[[int], object] | None = None,
+    truncated_ok: bool = True,
+    limit: int | None = None,
+    sort_by_time: bool = False,
+    dedup_time: bool = False,
+    dedup_keep: Literal["first", "last"] = "first",
+    all_float: bool = False,
+    keep_raw: bool = False,
+    encoding: Literal["utf-8", "latin-1"] = "utf-8",
+    temperature_unit: Literal["C", "F"] = "C",
+    local_time: bool = False,
+    double_running_cadence: bool = False,
+    fill_distance: bool = False,
+    force: bool = False,
+    altitude_source: Literal["auto", "enhanced", "baro", "gps"] = "auto",
+    altitude_offset: float = 0.0,
 ) -> pl.DataFrame:
     """
     Read record messages from a FIT file.
@@ -28,12 +179,146 @@ def read_recordmesgs(
     Parameters
     ----------
     file_path : str
-        Path to the FIT file.
+        Path to the FIT file. Gzip-compressed files (``.fit.gz``) are
+        decompressed automatically.
     field_mapping : dict[str, str] | None, optional
         Custom mapping from field numbers to field names.
         Format: {"field_123": "custom_name"}
     apply_default_mapping : bool, default True
-        Whether to apply the default FIT protocol field mapping.
+        Whether to name columns by their FIT profile field names, as in
+        ``read_data``.
+    prefer_enhanced : bool, default True
+        Whether to fill ``speed`` and ``altitude`` from their 32-bit
+        ``enhanced_speed`` and ``enhanced_altitude`` variants, which newer
+        devices record instead of or alongside them. The enhanced columns are
+        dropped, and the legacy values are kept only where no enhanced value
+        is recorded. If False, both are returned as decoded.
+    fields : list[str] | None, optional
+        Names of the columns to decode, after field mapping, e.g.
+        ``["timestamp", "position_lat", "position_long"]``. Other fields are
+        skipped while decoding, which saves time and memory on messages with
+        many fields. Names not found in the file are ignored. By default all
+        fields are decoded.
+    schema : dict[str, str] | None, optional
+        Dtypes to cast columns to after decoding, by final column name, e.g.
+        ``{"heart_rate": "f64"}``. Accepted dtypes are ``i8`` to ``i64``,
+        ``u8`` to ``u64``, ``f32``, ``f64``, ``bool``, ``str``, ``cat``
+        (Categorical), ``datetime`` (UTC, milliseconds) and ``list[...]`` of
+        any of these. Columns not
+        listed keep their inferred dtype; see ``get_schema``.
+    start_time, end_time : datetime | float | None, optional
+        Only keep messages timestamped within this range, inclusive, given
+        as datetimes (naive ones are taken as UTC) or Unix seconds. Messages
+        outside it are skipped while decoding, and messages without a
+        timestamp are skipped whenever a range is given.
+    add_index : bool, default False
+        Whether to add a leading ``message_index`` ``UInt32`` column numbering
+        the messages of this type in file order, from 0. Messages skipped by
+        a time range still take up their number.
+    decode_timestamps : bool, default True
+        Whether to return timestamp fields as UTC ``Datetime`` columns.
+        If False, the raw ``UInt32`` seconds since the FIT epoch
+        (1989-12-31 00:00:00 UTC) are kept.
+    apply_scale : bool, default True
+        Whether to apply the FIT profile scale and offset to numeric fields,
+        e.g. returning speed in m/s as ``Float64`` rather than raw mm/s.
+    convert_gps : bool, default True
+        Whether to convert the record ``position_lat`` and ``position_long``
+        fields from semicircles to degrees as ``Float64``. If False, their
+        raw ``Int32`` semicircles are kept. Positions of other message types,
+        such as the start and end positions of laps, are always converted.
+    decode_enums : bool, default True
+        Whether to return enum fields by their FIT profile names, e.g.
+        ``"cycling"`` for the session sport. Values without a name are kept
+        as numeric strings. If False, the raw integers are kept.
+    categorical_enums : bool, default True
+        Whether to return decoded enum columns as ``Categorical`` rather
+        than ``String``, which takes less memory and speeds up grouping on
+        columns such as ``sport`` or ``event``. Other string columns stay
+        ``String``.
+    verify_crc : bool, default False
+        Whether to check the header and file CRCs and raise ``ValueError``
+        if they don't match, e.g. for a corrupted download. By default the
+        CRCs are ignored and whatever can be decoded is returned.
+    mask_invalid : bool, default True
+        Whether to return values equal to the FIT "invalid" sentinel of their
+        base type (e.g. ``0xFFFF`` for ``uint16``) as null. If False, the
+        sentinels are kept as ordinary values.
+    progress : Callable[[int], object] | None, optional
+        Called with the number of messages decoded so far, every 10,000
+        messages and once more at the end, e.g. to update a progress bar.
+        Exceptions it raises abort the read.
+    truncated_ok : bool, default True
+        Whether to return the messages before the first malformed one, with
+        a ``UserWarning``, if decoding fails partway through the file, e.g.
+        for a recording cut off by a crashed device. If False, a
+        ``ValueError`` is raised instead.
+    limit : int | None, optional
+        Maximum number of messages to read, e.g. to preview a file. Decoding
+        stops once that many are read.
+    sort_by_time : bool, default False
+        Whether to sort the messages by timestamp, for firmware that writes
+        them out of order. Messages without a timestamp go last.
+    dedup_time : bool, default False
+        Whether to drop messages repeating the timestamp of another one,
+        keeping the one chosen by ``dedup_keep``. Messages without a
+        timestamp are kept.
+    dedup_keep : {"first", "last"}, default "first"
+        Which message of a repeated timestamp ``dedup_time`` keeps, in file
+        order, or time order with ``sort_by_time``.
+    all_float : bool, default False
+        Whether to cast every numeric column to ``Float64``, e.g. for a
+        uniform feature matrix, with invalid values as null. String,
+        datetime and list columns, ``message_index`` and columns given in
+        ``schema`` keep their dtype.
+    keep_raw : bool, default False
+        Whether to also return the undecoded integer of every field that
+        scaling, semicircle conversion or enum decoding changes, in a
+        ``<name>_raw`` column after it, e.g. ``speed_raw`` in mm/s next to
+        ``speed`` in m/s, for debugging or re-encoding. Timestamps and fields
+        unpacked from components get none.
+    encoding : {"utf-8", "latin-1"}, default "utf-8"
+        Character encoding of string fields such as device names. Strings end
+        at their first null byte, which pads fixed-width fields. Bytes that
+        aren't valid UTF-8 are replaced by ``"\ufffd"``; use ``"latin-1"``
+        for older devices that write accented names in Latin-1.
+    temperature_unit : {"C", "F"}, default "C"
+        Unit of temperature fields such as the record ``temperature``.
+        ``"F"`` converts them from the profile's degrees Celsius to
+        Fahrenheit as ``Float64``, with the invalid value 127 as null.
+    local_time : bool, default False
+        Whether to add a ``local_time`` column of the record timestamps in
+        the timezone the activity was recorded in, e.g. for plots against
+        wall-clock time. The offset from UTC is the difference between the
+        ``local_timestamp`` and ``timestamp`` of the activity message;
+        ``local_time`` is timezone-naive, or integer seconds without
+        ``decode_timestamps``. Files without an activity message, or one
+        without a local timestamp, get a null ``local_time``.
+    double_running_cadence : bool, default False
+        Whether to double ``cadence`` and ``fractional_cadence`` of records
+        in running sessions, from the steps of one leg per minute the FIT
+        profile records to the total steps per minute runners know. Records
+        of other sports, e.g. the bike leg of a triathlon, are unchanged.
+    fill_distance : bool, default False
+        Whether to reconstruct ``distance`` in meters from the GPS track, as
+        ``haversine_cumulative`` of ``position_lat`` and ``position_long``,
+        when the file has no distance or only nulls, e.g. for GPS-only
+        watches. Records without a position keep the previous distance.
+    force : bool, default False
+        Whether ``fill_distance`` also replaces a recorded distance, e.g. to
+        trust GPS over a miscalibrated wheel sensor.
+    altitude_source : {"auto", "enhanced", "baro", "gps"}, default "auto"
+        Source of the ``altitude`` column: ``"enhanced"`` for the
+        ``enhanced_altitude`` field, ``"baro"`` for the legacy ``altitude``
+        field, which devices with a barometer fill from it, and ``"gps"``
+        for the GPS altitude of the ``gps_metadata`` messages with the
+        record's timestamp. ``"auto"`` takes the first recorded of these, in
+        that order, or without ``prefer_enhanced`` of the last two. Other
+        than with ``"auto"``, ``enhanced_altitude`` is not returned, and
+        records without the chosen source have a null altitude.
+    altitude_offset : float, default 0.0
+        Meters added to ``altitude``, to correct a known bias of its source.
+        Needs ``apply_scale``, as unscaled altitudes aren't in meters.
 
     Returns
     -------
@@ -52,38 +337,71 @@ def read_recordmesgs(
     >>> # Read without any field mapping (raw field numbers)
     >>> df = polarsfit.read_recordmesgs("workout.fit", apply_default_mapping=False)
     """
-    # Get raw data from Rust (with field_X column names)
-    df = _read_recordmesgs(file_path)
-
-    # Apply field mapping by renaming columns
-    if apply_default_mapping or field_mapping:
-        # Build field mapping
-        final_mapping = {}
-
-        if apply_default_mapping:
-            # Apply default RECORD field mapping (lazy import to avoid circular import)
-            from polarsfit.fields import RECORD_FIELDS
-
-            # RECORD_FIELDS already has keys in format field_X -> field_name
-            final_mapping.update(RECORD_FIELDS)
-
-        if field_mapping:
-            # Apply custom mapping (overrides default)
-            final_mapping.update(field_mapping)
-
-        # Rename columns that have mappings
-        if final_mapping:
-            # Only rename columns that exist in the DataFrame
-            existing_columns = set(df.columns)
-            rename_mapping = {
-                col: new_name
-                for col, new_name in final_mapping.items()
-                if col in existing_columns
-            }
-            if rename_mapping:
-                df = df.rename(rename_mapping)
+    options: RecordOptions = {
+        "apply_default_mapping": apply_default_mapping,
+        "prefer_enhanced": prefer_enhanced,
+        "fields": fields,
+        "schema": schema,
+        "start_time": start_time,
+        "end_time": end_time,
+        "add_index": add_index,
+        "decode_timestamps": decode_timestamps,
+        "apply_scale": apply_scale,
+        "convert_gps": convert_gps,
+        "decode_enums": decode_enums,
+        "categorical_enums": categorical_enums,
+        "verify_crc": verify_crc,
+        "mask_invalid": mask_invalid,
+        "progress": progress,
+        "truncated_ok": truncated_ok,
+        "limit": limit,
+        "sort_by_time": sort_by_time,
+        "dedup_time": dedup_time,
+        "dedup_keep": dedup_keep,
+        "all_float": all_float,
+        "keep_raw": keep_raw,
+        "encoding": encoding,
+        "temperature_unit": temperature_unit,
+        "local_time": local_time,
+        "double_running_cadence": double_running_cadence,
+        "fill_distance": fill_distance,
+        "force": force,
+        "altitude_source": altitude_source,
+        "altitude_offset": altitude_offset,
+    }
+    return _read_records(partial(_read_data, file_path), field_mapping, options)
+
+
+def read_recordmesgs_from_bytes(
+    data: bytes,
+    field_mapping: dict[str, str] | None = None,
+    **options: Unpack[RecordOptions],
+) -> pl.DataFrame:
+    """
+    Read record messages from the contents of a FIT file.
+
+    Takes the same options as ``read_recordmesgs``.
+
+    Parameters
+    ----------
+    data : bytes
+        Raw bytes of the FIT file, e.g. from an HTTP response or a zip archive.
+        Any bytes-like object is accepted; gzip-compressed data is
+        decompressed automatically.
+
+    Returns
+    -------
+    polars.DataFrame
+        DataFrame containing the record messages with mapped field names.
 
-    return df
+    Examples
+    --------
+    >>> with open("workout.fit", "rb") as f:
+    ...     df = polarsfit.read_recordmesgs_from_bytes(f.read())
+    """
+    return _read_records(
+        partial(_read_data_from_bytes, bytes(data)), field_mapping, options
+    )
 
 
 def get_message_types(file_path: str) -> list[str]:
@@ -98,24 +416,184 @@ def get_message_types(file_path: str) -> list[str]:
     Returns
     -------
     list[str]
-        List of message type names found in the file.
+        List of message type names found in the file. Types missing from the
+        FIT profile, such as vendor-specific ones, are named by their global
+        message number, e.g. ``"message_65281"``, and can be read by that name.
 
     Examples
     --------
     >>> message_types = polarsfit.get_message_types("workout.fit")
     >>> print(message_types)
-    ['record', 'session', 'lap', 'file_id', 'event']
+    ['event', 'fileid', 'lap', 'record', 'session']
     """
     return _get_message_types(file_path)
 
 
-def read_data(
+def read_header(file_path: str) -> dict[str, Any]:
+    """
+    Read the header of a FIT file.
+
+    Only the 12 or 14 byte header is read, so this is instant even for large
+    files. Useful to diagnose files from unusual firmware, or to pick the
+    profile version to interpret them with.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    dict[str, Any]
+        ``header_size`` (12 or 14), ``protocol_version`` and
+        ``profile_version`` as major.minor numbers (e.g. 2.0 and 21.32),
+        ``data_size`` in bytes, the ``data_type`` signature ``".FIT"``, and
+        the ``header_crc`` of 14 byte headers (None for 12 byte headers).
+
+    Examples
+    --------
+    >>> polarsfit.read_header("workout.fit")["profile_version"]
+    21.32
+    """
+    return _read_header(file_path)
+
+
+def get_message_counts(file_path: str) -> dict[str, int]:
+    """
+    Count the messages of each type in a FIT file.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    dict[str, int]
+        Number of messages per message type found in the file.
+
+    Examples
+    --------
+    >>> counts = polarsfit.get_message_counts("workout.fit")
+    >>> if counts.get("lap", 0) > 1:
+    ...     laps = polarsfit.read_data("workout.fit", "lap")
+    """
+    return _get_message_counts(file_path)
+
+
+def get_field_info(file_path: str, message_type: str) -> list[tuple[int, str, str]]:
+    """
+    List the fields of a message type in a FIT file with their profile names.
+
+    Reports the field number, the FIT profile name and the profile unit of
+    every field that holds a value in at least one message of the type. Use
+    it to build ``field_mapping`` dictionaries without looking field numbers
+    up in the FIT SDK, or to see which fields a device records. Units
+    describe the scaled values; positions are in ``"semicircles"`` as
+    stored. Developer fields are not included.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    message_type : str
+        Type of messages to inspect (e.g., 'record', 'session', 'lap').
+
+    Returns
+    -------
+    list[tuple[int, str, str]]
+        ``(field_number, name, units)`` per field, by field number. Fields
+        missing from the profile are named ``field_<number>``, fields
+        without a unit have ``""``. Empty if the file has no messages of
+        the type.
+
+    Examples
+    --------
+    >>> polarsfit.get_field_info("workout.fit", "record")[:2]
+    [(0, 'position_lat', 'semicircles'), (1, 'position_long', 'semicircles')]
+    """
+    mesg_num, numbers = _get_field_numbers(file_path, message_type)
+    if mesg_num is None:
+        return []
+    return _profile_field_info(mesg_num, numbers)
+
+
+def get_schema(
     file_path: str,
     message_type: str,
     field_mapping: dict[str, str] | None = None,
+    **options: Unpack[ReadOptions],
+) -> dict[str, str]:
+    """
+    Get the columns and dtypes ``read_data`` would return for a message type.
+
+    Dtypes are inferred from the values in the file, so they can differ
+    between files, e.g. when a device writes a field with a different base
+    type. The names match those accepted by the ``schema`` option. Takes the
+    same options as ``read_data``.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    message_type : str
+        Type of messages to inspect (e.g., 'record', 'session', 'lap').
+
+    Returns
+    -------
+    dict[str, str]
+        Dtype name per column, in column order.
+
+    Examples
+    --------
+    >>> polarsfit.get_schema("workout.fit", "record")
+    {'timestamp': 'datetime', 'heart_rate': 'u8', 'speed': 'f64', ...}
+    """
+    rust_mapping = _message_field_mapping(
+        message_type, field_mapping, options.get("apply_default_mapping", True)
+    )
+
+    schema = dict(
+        _get_schema(file_path, message_type, rust_mapping, **_rust_options(options))
+    )
+    # Converted temperatures are floats whatever their integer base type
+    for column in _temperature_columns(
+        message_type, rust_mapping, options.get("temperature_unit", "C")
+    ):
+        if schema.get(column, "").startswith(("i", "u", "f")):
+            schema[column] = "f64"
+    return schema
+
+
+def read_data(
+    file_path: str,
+    message_type: str | list[str],
+    field_mapping: dict[str, str] | None = None,
     *,
     apply_default_mapping: bool = True,
-) -> pl.DataFrame:
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    start_time: datetime | float | None = None,
+    end_time: datetime | float | None = None,
+    add_index: bool = False,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    categorical_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    progress: Callable[[int], object] | None = None,
+    truncated_ok: bool = True,
+    limit: int | None = None,
+    sort_by_time: bool = False,
+    dedup_time: bool = False,
+    dedup_keep: Literal["first", "last"] = "first",
+    all_float: bool = False,
+    keep_raw: bool = False,
+    encoding: Literal["utf-8", "latin-1"] = "utf-8",
+    temperature_unit: Literal["C", "F"] = "C",
+) -> pl.DataFrame | dict[str, pl.DataFrame]:
     """
     Read messages of a specific type from a FIT file.
 
@@ -123,24 +601,129 @@ def read_data(
     ----------
     file_path : str
         Path to the FIT file.
-    message_type : str
-        Type of messages to read (e.g., 'record', 'session', 'lap').
+    message_type : str | list[str]
+        Type of messages to read (e.g., 'record', 'session', 'lap'), or a
+        list of types to read in a single pass over the file.
     field_mapping : dict[str, str] | None, optional
         Custom mapping from field numbers to field names.
-        Format: {"field_123": "custom_name"}
+        Format: {"field_123": "custom_name"}. With a list of message types,
+        it applies to each of them.
     apply_default_mapping : bool, default True
-        Whether to apply the default FIT protocol field mapping for this message type.
+        Whether to name columns by their FIT profile field names, e.g.
+        ``heart_rate`` instead of ``field_3``. Applies to every message type
+        in the profile; fields missing from it keep their ``field_<n>``
+        name, and ``field_mapping`` takes precedence.
+    fields : list[str] | None, optional
+        Names of the columns to decode, after field mapping, e.g.
+        ``["timestamp", "position_lat", "position_long"]``. Other fields are
+        skipped while decoding, which saves time and memory on messages with
+        many fields. Names not found in the file are ignored. By default all
+        fields are decoded.
+    schema : dict[str, str] | None, optional
+        Dtypes to cast columns to after decoding, by final column name, e.g.
+        ``{"heart_rate": "f64"}``. Accepted dtypes are ``i8`` to ``i64``,
+        ``u8`` to ``u64``, ``f32``, ``f64``, ``bool``, ``str``, ``cat``
+        (Categorical), ``datetime`` (UTC, milliseconds) and ``list[...]`` of
+        any of these. Columns not
+        listed keep their inferred dtype; see ``get_schema``.
+    start_time, end_time : datetime | float | None, optional
+        Only keep messages timestamped within this range, inclusive, given
+        as datetimes (naive ones are taken as UTC) or Unix seconds. Messages
+        outside it are skipped while decoding, and messages without a
+        timestamp are skipped whenever a range is given.
+    add_index : bool, default False
+        Whether to add a leading ``message_index`` ``UInt32`` column numbering
+        the messages of this type in file order, from 0. Messages skipped by
+        a time range still take up their number.
+    decode_timestamps : bool, default True
+        Whether to return timestamp fields as UTC ``Datetime`` columns.
+        If False, the raw ``UInt32`` seconds since the FIT epoch
+        (1989-12-31 00:00:00 UTC) are kept.
+    apply_scale : bool, default True
+        Whether to apply the FIT profile scale and offset to numeric fields,
+        e.g. returning speed in m/s as ``Float64`` rather than raw mm/s.
+    convert_gps : bool, default True
+        Whether to convert the record ``position_lat`` and ``position_long``
+        fields from semicircles to degrees as ``Float64``. If False, their
+        raw ``Int32`` semicircles are kept. Positions of other message types,
+        such as the start and end positions of laps, are always converted.
+    decode_enums : bool, default True
+        Whether to return enum fields by their FIT profile names, e.g.
+        ``"cycling"`` for the session sport. Values without a name are kept
+        as numeric strings. If False, the raw integers are kept.
+    categorical_enums : bool, default True
+        Whether to return decoded enum columns as ``Categorical`` rather
+        than ``String``, which takes less memory and speeds up grouping on
+        columns such as ``sport`` or ``event``. Other string columns stay
+        ``String``.
+    verify_crc : bool, default False
+        Whether to check the header and file CRCs and raise ``ValueError``
+        if they don't match, e.g. for a corrupted download. By default the
+        CRCs are ignored and whatever can be decoded is returned.
+    mask_invalid : bool, default True
+        Whether to return values equal to the FIT "invalid" sentinel of their
+        base type (e.g. ``0xFFFF`` for ``uint16``) as null. If False, the
+        sentinels are kept as ordinary values.
+    progress : Callable[[int], object] | None, optional
+        Called with the number of messages decoded so far, every 10,000
+        messages and once more at the end, e.g. to update a progress bar.
+        Exceptions it raises abort the read.
+    truncated_ok : bool, default True
+        Whether to return the messages before the first malformed one, with
+        a ``UserWarning``, if decoding fails partway through the file, e.g.
+        for a recording cut off by a crashed device. If False, a
+        ``ValueError`` is raised instead.
+    limit : int | None, optional
+        Maximum number of messages to read, e.g. to preview a file. Decoding
+        stops once that many are read; with a list of message types, each
+        type is limited to that many.
+    sort_by_time : bool, default False
+        Whether to sort the messages by timestamp, for firmware that writes
+        them out of order. Messages without a timestamp go last.
+    dedup_time : bool, default False
+        Whether to drop messages repeating the timestamp of another one,
+        keeping the one chosen by ``dedup_keep``. Messages without a
+        timestamp are kept.
+    dedup_keep : {"first", "last"}, default "first"
+        Which message of a repeated timestamp ``dedup_time`` keeps, in file
+        order, or time order with ``sort_by_time``.
+    all_float : bool, default False
+        Whether to cast every numeric column to ``Float64``, e.g. for a
+        uniform feature matrix, with invalid values as null. String,
+        datetime and list columns, ``message_index`` and columns given in
+        ``schema`` keep their dtype.
+    keep_raw : bool, default False
+        Whether to also return the undecoded integer of every field that
+        scaling, semicircle conversion or enum decoding changes, in a
+        ``<name>_raw`` column after it, e.g. ``speed_raw`` in mm/s next to
+        ``speed`` in m/s, for debugging or re-encoding. Timestamps and fields
+        unpacked from components get none.
+    encoding : {"utf-8", "latin-1"}, default "utf-8"
+        Character encoding of string fields such as device names. Strings end
+        at their first null byte, which pads fixed-width fields. Bytes that
+        aren't valid UTF-8 are replaced by ``"\ufffd"``; use ``"latin-1"``
+        for older devices that write accented names in Latin-1.
+    temperature_unit : {"C", "F"}, default "C"
+        Unit of temperature fields such as the record ``temperature``.
+        ``"F"`` converts them from the profile's degrees Celsius to
+        Fahrenheit as ``Float64``, with the invalid value 127 as null.
 
     Returns
     -------
-    polars.DataFrame
-        DataFrame containing the specified message type with mapped field names.
+    polars.DataFrame | dict[str, polars.DataFrame]
+        DataFrame containing the specified message type with mapped field
+        names. For a list of message types, a DataFrame per type, keyed and
+        ordered as given; types missing from the file give empty frames.
 
     Examples
     --------
     >>> # Read session data with default mapping
     >>> sessions = polarsfit.read_data("workout.fit", "session")
     >>>
+    >>> # Read records and laps in one pass
+    >>> frames = polarsfit.read_data("workout.fit", ["record", "lap"])
+    >>> records, laps = frames["record"], frames["lap"]
+    >>>
     >>> # Read lap data with custom mapping
     >>> custom_mapping = {"field_123": "my_custom_field"}
     >>> laps = polarsfit.read_data("workout.fit", "lap", field_mapping=custom_mapping)
@@ -151,20 +734,2166 @@ def read_data(
     ...     data = polarsfit.read_data("workout.fit", msg_type)
     ...     print(f"{msg_type}: {data.shape}")
     """
+    options: ReadOptions = {
+        "apply_default_mapping": apply_default_mapping,
+        "fields": fields,
+        "schema": schema,
+        "start_time": start_time,
+        "end_time": end_time,
+        "add_index": add_index,
+        "decode_timestamps": decode_timestamps,
+        "apply_scale": apply_scale,
+        "convert_gps": convert_gps,
+        "decode_enums": decode_enums,
+        "categorical_enums": categorical_enums,
+        "verify_crc": verify_crc,
+        "mask_invalid": mask_invalid,
+        "progress": progress,
+        "truncated_ok": truncated_ok,
+        "limit": limit,
+        "sort_by_time": sort_by_time,
+        "dedup_time": dedup_time,
+        "dedup_keep": dedup_keep,
+        "all_float": all_float,
+        "keep_raw": keep_raw,
+        "encoding": encoding,
+        "temperature_unit": temperature_unit,
+    }
+    if not isinstance(message_type, str):
+        message_types = list(message_type)
+        mappings = {
+            name: mapping
+            for name in message_types
+            if (
+                mapping := _message_field_mapping(
+                    name, field_mapping, apply_default_mapping
+                )
+            )
+        }
+        frames = _read_data_by_type(
+            file_path, message_types, mappings, **_rust_options(options)
+        )
+        return {
+            name: _with_temperature_unit(
+                frames[name], name, mappings.get(name), temperature_unit
+            )
+            for name in message_types
+        }
+
+    return _read_messages(
+        partial(_read_data, file_path), message_type, field_mapping, options
+    )
+
+
+def read_data_from_bytes(
+    data: bytes,
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    **options: Unpack[ReadOptions],
+) -> pl.DataFrame:
+    """
+    Read messages of a specific type from the contents of a FIT file.
+
+    Takes the same options as ``read_data``.
+
+    Parameters
+    ----------
+    data : bytes
+        Raw bytes of the FIT file, e.g. from an HTTP response or a zip archive.
+        Any bytes-like object is accepted; gzip-compressed data is
+        decompressed automatically.
+    message_type : str
+        Type of messages to read (e.g., 'record', 'session', 'lap').
+
+    Returns
+    -------
+    polars.DataFrame
+        DataFrame containing the specified message type with mapped field names.
+
+    Examples
+    --------
+    >>> import zipfile
+    >>> with zipfile.ZipFile("export.zip") as archive:
+    ...     laps = polarsfit.read_data_from_bytes(archive.read("workout.fit"), "lap")
+    """
+    return _read_messages(
+        partial(_read_data_from_bytes, bytes(data)),
+        message_type,
+        field_mapping,
+        options,
+    )
+
+
+def read_data_from_fileobj(
+    fileobj: BinaryIO,
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    **options: Unpack[ReadOptions],
+) -> pl.DataFrame:
+    """
+    Read messages of a specific type from a binary file-like object.
+
+    The object is read to the end with a single ``read()`` call and the
+    contents are parsed as by ``read_data_from_bytes``, without holding the
+    GIL. Takes the same options as ``read_data``.
+
+    Parameters
+    ----------
+    fileobj : BinaryIO
+        Any object whose ``read()`` returns the bytes of a FIT file, e.g. an
+        ``io.BytesIO``, a tarfile member or a streamed HTTP response.
+    message_type : str
+        Type of messages to read (e.g., 'record', 'session', 'lap').
+
+    Returns
+    -------
+    polars.DataFrame
+        DataFrame containing the specified message type with mapped field names.
+
+    Examples
+    --------
+    >>> import tarfile
+    >>> with tarfile.open("export.tar.gz") as archive:
+    ...     member = archive.extractfile("workout.fit")
+    ...     records = polarsfit.read_data_from_fileobj(member, "record")
+    """
+    data = fileobj.read()
+    if not isinstance(data, bytes | bytearray | memoryview):
+        raise TypeError(
+            f"read() must return bytes, not {type(data).__name__}; "
+            "open the file in binary mode"
+        )
+    return read_data_from_bytes(data, message_type, field_mapping, **options)
+
+
+def read_data_arrow_ipc(
+    file_path: str,
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    apply_default_mapping: bool = True,
+) -> bytes:
+    """
+    Read messages of a specific type from a FIT file as Arrow IPC bytes.
+
+    The messages are decoded as ``read_data`` does and serialized in Rust to
+    an Arrow IPC stream, which any Arrow implementation can load without
+    Polars, e.g. ``pyarrow.ipc.open_stream`` or DuckDB.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    message_type : str
+        Type of messages to read (e.g., 'record', 'session', 'lap').
+    field_mapping : dict[str, str] | None, optional
+        Custom mapping from field numbers to field names.
+        Format: {"field_123": "custom_name"}
+    apply_default_mapping : bool, default True
+        Whether to name columns by their FIT profile field names, as in
+        ``read_data``.
+
+    Returns
+    -------
+    bytes
+        The Arrow IPC stream, one record batch per chunk of the DataFrame.
+
+    Examples
+    --------
+    >>> import pyarrow as pa
+    >>> stream = polarsfit.read_data_arrow_ipc("workout.fit", "record")
+    >>> table = pa.ipc.open_stream(stream).read_all()
+    """
+    rust_mapping = _message_field_mapping(
+        message_type, field_mapping, apply_default_mapping
+    )
+    return _read_data_arrow_ipc(file_path, message_type, rust_mapping)
+
+
+def read_data_with_units(
+    file_path: str,
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    apply_default_mapping: bool = True,
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+) -> tuple[pl.DataFrame, dict[str, str]]:
+    """
+    Read messages of a specific type along with the units of their columns.
+
+    Units come from the FIT profile and describe the scaled values, e.g.
+    ``"m/s"`` for speed. Positions are in ``"degrees"``, except record
+    positions read with ``convert_gps=False``. Columns without a unit, such as enums,
+    decoded timestamps and developer fields, are left out. Takes the same
+    options as ``read_data``.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    message_type : str
+        Type of messages to read (e.g., 'record', 'session', 'lap').
+
+    Returns
+    -------
+    tuple[polars.DataFrame, dict[str, str]]
+        The messages as returned by ``read_data`` and the unit of each column.
+
+    Examples
+    --------
+    >>> records, units = polarsfit.read_data_with_units("workout.fit", "record")
+    >>> ax.set_ylabel(f"heart_rate [{units['heart_rate']}]")
+    """
+    df = read_data(
+        file_path,
+        message_type,
+        field_mapping,
+        apply_default_mapping=apply_default_mapping,
+        fields=fields,
+        schema=schema,
+        decode_timestamps=decode_timestamps,
+        apply_scale=apply_scale,
+        convert_gps=convert_gps,
+        decode_enums=decode_enums,
+        verify_crc=verify_crc,
+        mask_invalid=mask_invalid,
+    )
+
+    try:
+        field_units = get_field_units(message_type)
+    except ValueError:
+        # No profile units available for this message type
+        field_units = {}
+
+    mapping = (
+        _message_field_mapping(message_type, field_mapping, apply_default_mapping)
+        or {}
+    )
+    units = {}
+    for number, unit in field_units.items():
+        column = mapping.get(f"field_{number}", f"field_{number}")
+        if column not in df.columns or isinstance(df.schema[column], pl.Datetime):
+            continue
+        if unit == "semicircles" and df.schema[column].is_float():
+            unit = "degrees"
+        units[column] = unit
+    return df, units
+
+
+def read_many_data(
+    file_paths: list[str],
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    on_error: Literal["raise", "return"] = "raise",
+    **options: Unpack[ReadOptions],
+) -> list[pl.DataFrame | Exception]:
+    """
+    Read messages of a specific type from many FIT files in parallel.
+
+    The files are parsed concurrently on a thread pool without holding the
+    GIL. Takes the same options as ``read_data``; ``progress`` is called with
+    the running count of each file as it is decoded.
+
+    Parameters
+    ----------
+    file_paths : list[str]
+        Paths to the FIT files.
+    message_type : str
+        Type of messages to read (e.g., 'record', 'session', 'lap').
+    on_error : {"raise", "return"}, default "raise"
+        What to do when a file can't be read. ``"raise"`` raises the error of
+        the first failing file; ``"return"`` puts the exception in that
+        file's position of the result so the rest of the batch is kept.
+
+    Returns
+    -------
+    list[polars.DataFrame | Exception]
+        One entry per input path, in input order.
+
+    Examples
+    --------
+    >>> from pathlib import Path
+    >>> paths = [str(p) for p in Path("activities").glob("*.fit")]
+    >>> results = polarsfit.read_many_data(paths, "session", on_error="return")
+    >>> sessions = [df for df in results if isinstance(df, pl.DataFrame)]
+    """
+    if on_error not in ("raise", "return"):
+        raise ValueError(f"on_error must be 'raise' or 'return', got {on_error!r}")
+
+    rust_mapping = _message_field_mapping(
+        message_type, field_mapping, options.get("apply_default_mapping", True)
+    )
+
+    results = _read_many_data(
+        list(file_paths), message_type, rust_mapping, **_rust_options(options)
+    )
+    temperature_unit = options.get("temperature_unit", "C")
+    results = [
+        result
+        if isinstance(result, Exception)
+        else _with_temperature_unit(
+            result, message_type, rust_mapping, temperature_unit
+        )
+        for result in results
+    ]
+
+    if on_error == "raise":
+        for path, result in zip(file_paths, results, strict=True):
+            if isinstance(result, Exception):
+                result.add_note(f"while reading {path}")
+                raise result
+    return results
+
+
+def read_many_concat(
+    file_paths: list[str],
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    source_column: str | None = "source",
+    **options: Unpack[ReadOptions],
+) -> pl.DataFrame:
+    """
+    Read messages of a specific type from many FIT files into one DataFrame.
+
+    The files are read in parallel as with ``read_many_data`` and stacked in
+    input order. Columns are the union of all files' columns, with nulls
+    where a file lacks a field, since devices and firmware versions emit
+    different field sets. Takes the same options as ``read_data``.
+
+    Parameters
+    ----------
+    file_paths : list[str]
+        Paths to the FIT files.
+    message_type : str
+        Type of messages to read (e.g., 'record', 'session', 'lap').
+    source_column : str | None, default "source"
+        Name of the leading column holding each row's file path, or None to
+        leave it out.
+
+    Returns
+    -------
+    polars.DataFrame
+        The concatenated messages of all files.
+
+    Examples
+    --------
+    >>> from pathlib import Path
+    >>> paths = sorted(str(p) for p in Path("activities").glob("*.fit"))
+    >>> sessions = polarsfit.read_many_concat(paths, "session")
+    >>> sessions.group_by("source").len()
+    """
+    frames = read_many_data(file_paths, message_type, field_mapping, **options)
+
+    if source_column is not None:
+        frames = [
+            df.select(pl.lit(path, dtype=pl.String).alias(source_column), pl.all())
+            for path, df in zip(file_paths, frames, strict=True)
+        ]
+    return concat_frames(frames)
+
+
+def read_directory(
+    dir_path: str,
+    pattern: str = "*.fit",
+    message_type: str = "record",
+    field_mapping: dict[str, str] | None = None,
+    *,
+    source_column: str | None = "source",
+    **options: Unpack[ReadOptions],
+) -> pl.DataFrame:
+    """
+    Read messages of a specific type from every FIT file in a directory.
+
+    The files matching ``pattern`` are read in parallel and stacked in path
+    order as with ``read_many_concat``. Matches that aren't FIT files, or
+    can't be decoded, are skipped with a ``UserWarning``. Takes the same
+    options as ``read_data``, with ``progress`` called as by
+    ``read_many_data``.
+
+    Parameters
+    ----------
+    dir_path : str
+        Path to the directory.
+    pattern : str, default "*.fit"
+        Glob pattern of the files to read, relative to ``dir_path``, e.g.
+        ``"**/*.fit"`` to include subdirectories or ``"*.fit*"`` to include
+        gzip-compressed files.
+    message_type : str, default "record"
+        Type of messages to read (e.g., 'record', 'session', 'lap').
+    source_column : str | None, default "source"
+        Name of the leading column holding each row's file path, or None to
+        leave it out.
+
+    Returns
+    -------
+    polars.DataFrame
+        The concatenated messages of all files; empty if none match.
+
+    Examples
+    --------
+    >>> sessions = polarsfit.read_directory("activities", message_type="session")
+    >>> sessions.group_by("source").len()
+    """
+    directory = Path(dir_path)
+    if not directory.is_dir():
+        raise FileNotFoundError(f"No such directory: {dir_path}")
+    paths = sorted(str(path) for path in directory.glob(pattern) if path.is_file())
+
+    results = read_many_data(
+        paths, message_type, field_mapping, on_error="return", **options
+    )
+
+    frames = []
+    for path, result in zip(paths, results, strict=True):
+        if isinstance(result, ValueError):
+            warnings.warn(f"Skipping {path}: {result}", UserWarning, stacklevel=2)
+            continue
+        if isinstance(result, Exception):
+            result.add_note(f"while reading {path}")
+            raise result
+        if source_column is not None:
+            result = result.select(
+                pl.lit(path, dtype=pl.String).alias(source_column), pl.all()
+            )
+        frames.append(result)
+    return concat_frames(frames)
+
+
+def concat_frames(
+    frames: list[pl.DataFrame], how: Literal["diagonal", "vertical"] = "diagonal"
+) -> pl.DataFrame:
+    """
+    Stack DataFrames read from different files, whose columns may differ.
+
+    Activities recorded by different devices or firmware versions have
+    different fields, and the same field can decode to an integer in one
+    file and a float in another. Columns are taken in the order they are
+    first seen, and each is cast to a dtype holding its values in every
+    frame: mixed integers widen, integers and floats become ``Float64`` and
+    anything mixed with strings becomes ``String``. Categorical columns
+    stay categorical.
+
+    Parameters
+    ----------
+    frames : list[polars.DataFrame]
+        Frames to stack, in order.
+    how : {"diagonal", "vertical"}, default "diagonal"
+        ``"diagonal"`` takes the union of the columns, with nulls where a
+        frame lacks one; ``"vertical"`` raises ``ValueError`` unless all
+        frames have the same columns.
+
+    Returns
+    -------
+    polars.DataFrame
+        The stacked rows; empty for an empty list.
+
+    Examples
+    --------
+    >>> frames = [polarsfit.read_recordmesgs(path) for path in paths]
+    >>> records = polarsfit.concat_frames(frames)
+    """
+    return _concat_frames(frames, how)
+
+
+def scan_data(
+    file_path: str,
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    batch_size: int = 65536,
+    **options: Unpack[ReadOptions],
+) -> pl.LazyFrame:
+    """
+    Lazily scan messages of a specific type from a FIT file.
+
+    The file is decoded in batches of ``batch_size`` messages when the query
+    is collected. Filters, column selections and row limits are applied to
+    each batch as it is produced, so the full DataFrame is never built in
+    memory. Resolving the schema takes one extra pass over the file. Takes
+    the same options as ``read_data``, but ``sort_by_time`` and
+    ``dedup_time`` work within each batch, and ``progress`` is called once
+    per batch.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    message_type : str
+        Type of messages to scan (e.g., 'record', 'session', 'lap').
+    batch_size : int, default 65536
+        Maximum number of messages decoded into each batch.
+
+    Returns
+    -------
+    polars.LazyFrame
+        LazyFrame over the specified message type with mapped field names.
+
+    Examples
+    --------
+    >>> hard_efforts = (
+    ...     polarsfit.scan_data("workout.fit", "record")
+    ...     .filter(pl.col("power") > 300)
+    ...     .select("timestamp", "power")
+    ...     .collect()
+    ... )
+    """
+    rust_mapping = _message_field_mapping(
+        message_type, field_mapping, options.get("apply_default_mapping", True)
+    )
+    rust_options = _rust_options(options)
+    temperature_unit = options.get("temperature_unit", "C")
+
+    def batches(**overrides: Any) -> Iterator[pl.DataFrame]:
+        reader = _MessageBatchReader(
+            file_path,
+            message_type,
+            rust_mapping,
+            batch_size,
+            **{**rust_options, **overrides},
+        )
+        for batch in reader:
+            yield _with_temperature_unit(
+                batch, message_type, rust_mapping, temperature_unit
+            )
+
+    resolved: dict[str, pl.DataType] = {}
+
+    def resolve_schema() -> dict[str, pl.DataType]:
+        # Batches are inferred independently; their union is the scan schema
+        if not resolved:
+            # The schema pass isn't part of the progress the caller sees
+            empty = [batch.clear() for batch in batches(progress=None)]
+            if empty:
+                resolved.update(pl.concat(empty, how="diagonal_relaxed").schema)
+        return resolved
+
+    def source(
+        with_columns: list[str] | None,
+        predicate: pl.Expr | None,
+        n_rows: int | None,
+        _batch_size: int | None,
+    ) -> Iterator[pl.DataFrame]:
+        columns = resolve_schema()
+        # Only decode the columns the query uses
+        overrides = {}
+        if with_columns is not None:
+            fields = list(with_columns)
+            if predicate is not None:
+                fields += predicate.meta.root_names()
+            overrides["fields"] = fields
+        for batch in batches(**overrides):
+            df = batch.select(
+                pl.col(name).cast(dtype)
+                if name in batch.columns
+                else pl.lit(None, dtype=dtype).alias(name)
+                for name, dtype in columns.items()
+            )
+            if predicate is not None:
+                df = df.filter(predicate)
+            if with_columns is not None:
+                df = df.select(with_columns)
+            if n_rows is not None:
+                df = df.head(n_rows)
+                n_rows -= df.height
+            yield df
+            if n_rows == 0:
+                break
+
+    return register_io_source(source, schema=resolve_schema)
+
+
+def scan_recordmesgs(
+    file_path: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    batch_size: int = 65536,
+    **options: Unpack[ReadOptions],
+) -> pl.LazyFrame:
+    """
+    Lazily scan record messages from a FIT file.
+
+    Shorthand for ``scan_data(file_path, "record", ...)``.
+
+    Examples
+    --------
+    >>> lf = polarsfit.scan_recordmesgs("workout.fit")
+    >>> lf.select(pl.col("heart_rate").mean()).collect()
+    """
+    return scan_data(
+        file_path, "record", field_mapping, batch_size=batch_size, **options
+    )
+
+
+class RecordBatchReader:
+    """
+    Iterate over the record messages of a FIT file in DataFrame chunks.
+
+    Each chunk holds at most ``chunk_size`` records. The file is read up front
+    but records are only decoded as chunks are requested, which keeps memory
+    flat and lets a loop stop early. Takes the options of
+    ``read_recordmesgs`` and applies them to each chunk, except that:
+
+    - ``fill_distance`` isn't supported, since the distance sums over the
+      whole track;
+    - ``sort_by_time`` and ``dedup_time`` work within each chunk;
+    - columns and dtypes are inferred per chunk, so pass ``schema`` to pin
+      the dtypes;
+    - ``limit`` caps the records of all chunks together;
+    - ``progress`` is called with the number of messages decoded so far once
+      per chunk.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    chunk_size : int, default 65536
+        Maximum number of records in each chunk.
+    field_mapping : dict[str, str] | None, optional
+        Custom mapping from field numbers to field names, as for
+        ``read_recordmesgs``.
+
+    Examples
+    --------
+    >>> for chunk in polarsfit.RecordBatchReader("workout.fit", chunk_size=1000):
+    ...     print(chunk["power"].mean())
+    """
+
+    def __init__(
+        self,
+        file_path: str,
+        chunk_size: int = 65536,
+        field_mapping: dict[str, str] | None = None,
+        **options: Unpack[RecordOptions],
+    ):
+        if options.get("fill_distance", False):
+            raise ValueError(
+                "fill_distance needs the whole track; use read_recordmesgs"
+            )
+        self._mapping = _message_field_mapping(
+            "record", field_mapping, options.get("apply_default_mapping", True)
+        )
+        self._options = options
+        self._decode = partial(_read_data, file_path)
+        # Other message types are read once, for all chunks
+        self._other: dict[tuple, pl.DataFrame] = {}
+        self._chunks = _RecordBatchReader(
+            file_path,
+            self._mapping,
+            chunk_size,
+            **_record_rust_options(options, self._mapping),
+        )
+
+    def __iter__(self) -> "RecordBatchReader":
+        return self
+
+    def __next__(self) -> pl.DataFrame:
+        return _process_records(
+            next(self._chunks), self._mapping, self._options, self._read_other
+        )
+
+    def _read_other(self, message_type: str, **options: Any) -> pl.DataFrame:
+        key = (message_type, *sorted(options.items()))
+        if key not in self._other:
+            self._other[key] = _read_messages(self._decode, message_type, None, options)
+        return self._other[key]
+
+
+def read_all_messages(
+    file_path: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    apply_default_mapping: bool = True,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    categorical_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    progress: Callable[[int], object] | None = None,
+    truncated_ok: bool = True,
+    limit: int | None = None,
+    sort_by_time: bool = False,
+    dedup_time: bool = False,
+    dedup_keep: Literal["first", "last"] = "first",
+    all_float: bool = False,
+    keep_raw: bool = False,
+    encoding: Literal["utf-8", "latin-1"] = "utf-8",
+    temperature_unit: Literal["C", "F"] = "C",
+) -> dict[str, pl.DataFrame]:
+    """
+    Read every message type from a FIT file in a single pass.
+
+    Takes the same options as ``read_data``; ``field_mapping`` is applied to
+    every message type.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    dict[str, polars.DataFrame]
+        DataFrame per message type, keyed by the names returned by
+        ``get_message_types``.
+
+    Examples
+    --------
+    >>> messages = polarsfit.read_all_messages("workout.fit")
+    >>> records, laps = messages["record"], messages["lap"]
+    """
+    frames = _read_all_messages(
+        file_path,
+        decode_timestamps=decode_timestamps,
+        apply_scale=apply_scale,
+        convert_gps=convert_gps,
+        decode_enums=decode_enums,
+        categorical_enums=categorical_enums,
+        verify_crc=verify_crc,
+        mask_invalid=mask_invalid,
+        progress=progress,
+        truncated_ok=truncated_ok,
+        limit=limit,
+        sort_by_time=sort_by_time,
+        dedup_time=dedup_time,
+        dedup_keep=dedup_keep,
+        all_float=all_float,
+        keep_raw=keep_raw,
+        encoding=encoding,
+    )
+
+    for message_type, df in frames.items():
+        mapping = _message_field_mapping(
+            message_type, field_mapping, apply_default_mapping
+        )
+        if mapping:
+            # The raw columns of keep_raw follow the names of their decoded ones
+            renames = {
+                col + suffix: name + suffix
+                for col, name in mapping.items()
+                for suffix in ("", "_raw")
+            }
+            df = df.rename(
+                {col: name for col, name in renames.items() if col in df.columns}
+            )
+        frames[message_type] = _with_temperature_unit(
+            df, message_type, mapping, temperature_unit
+        )
+    return frames
+
+
+def read_session_summary(file_path: str) -> pl.DataFrame:
+    """
+    Read a tidy summary of each session in a FIT file.
+
+    Returns one row per session (several for multisport activities) with
+    stable column names, whichever fields the device recorded. Fields the
+    file doesn't contain are null.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    polars.DataFrame
+        Columns ``sport``, ``sub_sport``, ``start_time``, ``end_time``,
+        ``total_elapsed_time`` and ``total_timer_time`` (s),
+        ``total_distance`` (m), ``total_calories`` (kcal), ``avg_speed`` and
+        ``max_speed`` (m/s), ``avg_pace`` (s/km), ``avg_heart_rate`` and
+        ``max_heart_rate`` (bpm), ``avg_power`` and ``max_power`` (W),
+        ``total_ascent`` and ``total_descent`` (m), and the
+        ``pool_length`` (m) of pool swims.
+
+    Examples
+    --------
+    >>> summary = polarsfit.read_session_summary("workout.fit")
+    >>> summary.select("sport", "total_distance", "avg_heart_rate")
+    """
+    sessions = read_data(file_path, "session", apply_default_mapping=False)
+    return _summarize_sessions(sessions)
+
+
+def read_hrv(file_path: str) -> pl.DataFrame:
+    """
+    Read the R-R intervals of a FIT file for heart rate variability analysis.
+
+    ``hrv`` messages hold up to five beat-to-beat intervals each, padded with
+    the invalid value. The intervals of all messages are concatenated in
+    file order, without the padding.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    polars.DataFrame
+        A single Float64 ``rr_interval`` column with one row per heartbeat,
+        in seconds. Empty if the file has no ``hrv`` messages, which devices
+        only record with HRV logging enabled.
+
+    Examples
+    --------
+    >>> rr = polarsfit.read_hrv("workout.fit")["rr_interval"]
+    >>> rmssd = (rr.diff() ** 2).mean() ** 0.5
+    """
+    hrv = read_data(file_path, "hrv", apply_default_mapping=False)
+    if "field_0" not in hrv.columns:
+        return pl.DataFrame(schema={"rr_interval": pl.Float64})
+    intervals = hrv["field_0"]
+    # Messages holding a single interval are decoded as scalars
+    if isinstance(intervals.dtype, pl.List):
+        intervals = intervals.explode()
+    return intervals.drop_nulls().cast(pl.Float64).alias("rr_interval").to_frame()
+
+
+def read_timer_events(file_path: str) -> pl.DataFrame:
+    """
+    Read the segments of an activity during which its timer was running.
+
+    Devices write a ``timer`` event with type ``start`` when recording
+    starts or resumes, and a stop type when it pauses, manually or on
+    auto-pause, or ends. Each start is paired with the stop after it. Unlike
+    ``detect_stops``, this needs no speed threshold: it is when the device
+    itself counted time.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    polars.DataFrame
+        One row per segment in time order: ``start_time`` and ``end_time``
+        as UTC datetimes and ``duration`` in seconds. The end is null if the
+        file ends while the timer runs. Empty if the file has no timer
+        events.
+
+    Examples
+    --------
+    >>> segments = polarsfit.read_timer_events("ride.fit")
+    >>> segments["duration"].sum()  # the session's total_timer_time
+    """
+    events = read_data(file_path, "event", apply_default_mapping=False)
+    return _timer_segments(events)
+
+
+def read_lengths(file_path: str) -> pl.DataFrame:
+    """
+    Read the pool lengths of a swim with stable column names.
+
+    Pool swims record a ``length`` message per length of the pool, with its
+    stroke, stroke count and timing, instead of positions in records.
+    Lengths spent resting at the wall are ``idle`` and have no stroke. The
+    pool length itself is in the ``pool_length`` column of
+    ``read_session_summary``.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    polars.DataFrame
+        One row per length with columns ``start_time``,
+        ``total_elapsed_time`` and ``total_timer_time`` (s), ``length_type``
+        (``"active"`` or ``"idle"``), ``swim_stroke`` (e.g. ``"freestyle"``
+        or ``"breaststroke"``), ``total_strokes``, ``avg_speed`` (m/s),
+        ``avg_swimming_cadence`` (strokes/min) and ``end_time``. Empty if
+        the file has no ``length`` messages.
+
+    Examples
+    --------
+    >>> lengths = polarsfit.read_lengths("pool_swim.fit")
+    >>> lengths.filter(pl.col("length_type") == "active").group_by(
+    ...     "swim_stroke"
+    ... ).agg(pl.len(), pl.col("total_strokes").mean())
+    """
+    lengths = read_data(file_path, "length", apply_default_mapping=False)
+    return _summarize_lengths(lengths)
+
+
+def read_course_points(file_path: str) -> pl.DataFrame:
+    """
+    Read the cues of a course file with stable column names.
+
+    Courses are planned routes for navigation on the device: the ``record``
+    messages trace the route, and a ``course_point`` message marks each cue
+    along it, such as a turn or a climb's summit.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    polars.DataFrame
+        One row per course point, in file order, with columns
+        ``timestamp``, ``name``, ``type`` (e.g. ``"left"``, ``"right"`` or
+        ``"summit"``), ``position_lat`` and ``position_long`` (degrees) and
+        ``distance`` (m along the route). Empty if the file has no
+        ``course_point`` messages.
+
+    Examples
+    --------
+    >>> cues = polarsfit.read_course_points("route.fit")
+    >>> cues.filter(pl.col("type").is_in(["left", "right"]))
+    """
+    points = read_data(file_path, "course_point", apply_default_mapping=False)
+    return _summarize_course_points(points)
+
+
+def read_workout_steps(file_path: str) -> pl.DataFrame:
+    """
+    Read the steps of a structured workout with stable column names.
+
+    Workout files, as exported by training apps and coaches, hold a
+    ``workout_step`` message per step of the plan: how long it lasts and at
+    what heart rate, power, speed or cadence it is to be done.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    polars.DataFrame
+        One row per step, in file order, with columns ``message_index``,
+        ``name``, ``duration_type`` (e.g. ``"time"``, ``"distance"`` or
+        ``"open"``), ``duration_value`` (s for time and m for distance
+        steps, else as stored, e.g. the step to repeat from),
+        ``target_type`` (e.g. ``"heart_rate"`` or ``"power"``),
+        ``target_value`` (the zone, or 0 for a custom range),
+        ``custom_target_value_low``, ``custom_target_value_high`` and
+        ``intensity`` (e.g. ``"warmup"`` or ``"active"``). Empty if the file
+        has no ``workout_step`` messages.
+
+    Examples
+    --------
+    >>> steps = polarsfit.read_workout_steps("intervals.fit")
+    >>> steps.filter(pl.col("target_type") == "power")
+    """
+    steps = read_data(file_path, "workout_step", apply_default_mapping=False)
+    return _summarize_workout_steps(steps)
+
+
+def resample_records(
+    file_path: str, interval_seconds: float, agg: Aggregation = "mean"
+) -> pl.DataFrame:
+    """
+    Read record messages resampled to a fixed time interval.
+
+    Records are binned into windows of ``interval_seconds`` aligned to the
+    epoch, which makes activities recorded at different rates comparable and
+    shrinks high-rate files. Numeric columns are aggregated with ``agg``;
+    cumulative columns such as ``distance`` and non-numeric columns keep the
+    last value in each window. Windows without records, e.g. during a pause,
+    are rows of nulls so the grid is regular.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    interval_seconds : float
+        Length of each window in seconds.
+    agg : {"mean", "median", "min", "max", "first", "last"}, default "mean"
+        Aggregation of the numeric columns within each window.
+
+    Returns
+    -------
+    polars.DataFrame
+        One row per window, labelled by the window's start ``timestamp``.
+
+    Examples
+    --------
+    >>> every_5s = polarsfit.resample_records("workout.fit", 5)
+    >>> every_minute = polarsfit.resample_records("workout.fit", 60, agg="max")
+    """
+    return _resample(read_recordmesgs(file_path), interval_seconds, agg)
+
+
+def read_records_with_laps(
+    file_path: str, lap_fields: list[str] | None = None
+) -> pl.DataFrame:
+    """
+    Read record messages annotated with the lap they were recorded in.
+
+    Laps are numbered from 1 in order of their ``start_time`` and last for
+    their ``total_elapsed_time``. A record belongs to the lap whose time range
+    contains its timestamp. Records in a gap between laps, or after the last
+    lap, belong to the lap nearest in time; records before the first lap get
+    lap 0.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    lap_fields : list of str, optional
+        Lap message fields by their profile name, e.g. ``"avg_power"``,
+        ``"avg_heart_rate"`` or ``"total_distance"``, to copy onto every
+        record of the lap as ``lap_<name>``, to compare each record with its
+        lap without a join.
+
+    Returns
+    -------
+    polars.DataFrame
+        The records as returned by ``read_recordmesgs`` plus a ``UInt32``
+        ``lap`` column, null for records without a timestamp, and a column
+        per lap field, null for records in lap 0 and for fields the file's
+        laps don't have.
+
+    Raises
+    ------
+    ValueError
+        If a lap field isn't in the lap message of the FIT profile.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_records_with_laps("intervals.fit")
+    >>> records.group_by("lap").agg(pl.col("power").mean()).sort("lap")
+    >>> records = polarsfit.read_records_with_laps(
+    ...     "intervals.fit", lap_fields=["avg_power"]
+    ... )
+    >>> records.with_columns(above_lap=pl.col("power") - pl.col("lap_avg_power"))
+    """
+    numbers = {name: number for number, name in get_field_mapping("lap").items()}
+    unknown = [name for name in lap_fields or [] if name not in numbers]
+    if unknown:
+        raise ValueError(f"unknown lap fields: {', '.join(unknown)}")
+    records = read_recordmesgs(file_path)
+    laps = read_data(file_path, "lap", apply_default_mapping=False)
+    fields = {name: numbers[name] for name in lap_fields or []}
+    return _assign_laps(records, laps, fields)
+
+
+def split_by_session(file_path: str) -> list[tuple[str, pl.DataFrame]]:
+    """
+    Read record messages split by the session they were recorded in.
+
+    Multisport files such as triathlons hold a ``session`` message per leg,
+    transitions included, each with its own sport. A session lasts from its
+    ``start_time`` until the next session starts; records before the first
+    session belong to it. Records without a timestamp are left out.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    list of (str, polars.DataFrame)
+        One ``(sport, records)`` pair per session in order of start time,
+        e.g. ``("swimming", ...)``, ``("transition", ...)``. The records are
+        as returned by ``read_recordmesgs``; a session without a sport is
+        ``"generic"``. Empty if the file has no sessions with a start time.
+
+    Examples
+    --------
+    >>> for sport, records in polarsfit.split_by_session("triathlon.fit"):
+    ...     print(sport, records["distance"].max())
+    """
+    records = read_recordmesgs(file_path)
+    sessions = read_data(file_path, "session", apply_default_mapping=False)
+    return _split_by_session(records, sessions)
+
+
+def power_curve(
+    file_path: str, durations: list[int] | None = None
+) -> pl.DataFrame:
+    """
+    Compute the mean-maximal power curve of a FIT file.
+
+    For each duration, the curve holds the highest average power sustained
+    for that many consecutive seconds, the basis of critical power and FTP
+    estimates. Power is resampled to one value per second first, so files
+    recorded at other rates compare; seconds without a record keep the
+    power before them for up to 10 seconds, as with smart recording, and
+    longer gaps count as 0 W.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    durations : list[int] | None, optional
+        Durations in seconds to compute the best average power for. By
+        default 1 and 5 s, 15 and 30 s, 1, 5, 10 and 20 min and an hour.
+
+    Returns
+    -------
+    polars.DataFrame
+        A ``UInt32`` ``duration`` and a ``Float64`` ``power`` column in W,
+        one row per duration in the order given. ``power`` is null for
+        durations longer than the activity and for files without power.
+
+    Examples
+    --------
+    >>> curve = polarsfit.power_curve("ride.fit")
+    >>> ftp_estimate = 0.95 * curve.filter(pl.col("duration") == 1200)["power"]
+    """
+    if durations is None:
+        durations = POWER_CURVE_DURATIONS
+    return _power_curve(read_recordmesgs(file_path), durations)
+
+
+def time_in_zones(
+    file_path: str,
+    channel: str = "heart_rate",
+    *,
+    boundaries: list[float],
+    max_gap: float = 10.0,
+) -> pl.DataFrame:
+    """
+    Compute the time spent in each zone of a record channel.
+
+    Zones are numbered as by ``hr_zone``: ``boundaries`` are the ascending
+    lower bounds of zones 1 to N, values below the first are in zone 0.
+    Each record counts for the time until the next one, so files recorded
+    at any rate, or only on changes, are weighted correctly. Intervals
+    longer than ``max_gap``, e.g. while the device was paused, don't count
+    towards any zone, nor do records where the channel is null.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    channel : str, default "heart_rate"
+        Record column to bucket, e.g. ``"heart_rate"`` or ``"power"``.
+    boundaries : list[float]
+        Strictly ascending lower bounds of the zones.
+    max_gap : float, default 10.0
+        Longest interval in seconds between records that is counted.
+
+    Returns
+    -------
+    polars.DataFrame
+        A ``UInt8`` ``zone`` and a ``Float64`` ``seconds`` column with one
+        row per zone, from 0 to ``len(boundaries)``, including empty ones.
+
+    Examples
+    --------
+    >>> zones = polarsfit.time_in_zones(
+    ...     "workout.fit", boundaries=[110, 130, 150, 165, 180]
+    ... )
+    >>> power_zones = polarsfit.time_in_zones(
+    ...     "ride.fit", "power", boundaries=[140, 190, 230, 270, 310]
+    ... )
+    """
+    return _time_in_zones(read_recordmesgs(file_path), channel, boundaries, max_gap)
+
+
+def detect_stops(
+    file_path: str, min_stop_seconds: float = 10.0, min_speed: float = 0.5
+) -> pl.DataFrame:
+    """
+    Find the stops and pauses of an activity.
+
+    A stop is a stretch where the speed stayed below ``min_speed``, or where
+    the device paused and no records were written for more than 10 seconds.
+    Stops less than 5 seconds of moving apart, e.g. rolling forward at a
+    red light, are merged into one. Use them to split a ride at its coffee
+    stops or to tell moving from elapsed time.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    min_stop_seconds : float, default 10.0
+        Shortest stop to report, in seconds.
+    min_speed : float, default 0.5
+        Speed in m/s below which the athlete is stopped, as in ``is_moving``.
+
+    Returns
+    -------
+    polars.DataFrame
+        One row per stop in time order: ``start_time`` and ``end_time`` as
+        UTC datetimes, from the first stopped record to the record moving
+        again, and ``duration`` in seconds. Empty for files without speed.
+
+    Examples
+    --------
+    >>> stops = polarsfit.detect_stops("ride.fit", min_stop_seconds=120)
+    >>> stops["duration"].sum() / 60  # minutes stopped
+    """
+    return _detect_stops(read_recordmesgs(file_path), min_stop_seconds, min_speed)
+
+
+def best_efforts(
+    file_path: str, distances_m: list[float] | None = None
+) -> pl.DataFrame:
+    """
+    Find the fastest time over each distance anywhere in an activity.
+
+    The running counterpart of ``power_curve``: for each distance, a window
+    slides along the cumulative ``distance`` of the records to find the
+    shortest time between two records at least that far apart.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    distances_m : list[float] | None, optional
+        Distances in meters, by default 1 km, 5 km and 10 km.
+
+    Returns
+    -------
+    polars.DataFrame
+        One row per distance in the order given: ``distance`` in m, the
+        ``UInt32`` ``start_index`` of the record the effort starts at, as
+        returned by ``read_recordmesgs``, ``elapsed_time`` in s and
+        ``pace`` in s/km. All but ``distance`` are null for distances longer
+        than the activity and for files without distance.
+
+    Examples
+    --------
+    >>> efforts = polarsfit.best_efforts("run.fit", [400, 1609.344, 5000])
+    >>> efforts.select("distance", "elapsed_time")
+    """
+    if distances_m is None:
+        distances_m = BEST_EFFORT_DISTANCES
+    return _best_efforts(read_recordmesgs(file_path), distances_m)
+
+
+def decoupling(
+    file_path: str,
+    channel: Literal["power", "speed"] = "power",
+    *,
+    warmup_seconds: float = 0.0,
+    cooldown_seconds: float = 0.0,
+) -> float:
+    """
+    Compute the aerobic decoupling of an activity, in percent.
+
+    Decoupling (Pw:Hr for power, Pa:Hr for speed) compares the efficiency
+    ratio, mean ``channel`` over mean heart rate, of the first and second
+    half of the activity by time. A steady endurance effort whose heart rate
+    drifts up for the same output decouples; under 5% is commonly taken as
+    good aerobic durability. Trim the warm-up and cool-down so they don't
+    weigh on either half.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    channel : {"power", "speed"}, default "power"
+        Record column the heart rate is compared against.
+    warmup_seconds, cooldown_seconds : float, default 0.0
+        Seconds left out at the start and end of the activity.
+
+    Returns
+    -------
+    float
+        Drop of the efficiency ratio from the first half to the second, in
+        percent of the first; positive when heart rate drifted up.
+
+    Raises
+    ------
+    ValueError
+        If the file has no records with both heart rate and ``channel``, or
+        the trimming leaves no time to compare.
+
+    Examples
+    --------
+    >>> polarsfit.decoupling("long_ride.fit", warmup_seconds=600)
+    3.2
+    >>> polarsfit.decoupling("long_run.fit", "speed")
+    """
+    return _decoupling(
+        read_recordmesgs(file_path), channel, warmup_seconds, cooldown_seconds
+    )
+
+
+def estimate_calories(
+    file_path: str,
+    efficiency: float = CYCLING_EFFICIENCY,
+    *,
+    weight: float | None = None,
+    age: float | None = None,
+    sex: Literal["male", "female"] | None = None,
+) -> float:
+    """
+    Estimate the kilocalories burnt in an activity, for files without them.
+
+    With power, the mechanical work in kJ is divided by the gross
+    efficiency of the muscles, around 20-25% for cycling, so one kJ of work
+    is roughly one kcal burnt. Without power, the heart rate equation of
+    Keytel et al. (2005) is used if ``weight``, ``age`` and ``sex`` are
+    given.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    efficiency : float, default 0.23
+        Fraction of the energy burnt that ends up as work on the pedals.
+    weight : float | None, optional
+        Body weight in kg, for the heart rate estimate.
+    age : float | None, optional
+        Age in years, for the heart rate estimate.
+    sex : {"male", "female"} | None, optional
+        Sex, for the heart rate estimate.
+
+    Returns
+    -------
+    float
+        Estimated energy burnt in kcal.
+
+    Raises
+    ------
+    ValueError
+        If the file has no power and no heart rate, or no power and the
+        heart rate estimate lacks ``weight``, ``age`` or ``sex``.
+
+    Examples
+    --------
+    >>> polarsfit.estimate_calories("ride.fit")
+    748.2
+    >>> polarsfit.estimate_calories("run.fit", weight=70, age=35, sex="female")
+    """
+    return _estimate_calories(
+        read_recordmesgs(file_path), efficiency, weight, age, sex
+    )
+
+
+def ascent_descent(
+    file_path: str, threshold: float = ASCENT_THRESHOLD
+) -> tuple[float, float]:
+    """
+    Total ascent and descent of an activity, counted as bike computers do.
+
+    Summing every rise of the altitude counts its noise as climbing too.
+    Devices instead only count a change once the altitude has moved at least
+    ``threshold`` meters from where it was last counted, so the totals
+    roughly match the session's ``total_ascent`` and ``total_descent``.
+    ``enhanced_altitude`` is preferred where recorded.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    threshold : float, default 3.0
+        Smallest change of altitude in meters that is counted.
+
+    Returns
+    -------
+    tuple of (float, float)
+        Total ascent and total descent in meters, 0 without altitudes.
+
+    Raises
+    ------
+    ValueError
+        If ``threshold`` is negative.
+
+    Examples
+    --------
+    >>> ascent, descent = polarsfit.ascent_descent("ride.fit")
+    >>> polarsfit.ascent_descent("ride.fit", threshold=5.0)
+    """
+    return _ascent_descent(read_recordmesgs(file_path), threshold)
+
+
+def write_records_to_fit(
+    df: pl.DataFrame,
+    file_path: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    apply_default_mapping: bool = True,
+) -> None:
+    """
+    Write record messages to a new FIT file.
+
+    The file holds a ``file_id`` message marking it as an activity, followed
+    by one record message per row, and has valid header and file CRCs. Values
+    are encoded back to the raw FIT representation: datetimes to FIT
+    timestamps, degrees to semicircles, scaled values to their stored
+    integers and enum names to their numbers. Nulls are written as the FIT
+    "invalid" value, so they read back as null.
+
+    Parameters
+    ----------
+    df : polars.DataFrame
+        Record messages as returned by ``read_recordmesgs``, with or without
+        decoding applied. Columns that aren't record fields, such as
+        developer fields, ``message_index`` or derived columns, are not
+        written.
+    file_path : str
+        Path of the FIT file to write. An existing file is overwritten.
+    field_mapping : dict[str, str] | None, optional
+        Custom mapping from field numbers to the column names of ``df``, as
+        passed to ``read_recordmesgs``. Format: {"field_123": "custom_name"}
+    apply_default_mapping : bool, default True
+        Whether columns are named by their FIT profile field names.
+        Columns named ``field_<n>`` are always written as field ``n``.
+
+    Examples
+    --------
+    >>> # Trim the first minute of a 1 Hz recording
+    >>> records = polarsfit.read_recordmesgs("workout.fit")
+    >>> polarsfit.write_records_to_fit(records.slice(60), "trimmed.fit")
+    """
+    mapping = _message_field_mapping("record", field_mapping, apply_default_mapping)
+    # Rust identifies fields by their raw field_<n> names
+    fields = {name: field for field, name in (mapping or {}).items()}
+    raw = df.select(
+        pl.col(column).alias(fields.get(column, column))
+        for column in df.columns
+        if fields.get(column, column).startswith("field_")
+    )
+    _write_records_to_fit(raw, file_path)
+
+
+def write_data_to_parquet(
+    file_path: str,
+    message_type: str,
+    out_path: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    apply_default_mapping: bool = True,
+    compression: Literal["snappy", "zstd"] = "snappy",
+) -> None:
+    """
+    Convert messages of a specific type from a FIT file to a Parquet file.
+
+    The messages are decoded as ``read_data`` does and written by the Polars
+    Parquet writer without passing through Python, so converting a folder
+    of activities doesn't hold the GIL or copy each DataFrame.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    message_type : str
+        Type of messages to write (e.g., 'record', 'session', 'lap').
+    out_path : str
+        Path of the Parquet file to write. An existing file is overwritten.
+    field_mapping : dict[str, str] | None, optional
+        Custom mapping from field numbers to field names.
+        Format: {"field_123": "custom_name"}
+    apply_default_mapping : bool, default True
+        Whether to name columns by their FIT profile field names, as in
+        ``read_data``.
+    compression : {"snappy", "zstd"}, default "snappy"
+        Compression codec of the Parquet file. ``zstd`` gives smaller files,
+        ``snappy`` is faster to write and read.
+
+    Examples
+    --------
+    >>> polarsfit.write_data_to_parquet("workout.fit", "record", "records.parquet")
+    >>> records = pl.read_parquet("records.parquet")
+    """
+    rust_mapping = _message_field_mapping(
+        message_type, field_mapping, apply_default_mapping
+    )
+    _write_data_to_parquet(
+        file_path, message_type, out_path, rust_mapping, compression
+    )
+
+
+def records_to_gpx(file_path: str, out_path: str) -> None:
+    """
+    Convert the record messages of a FIT file to a GPX track.
+
+    Writes a GPX 1.1 file with one track segment holding a trackpoint per
+    record with a GPS position, with its altitude and time. Heart rate and
+    cadence are added as Garmin ``gpxtpx:TrackPointExtension`` elements and
+    power as a ``power`` extension, when the records have them. Records
+    without a position, e.g. indoors or before a GPS fix, are skipped.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    out_path : str
+        Path of the GPX file to write. An existing file is overwritten.
+
+    Examples
+    --------
+    >>> polarsfit.records_to_gpx("workout.fit", "workout.gpx")
+    """
+    tree = _to_gpx(read_recordmesgs(file_path))
+    ET.indent(tree)
+    tree.write(out_path, encoding="UTF-8", xml_declaration=True)
+
+
+def activity_to_tcx(file_path: str, out_path: str) -> None:
+    """
+    Convert the laps and records of a FIT file to a TCX activity.
+
+    Writes a Garmin Training Center (TCX v2) file with one ``Activity``
+    whose ``Sport`` comes from the session sport: ``Running``, ``Biking``
+    or ``Other``. Each lap message becomes a ``Lap`` with its time,
+    distance, calories, heart rate, cadence, intensity and trigger,
+    wrapping a ``Track`` of the records assigned to it as by
+    ``read_records_with_laps``. Trackpoints carry time, position, altitude,
+    distance, heart rate and cadence where recorded. A file without lap
+    messages is written as a single lap over all records.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    out_path : str
+        Path of the TCX file to write. An existing file is overwritten.
+
+    Examples
+    --------
+    >>> polarsfit.activity_to_tcx("workout.fit", "workout.tcx")
+    """
+    records = read_recordmesgs(file_path)
+    laps = read_data(file_path, "lap", apply_default_mapping=False)
+
+    tree = _to_tcx(_assign_laps(records, laps), laps, _session_sport(file_path))
+    ET.indent(tree)
+    tree.write(out_path, encoding="UTF-8", xml_declaration=True)
+
+
+def records_to_geojson(file_path: str, out_path: str) -> None:
+    """
+    Convert the record messages of a FIT file to a GeoJSON track.
+
+    Writes a GeoJSON ``Feature`` whose ``LineString`` geometry holds the
+    positions of the records in ``[lon, lat]`` degrees, as web maps such as
+    Leaflet and Mapbox load directly. Records without a position are
+    skipped. Its properties are the session ``sport``, the
+    ``total_distance`` in meters of the last record and the ``start_time``
+    and ``end_time`` of the records, null where the file doesn't have them.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+    out_path : str
+        Path of the GeoJSON file to write. An existing file is overwritten.
+
+    Examples
+    --------
+    >>> polarsfit.records_to_geojson("workout.fit", "workout.geojson")
+    """
+    feature = _to_geojson(read_recordmesgs(file_path), _session_sport(file_path))
+    with open(out_path, "w", encoding="utf-8") as out:
+        json.dump(feature, out)
+
+
+def _session_sport(file_path: str) -> str | None:
+    """The sport of the first session naming one, if any."""
+    sessions = read_data(file_path, "session", apply_default_mapping=False)
+    sports = (
+        sessions["field_5"].drop_nulls() if "field_5" in sessions.columns else []
+    )
+    return sports[0] if len(sports) else None
+
+
+def read_file_metadata(file_path: str) -> dict[str, Any]:
+    """
+    Read the identification metadata of a FIT file.
+
+    Combines the ``file_id`` message, which identifies the file and the
+    device that created it, with the ``device_info`` messages for every
+    device and sensor involved.
+
+    Parameters
+    ----------
+    file_path : str
+        Path to the FIT file.
+
+    Returns
+    -------
+    dict[str, Any]
+        ``file_type``, ``manufacturer``, ``product``, ``product_name``,
+        ``serial_number``, ``time_created`` and ``software_version`` of the
+        creating device, plus ``devices``: a DataFrame with one row per
+        distinct device. Manufacturers are decoded to names such as
+        ``"garmin"``, falling back to ``"manufacturer_<id>"``; product
+        names are filled in from the FIT profile where it lists them.
+
+    Examples
+    --------
+    >>> meta = polarsfit.read_file_metadata("workout.fit")
+    >>> meta["manufacturer"], meta["product_name"]
+    ('garmin', 'edge1000')
+    """
+    frames = read_all_messages(file_path, apply_default_mapping=False)
+    return _file_metadata(frames.get("fileid"), frames.get("deviceinfo"))
+
+
+def decode_manufacturer(manufacturer: int) -> str:
+    """
+    Look up the FIT profile name of a manufacturer id.
+
+    Parameters
+    ----------
+    manufacturer : int
+        Manufacturer id, as in the raw ``manufacturer`` field of ``file_id``
+        or ``device_info`` messages read with ``decode_enums=False``.
+
+    Returns
+    -------
+    str
+        The name, e.g. ``"garmin"`` for 1 or ``"wahoo_fitness"`` for 32, or
+        ``"manufacturer_<id>"`` for ids the profile doesn't list.
+
+    Examples
+    --------
+    >>> polarsfit.decode_manufacturer(294)
+    'coros'
+    """
+    return _decode_manufacturer(manufacturer)
+
+
+def decode_product(manufacturer: int, product: int) -> str:
+    """
+    Look up the FIT profile name of a manufacturer's product id.
+
+    The profile only lists the products of Garmin, including Dynastream and
+    Tacx, and Favero; of those, common watches, bike computers and sensors
+    are known. ``read_file_metadata`` looks names up in the full profile.
+
+    Parameters
+    ----------
+    manufacturer : int
+        Manufacturer id, see ``decode_manufacturer``.
+    product : int
+        Product id, as in the raw ``product`` field of ``file_id`` or
+        ``device_info`` messages.
+
+    Returns
+    -------
+    str
+        The name, e.g. ``"edge1000"`` for Garmin product 1836, or
+        ``"product_<id>"`` for products without a known name.
+
+    Examples
+    --------
+    >>> polarsfit.decode_product(1, 2691)
+    'fr935'
+    """
+    return _decode_product(manufacturer, product)
+
+
+def _unix_seconds(value: datetime | float | None) -> float | None:
+    """Convert a time range bound to Unix seconds, taking naive datetimes as UTC."""
+    if isinstance(value, datetime):
+        if value.tzinfo is None:
+            value = value.replace(tzinfo=timezone.utc)
+        return value.timestamp()
+    return None if value is None else float(value)
+
+
+def _rust_options(options: Mapping[str, Any]) -> dict[str, Any]:
+    """Keyword options for the Rust readers, leaving out those applied in Python."""
+    rust_options = {
+        key: value
+        for key, value in options.items()
+        if key not in ("apply_default_mapping", "temperature_unit")
+    }
+    for bound in ("start_time", "end_time"):
+        if bound in rust_options:
+            rust_options[bound] = _unix_seconds(rust_options[bound])
+    return rust_options
+
+
+def _read_messages(
+    decode: Callable[..., pl.DataFrame],
+    message_type: str,
+    field_mapping: dict[str, str] | None,
+    options: ReadOptions,
+) -> pl.DataFrame:
+    """
+    Read one message type with a Rust reader, then apply the Python options.
+
+    ``decode`` takes the message type, the Rust field mapping and the Rust
+    options, e.g. ``partial(_read_data, file_path)``.
+    """
+    mapping = _message_field_mapping(
+        message_type, field_mapping, options.get("apply_default_mapping", True)
+    )
+    df = decode(message_type, mapping, **_rust_options(options))
+    return _with_temperature_unit(
+        df, message_type, mapping, options.get("temperature_unit", "C")
+    )
+
+
+def _read_records(
+    decode: Callable[..., pl.DataFrame],
+    field_mapping: dict[str, str] | None,
+    options: RecordOptions,
+) -> pl.DataFrame:
+    """Read the record messages with a Rust reader, as ``read_recordmesgs``."""
+    mapping = _message_field_mapping(
+        "record", field_mapping, options.get("apply_default_mapping", True)
+    )
+    records = decode("record", mapping, **_record_rust_options(options, mapping))
+
+    def read_other(message_type: str, **other_options: Any) -> pl.DataFrame:
+        return _read_messages(decode, message_type, None, other_options)
+
+    return _process_records(records, mapping, options, read_other)
+
+
+def _record_rust_options(
+    options: RecordOptions, mapping: dict[str, str] | None
+) -> dict[str, Any]:
+    """Rust options decoding the records of ``read_recordmesgs``."""
+    decode_options = {
+        key: value for key, value in options.items() if key not in _RECORD_OPTIONS
+    }
+    if options.get("prefer_enhanced", True):
+        decode_options["fields"] = _with_enhanced_fields(
+            options.get("fields"), mapping
+        )
+    return _rust_options(decode_options)
+
+
+def _process_records(
+    records: pl.DataFrame,
+    mapping: dict[str, str] | None,
+    options: RecordOptions,
+    read_other: Callable[..., pl.DataFrame],
+) -> pl.DataFrame:
+    """
+    Apply the options of ``read_recordmesgs`` left after decoding to records.
+
+    Works on any frame of decoded records, e.g. one chunk of a file.
+    ``read_other`` reads another message type of the same file, taking the
+    options of ``read_data``.
+    """
+    decode_timestamps = options.get("decode_timestamps", True)
+    apply_scale = options.get("apply_scale", True)
+    altitude_source = options.get("altitude_source", "auto")
+    altitude_offset = options.get("altitude_offset", 0.0)
+
+    def read_gps() -> pl.DataFrame:
+        gps_options = {key: options[key] for key in _GPS_OPTIONS if key in options}
+        return read_other("gps_metadata", apply_default_mapping=False, **gps_options)
+
+    if altitude_source != "auto":
+        records = _select_altitude(records, mapping, altitude_source, read_gps)
+    if options.get("prefer_enhanced", True):
+        records = _prefer_enhanced(records, mapping)
+    if apply_scale:
+        records = _running_dynamics_as_float(records, mapping)
+        records = _split_balance(records, mapping, options.get("keep_raw", False))
+    if altitude_source == "auto":
+        records = _fill_altitude_from_gps(records, mapping, read_gps)
+    if altitude_offset:
+        records = _offset_altitude(records, mapping, altitude_offset, apply_scale)
+    if options.get("fill_distance", False):
+        records = _fill_distance(records, mapping, options.get("force", False))
+    if options.get("local_time", False):
+        activity = read_other(
+            "activity", apply_default_mapping=False, decode_timestamps=False
+        )
+        records = _with_local_time(records, activity, mapping)
+    if options.get("double_running_cadence", False):
+        sessions = read_other(
+            "session",
+            apply_default_mapping=False,
+            decode_timestamps=decode_timestamps,
+        )
+        records = _double_running_cadence(records, sessions, mapping)
+    return _with_temperature_unit(
+        records, "record", mapping, options.get("temperature_unit", "C")
+    )
+
+
+# Options of read_recordmesgs applied after decoding, beyond those of read_data
+_RECORD_OPTIONS = (
+    "prefer_enhanced",
+    "local_time",
+    "double_running_cadence",
+    "fill_distance",
+    "force",
+    "altitude_source",
+    "altitude_offset",
+)
+
+# Options of the records that the GPS altitude filling them is read with
+_GPS_OPTIONS = (
+    "decode_timestamps",
+    "apply_scale",
+    "mask_invalid",
+    "verify_crc",
+    "truncated_ok",
+    "encoding",
+)
+
+# Record fields with a 32-bit "enhanced" variant: speed and altitude
+_ENHANCED_RECORD_FIELDS = {6: 73, 2: 78}
+
+# Running dynamics record fields by column name, which for ground contact
+# time and balance is the one runners know rather than the profile's "stance"
+_RUNNING_DYNAMICS_FIELDS = {
+    39: "vertical_oscillation",  # mm
+    40: "stance_time_percent",  # % of the stride
+    41: "ground_contact_time",  # ms
+    83: "vertical_ratio",  # % of the step length
+    84: "ground_contact_balance",  # % on the left foot
+    85: "step_length",  # mm
+}
+
+# Record field packing a percentage of power from one pedal together with a
+# flag set when it is the right pedal's
+_BALANCE_FIELD = 30
+_BALANCE_PERCENT_MASK = 0x7F
+_BALANCE_RIGHT_FLAG = 0x80
+
+# Invalid value of the sint8 temperature fields, null even without masking
+_TEMPERATURE_INVALID = 0x7F
+
+# Record fields of the cadence, in steps of one leg per minute when running
+_CADENCE_FIELDS = [4, 53]
+
+# Session field numbers in the FIT profile
+_SESSION_START_TIME = 2
+_SESSION_SPORT = 5
+
+# Record fields of the altitude sources, and the GPS altitude of gps_metadata
+_ALTITUDE = 2
+_ENHANCED_ALTITUDE = 78
+_GPS_METADATA_ALTITUDE = 3
+
+# Record position and distance field numbers, for reconstructing distance
+_POSITION_LAT = 0
+_POSITION_LONG = 1
+_DISTANCE = 5
+
+
+def _prefer_enhanced(
+    records: pl.DataFrame, mapping: dict[str, str] | None
+) -> pl.DataFrame:
+    """Fill legacy record columns from their enhanced variants and drop those."""
+    mapping = mapping or {}
+    pairs = [
+        (
+            mapping.get(f"field_{legacy}", f"field_{legacy}") + suffix,
+            mapping.get(f"field_{enhanced}", f"field_{enhanced}") + suffix,
+        )
+        for legacy, enhanced in _ENHANCED_RECORD_FIELDS.items()
+        # The raw columns of keep_raw follow their decoded ones
+        for suffix in ("", "_raw")
+    ]
+    for legacy_name, enhanced_name in pairs:
+        if enhanced_name not in records.columns:
+            continue
+        if legacy_name in records.columns:
+            records = records.with_columns(
+                pl.coalesce(enhanced_name, legacy_name).alias(legacy_name)
+            ).drop(enhanced_name)
+        else:
+            records = records.rename({enhanced_name: legacy_name})
+    return records
+
+
+def _running_dynamics_as_float(
+    records: pl.DataFrame, mapping: dict[str, str] | None
+) -> pl.DataFrame:
+    """Cast the running dynamics columns to Float64, whichever field they came from."""
+    mapping = mapping or {}
+    columns = [
+        mapping.get(f"field_{number}", f"field_{number}")
+        for number in _RUNNING_DYNAMICS_FIELDS
+    ]
+    return records.with_columns(
+        pl.col(column).cast(pl.Float64)
+        for column in columns
+        if column in records.columns
+    )
+
+
+def _split_balance(
+    records: pl.DataFrame, mapping: dict[str, str] | None, keep_raw: bool
+) -> pl.DataFrame:
+    """Decode the left/right balance column into the percentage of each pedal."""
+    name = (mapping or {}).get(f"field_{_BALANCE_FIELD}", f"field_{_BALANCE_FIELD}")
+    if name not in records.columns or not records.schema[name].is_integer():
+        return records
+
+    balance = pl.col(name)
+    percent = (balance & _BALANCE_PERCENT_MASK).cast(pl.Float64)
+    # Without the right flag the side is unspecified; devices report the left
+    left = (
+        pl.when(percent > 100)
+        .then(None)
+        .when((balance & _BALANCE_RIGHT_FLAG) != 0)
+        .then(100 - percent)
+        .otherwise(percent)
+    )
+    split = ["left_balance_pct", "right_balance_pct"]
+    if keep_raw:
+        split.append(f"{name}_raw")
+    columns = records.columns
+    index = columns.index(name)
+    return records.with_columns(
+        left.alias("left_balance_pct"),
+        (100 - left).alias("right_balance_pct"),
+        balance.alias(f"{name}_raw"),
+    ).select(*columns[:index], *split, *columns[index + 1 :])
+
+
+def _double_running_cadence(
+    records: pl.DataFrame, sessions: pl.DataFrame, mapping: dict[str, str] | None
+) -> pl.DataFrame:
+    """Double the cadence of records in running sessions to total steps."""
+    mapping = mapping or {}
+    columns = [
+        name
+        for number in _CADENCE_FIELDS
+        if (name := mapping.get(f"field_{number}", f"field_{number}"))
+        in records.columns
+    ]
+    sport = f"field_{_SESSION_SPORT}"
+    if not columns or sport not in sessions.columns:
+        return records
+
+    start = f"field_{_SESSION_START_TIME}"
+    timestamp = mapping.get("field_253", "field_253")
+    running = (pl.col(sport).cast(pl.String) == "running").fill_null(False)
+    starts = pl.DataFrame()
+    if start in sessions.columns and timestamp in records.columns:
+        starts = (
+            sessions.select(start, running.alias("running"))
+            .drop_nulls(start)
+            .sort(start)
+        )
+    if starts.is_empty():
+        in_running = pl.lit(sessions.select(running.all()).item())
+    else:
+        # Each record belongs to the last session started before it, or to the
+        # first session if it precedes them all
+        index = starts[start].search_sorted(records[timestamp], side="right")
+        index = (index.cast(pl.Int64) - 1).clip(lower_bound=0)
+        in_running = pl.lit(starts["running"].gather(index))
+
+    return records.with_columns(
+        pl.when(in_running).then(pl.col(name) * 2).otherwise(pl.col(name)).alias(name)
+        for name in columns
+    )
+
+
+def _fill_distance(
+    records: pl.DataFrame, mapping: dict[str, str] | None, force: bool
+) -> pl.DataFrame:
+    """Fill a missing or all-null distance with the GPS track's length."""
+    mapping = mapping or {}
+    lat, lon, distance = (
+        mapping.get(f"field_{number}", f"field_{number}")
+        for number in (_POSITION_LAT, _POSITION_LONG, _DISTANCE)
+    )
+    if not {lat, lon} <= set(records.columns):
+        return records
+    recorded = (
+        distance in records.columns
+        and records[distance].null_count() < records.height
+    )
+    if recorded and not force:
+        return records
+    # Positions are still semicircles without convert_gps
+    lat, lon = (
+        semicircle_to_degrees(name)
+        if records.schema[name].is_integer()
+        else pl.col(name)
+        for name in (lat, lon)
+    )
+    return records.with_columns(haversine_cumulative(lat, lon).alias(distance))
+
+
+def _select_altitude(
+    records: pl.DataFrame,
+    mapping: dict[str, str] | None,
+    source: str,
+    read_gps: Callable[[], pl.DataFrame],
+) -> pl.DataFrame:
+    """Replace the altitude column with one source and drop enhanced_altitude."""
+    mapping = mapping or {}
+    altitude, enhanced = (
+        mapping.get(f"field_{number}", f"field_{number}")
+        for number in (_ALTITUDE, _ENHANCED_ALTITUDE)
+    )
+    if source == "gps":
+        values = _gps_altitude(records, mapping, read_gps())
+    elif source in ("enhanced", "baro"):
+        name = enhanced if source == "enhanced" else altitude
+        values = (
+            records[name]
+            if name in records.columns
+            else pl.Series(altitude, [None] * records.height, dtype=pl.Float64)
+        )
+    else:
+        raise ValueError(
+            "altitude_source must be 'auto', 'enhanced', 'baro' or 'gps', "
+            f"got {source!r}"
+        )
+
+    if altitude not in records.columns and enhanced in records.columns:
+        # Take enhanced_altitude's place, as prefer_enhanced does
+        records = records.rename({enhanced: altitude})
+    records = records.with_columns(values.alias(altitude))
+    return records.drop(enhanced, f"{enhanced}_raw", strict=False)
+
+
+def _fill_altitude_from_gps(
+    records: pl.DataFrame,
+    mapping: dict[str, str] | None,
+    read_gps: Callable[[], pl.DataFrame],
+) -> pl.DataFrame:
+    """Fill missing altitudes from the GPS, reading it only if some are missing."""
+    mapping = mapping or {}
+    altitude, lat = (
+        mapping.get(f"field_{number}", f"field_{number}")
+        for number in (_ALTITUDE, _POSITION_LAT)
+    )
+    if altitude in records.columns and not records[altitude].null_count():
+        return records
+    # Without a fix there is no GPS altitude either, e.g. for indoor rides
+    if lat not in records.columns or records[lat].null_count() == records.height:
+        return records
+    gps = _gps_altitude(records, mapping, read_gps())
+    if gps.null_count() == gps.len():
+        return records
+    if altitude not in records.columns:
+        return records.with_columns(gps.alias(altitude))
+    return records.with_columns(pl.coalesce(altitude, gps).alias(altitude))
+
+
+def _gps_altitude(
+    records: pl.DataFrame, mapping: dict[str, str], gps: pl.DataFrame
+) -> pl.Series:
+    """GPS altitude of each record, from the gps_metadata message of its time."""
+    timestamp = mapping.get("field_253", "field_253")
+    gps_altitude = f"field_{_GPS_METADATA_ALTITUDE}"
+    timed = {"field_253", gps_altitude} <= set(gps.columns)
+    if timestamp not in records.columns or not timed:
+        return pl.Series("gps_altitude", [None] * records.height, dtype=pl.Float64)
+    by_time = (
+        gps.select(
+            pl.col("field_253").alias(timestamp),
+            pl.col(gps_altitude).alias("gps_altitude"),
+        )
+        .drop_nulls()
+        .unique(timestamp, keep="first", maintain_order=True)
+    )
+    return (
+        records.select(timestamp)
+        .join(by_time, on=timestamp, how="left", maintain_order="left")
+        .get_column("gps_altitude")
+    )
+
+
+def _offset_altitude(
+    records: pl.DataFrame,
+    mapping: dict[str, str] | None,
+    offset: float,
+    apply_scale: bool,
+) -> pl.DataFrame:
+    """Add a calibration offset in meters to the altitude column."""
+    if not apply_scale:
+        raise ValueError("altitude_offset is in meters and needs apply_scale=True")
+    altitude = (mapping or {}).get(f"field_{_ALTITUDE}", f"field_{_ALTITUDE}")
+    if altitude not in records.columns:
+        return records
+    return records.with_columns(pl.col(altitude) + offset)
+
+
+def _with_temperature_unit(
+    df: pl.DataFrame,
+    message_type: str,
+    mapping: dict[str, str] | None,
+    unit: str,
+) -> pl.DataFrame:
+    """Convert the Celsius fields of a message type to the requested unit."""
+    return df.with_columns(
+        pl.when(pl.col(column) != _TEMPERATURE_INVALID)
+        .then(pl.col(column).cast(pl.Float64) * 9 / 5 + 32)
+        .alias(column)
+        for column in _temperature_columns(message_type, mapping, unit)
+        if column in df.columns and df.schema[column].is_numeric()
+    )
+
+
+def _temperature_columns(
+    message_type: str, mapping: dict[str, str] | None, unit: str
+) -> list[str]:
+    """Get the Celsius columns of a message type that ``unit`` converts."""
+    if unit not in ("C", "F"):
+        raise ValueError(f"temperature_unit must be 'C' or 'F', got {unit!r}")
+    if unit == "C":
+        return []
+    try:
+        units = get_field_units(message_type)
+    except ValueError:
+        # Message type missing from the FIT profile
+        return []
+    mapping = mapping or {}
+    return [
+        mapping.get(f"field_{number}", f"field_{number}")
+        for number, field_units in units.items()
+        if field_units == "C"
+    ]
+
+
+def _with_local_time(
+    records: pl.DataFrame, activity: pl.DataFrame, mapping: dict[str, str] | None
+) -> pl.DataFrame:
+    """Add the wall-clock ``local_time`` of the records' timestamp column."""
+    timestamp = (mapping or {}).get("field_253", "field_253")
+    return _local_time(records, timestamp, _utc_offset(activity))
+
+
+def _with_enhanced_fields(
+    fields: list[str] | None, mapping: dict[str, str] | None
+) -> list[str] | None:
+    """Add the enhanced variants of requested legacy columns to decode."""
+    if fields is None:
+        return None
+    mapping = mapping or {}
+    enhanced = [
+        mapping.get(f"field_{enhanced}", f"field_{enhanced}")
+        for legacy, enhanced in _ENHANCED_RECORD_FIELDS.items()
+        if mapping.get(f"field_{legacy}", f"field_{legacy}") in fields
+    ]
+    return [*fields, *enhanced]
+
+
+def _message_field_mapping(
+    message_type: str,
+    field_mapping: dict[str, str] | None,
+    apply_default_mapping: bool,
+) -> dict[str, str] | None:
+    """Build the ``field_X`` mapping passed to Rust for a message type."""
     # Build field mapping
     final_mapping = {}
 
     if apply_default_mapping:
         # Try to get default mapping for this message type
         try:
-            msg_type_enum = getattr(MessageType, message_type.upper())
-            default_fields = get_field_mapping(msg_type_enum)
+            default_fields = get_field_mapping(message_type)
             default_mapping = {
                 f"field_{k}": v for k, v in default_fields.items()
             }
             final_mapping.update(default_mapping)
-        except (AttributeError, KeyError):
-            # No default mapping available for this message type
+            if message_type == "record":
+                final_mapping.update(
+                    {f"field_{k}": v for k, v in _RUNNING_DYNAMICS_FIELDS.items()}
+                )
+        except ValueError:
+            # Message type missing from the FIT profile
             pass
 
     if field_mapping:
@@ -172,17 +2901,77 @@ def read_data(
         final_mapping.update(field_mapping)
 
     # Convert to format expected by Rust, or pass None if empty
-    rust_mapping = final_mapping if final_mapping else None
+    return final_mapping if final_mapping else None
 
-    return _read_data(file_path, message_type, rust_mapping)
 
 
 __all__ = [
+    "ReadOptions",
+    "RecordOptions",
     "read_recordmesgs",
     "get_message_types",
+    "get_message_counts",
+    "get_schema",
+    "get_field_info",
+    "read_header",
     "read_data",
+    "read_recordmesgs_from_bytes",
+    "read_data_from_bytes",
+    "read_data_from_fileobj",
+    "read_data_arrow_ipc",
+    "read_data_with_units",
+    "read_many_data",
+    "read_many_concat",
+    "read_directory",
+    "concat_frames",
+    "read_all_messages",
+    "scan_data",
+    "scan_recordmesgs",
+    "RecordBatchReader",
+    "read_session_summary",
+    "read_hrv",
+    "read_lengths",
+    "read_course_points",
+    "read_workout_steps",
+    "read_timer_events",
+    "read_file_metadata",
+    "decode_manufacturer",
+    "decode_product",
+    "resample_records",
+    "read_records_with_laps",
+    "split_by_session",
+    "power_curve",
+    "time_in_zones",
+    "detect_stops",
+    "best_efforts",
+    "decoupling",
+    "estimate_calories",
+    "ascent_descent",
+    "write_records_to_fit",
+    "write_data_to_parquet",
+    "records_to_gpx",
+    "activity_to_tcx",
+    "records_to_geojson",
+    "semicircle_to_degrees",
+    "haversine_cumulative",
+    "grade",
+    "normalized_power",
+    "elevation_gain",
+    "hr_zone",
+    "pace",
+    "rolling_smooth",
+    "is_moving",
+    "moving_time",
+    "simplify_track",
+    "wprime_balance",
+    "interpolate_gps",
+    "bearing",
+    "vertical_speed",
+    "find_peaks",
+    "grade_adjusted_pace",
     "MessageType",
     "get_field_mapping",
+    "get_field_units",
     "get_available_message_types",
 ]
 
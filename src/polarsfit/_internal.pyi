@@ -1,15 +1,379 @@
 """Type stubs for the internal Rust module."""
 
+from collections.abc import Callable, Iterator
+from typing import Any, Literal
+
 import polars as pl
 
-def read_recordmesgs(file_path: str) -> pl.DataFrame:
+def read_recordmesgs(
+    file_path: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    start_time: float | None = None,
+    end_time: float | None = None,
+    add_index: bool = False,
+    progress: Callable[[int], object] | None = None,
+    truncated_ok: bool = True,
+    limit: int | None = None,
+    sort_by_time: bool = False,
+    dedup_time: bool = False,
+    dedup_keep: Literal["first", "last"] = "first",
+    all_float: bool = False,
+    keep_raw: bool = False,
+    encoding: Literal["utf-8", "latin-1"] = "utf-8",
+    categorical_enums: bool = True,
+) -> pl.DataFrame:
     """Read record messages from a FIT file.
 
     Args:
         file_path: Path to the FIT file to read
+        field_mapping: Optional mapping from ``field_<n>`` to column names
+        decode_timestamps: Return timestamp fields as UTC datetimes
+        apply_scale: Apply the FIT profile scale and offset to numeric fields
+        convert_gps: Convert record positions from semicircles to degrees
+        decode_enums: Return enum fields by their FIT profile names
+        verify_crc: Raise ValueError if the header or file CRC doesn't match
+        mask_invalid: Return FIT invalid sentinel values as null
+        fields: Optional column names to decode, skipping all other fields
+        schema: Optional mapping from column names to dtype names to cast to
+        start_time: Skip messages timestamped before this many Unix seconds
+        end_time: Skip messages timestamped after this many Unix seconds
+        add_index: Add a leading message_index column numbering the messages
 
     Returns
     -------
         Polars DataFrame containing the record messages
     """
     ...
+
+def get_message_types(file_path: str) -> list[str]:
+    """Get all message types present in a FIT file."""
+    ...
+
+def read_header(file_path: str) -> dict[str, Any]:
+    """Read only the header of a FIT file."""
+    ...
+
+def get_message_counts(file_path: str) -> dict[str, int]:
+    """Count the messages of each type in a FIT file."""
+    ...
+
+def get_field_numbers(
+    file_path: str, message_type: str
+) -> tuple[int | None, list[int]]:
+    """Get the global message number and the numbers of the fields with values."""
+    ...
+
+def decode_manufacturer(manufacturer: int) -> str:
+    """Name of a manufacturer id from the FIT profile."""
+    ...
+
+def decode_product(manufacturer: int, product: int) -> str:
+    """Name of a product id of a manufacturer from the FIT profile."""
+    ...
+
+def get_schema(
+    file_path: str,
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    start_time: float | None = None,
+    end_time: float | None = None,
+    add_index: bool = False,
+    progress: Callable[[int], object] | None = None,
+    truncated_ok: bool = True,
+    limit: int | None = None,
+    sort_by_time: bool = False,
+    dedup_time: bool = False,
+    dedup_keep: Literal["first", "last"] = "first",
+    all_float: bool = False,
+    keep_raw: bool = False,
+    encoding: Literal["utf-8", "latin-1"] = "utf-8",
+    categorical_enums: bool = True,
+) -> list[tuple[str, str]]:
+    """Get the column names and dtype names of a message type, in column order."""
+    ...
+
+def read_data(
+    file_path: str,
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    start_time: float | None = None,
+    end_time: float | None = None,
+    add_index: bool = False,
+    progress: Callable[[int], object] | None = None,
+    truncated_ok: bool = True,
+    limit: int | None = None,
+    sort_by_time: bool = False,
+    dedup_time: bool = False,
+    dedup_keep: Literal["first", "last"] = "first",
+    all_float: bool = False,
+    keep_raw: bool = False,
+    encoding: Literal["utf-8", "latin-1"] = "utf-8",
+    categorical_enums: bool = True,
+) -> pl.DataFrame:
+    """Read messages of a specific type from a FIT file."""
+    ...
+
+def read_data_by_type(
+    file_path: str,
+    message_types: list[str],
+    field_mappings: dict[str, dict[str, str]] | None = None,
+    *,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    start_time: float | None = None,
+    end_time: float | None = None,
+    add_index: bool = False,
+    progress: Callable[[int], object] | None = None,
+    truncated_ok: bool = True,
+    limit: int | None = None,
+    sort_by_time: bool = False,
+    dedup_time: bool = False,
+    dedup_keep: Literal["first", "last"] = "first",
+    all_float: bool = False,
+    keep_raw: bool = False,
+    encoding: Literal["utf-8", "latin-1"] = "utf-8",
+    categorical_enums: bool = True,
+) -> dict[str, pl.DataFrame]:
+    """Read messages of several types from a FIT file in one pass, by type."""
+    ...
+
+def read_recordmesgs_from_bytes(
+    data: bytes,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    start_time: float | None = None,
+    end_time: float | None = None,
+    add_index: bool = False,
+    progress: Callable[[int], object] | None = None,
+    truncated_ok: bool = True,
+    limit: int | None = None,
+    sort_by_time: bool = False,
+    dedup_time: bool = False,
+    dedup_keep: Literal["first", "last"] = "first",
+    all_float: bool = False,
+    keep_raw: bool = False,
+    encoding: Literal["utf-8", "latin-1"] = "utf-8",
+    categorical_enums: bool = True,
+) -> pl.DataFrame:
+    """Read record messages from the bytes of a FIT file."""
+    ...
+
+def read_data_from_bytes(
+    data: bytes,
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    start_time: float | None = None,
+    end_time: float | None = None,
+    add_index: bool = False,
+    progress: Callable[[int], object] | None = None,
+    truncated_ok: bool = True,
+    limit: int | None = None,
+    sort_by_time: bool = False,
+    dedup_time: bool = False,
+    dedup_keep: Literal["first", "last"] = "first",
+    all_float: bool = False,
+    keep_raw: bool = False,
+    encoding: Literal["utf-8", "latin-1"] = "utf-8",
+    categorical_enums: bool = True,
+) -> pl.DataFrame:
+    """Read messages of a specific type from the bytes of a FIT file."""
+    ...
+
+def read_data_arrow_ipc(
+    file_path: str,
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+) -> bytes:
+    """Read messages of a specific type from a FIT file as Arrow IPC stream bytes."""
+    ...
+
+def read_many_data(
+    file_paths: list[str],
+    message_type: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    start_time: float | None = None,
+    end_time: float | None = None,
+    add_index: bool = False,
+) -> list[pl.DataFrame | Exception]:
+    """Read messages of a specific type from many FIT files in parallel.
+
+    Failed files are returned as the exception they raised.
+    """
+    ...
+
+def read_all_messages(
+    file_path: str,
+    field_mapping: dict[str, str] | None = None,
+    *,
+    decode_timestamps: bool = True,
+    apply_scale: bool = True,
+    convert_gps: bool = True,
+    decode_enums: bool = True,
+    verify_crc: bool = False,
+    mask_invalid: bool = True,
+    fields: list[str] | None = None,
+    schema: dict[str, str] | None = None,
+    start_time: float | None = None,
+    end_time: float | None = None,
+    add_index: bool = False,
+    progress: Callable[[int], object] | None = None,
+    truncated_ok: bool = True,
+    limit: int | None = None,
+    sort_by_time: bool = False,
+    dedup_time: bool = False,
+    dedup_keep: Literal["first", "last"] = "first",
+    all_float: bool = False,
+    keep_raw: bool = False,
+    encoding: Literal["utf-8", "latin-1"] = "utf-8",
+    categorical_enums: bool = True,
+) -> dict[str, pl.DataFrame]:
+    """Read every message type from a FIT file, one DataFrame per type."""
+    ...
+
+def concat_frames(
+    frames: list[pl.DataFrame], how: Literal["diagonal", "vertical"] = "diagonal"
+) -> pl.DataFrame:
+    """Stack DataFrames on the union of their columns, promoting dtypes."""
+    ...
+
+def write_records_to_fit(df: pl.DataFrame, file_path: str) -> None:
+    """Write record messages with field_<n> column names to a new FIT file."""
+    ...
+
+def write_data_to_parquet(
+    file_path: str,
+    message_type: str,
+    out_path: str,
+    field_mapping: dict[str, str] | None = None,
+    compression: Literal["snappy", "zstd"] = "snappy",
+) -> None:
+    """Write messages of a specific type from a FIT file to a Parquet file."""
+    ...
+
+class MessageBatchReader:
+    """Iterate over the messages of one type in a FIT file in DataFrame batches."""
+
+    def __init__(
+        self,
+        file_path: str,
+        message_type: str,
+        field_mapping: dict[str, str] | None = None,
+        batch_size: int = 65536,
+        *,
+        decode_timestamps: bool = True,
+        apply_scale: bool = True,
+        convert_gps: bool = True,
+        decode_enums: bool = True,
+        verify_crc: bool = False,
+        mask_invalid: bool = True,
+        fields: list[str] | None = None,
+        schema: dict[str, str] | None = None,
+        start_time: float | None = None,
+        end_time: float | None = None,
+        add_index: bool = False,
+        progress: Callable[[int], object] | None = None,
+        truncated_ok: bool = True,
+        limit: int | None = None,
+        sort_by_time: bool = False,
+        dedup_time: bool = False,
+        dedup_keep: Literal["first", "last"] = "first",
+        all_float: bool = False,
+        keep_raw: bool = False,
+        encoding: Literal["utf-8", "latin-1"] = "utf-8",
+        categorical_enums: bool = True,
+    ) -> None: ...
+    def __iter__(self) -> Iterator[pl.DataFrame]: ...
+    def __next__(self) -> pl.DataFrame: ...
+
+class RecordBatchReader:
+    """Iterate over the record messages of a FIT file in DataFrame chunks."""
+
+    def __init__(
+        self,
+        file_path: str,
+        field_mapping: dict[str, str] | None = None,
+        chunk_size: int = 65536,
+        *,
+        decode_timestamps: bool = True,
+        apply_scale: bool = True,
+        convert_gps: bool = True,
+        decode_enums: bool = True,
+        verify_crc: bool = False,
+        mask_invalid: bool = True,
+        fields: list[str] | None = None,
+        schema: dict[str, str] | None = None,
+        start_time: float | None = None,
+        end_time: float | None = None,
+        add_index: bool = False,
+        progress: Callable[[int], object] | None = None,
+        truncated_ok: bool = True,
+        limit: int | None = None,
+        sort_by_time: bool = False,
+        dedup_time: bool = False,
+        dedup_keep: Literal["first", "last"] = "first",
+        all_float: bool = False,
+        keep_raw: bool = False,
+        encoding: Literal["utf-8", "latin-1"] = "utf-8",
+        categorical_enums: bool = True,
+    ) -> None: ...
+    def __iter__(self) -> Iterator[pl.DataFrame]: ...
+    def __next__(self) -> pl.DataFrame: ...
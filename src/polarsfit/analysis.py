@@ -0,0 +1,343 @@
+"""Performance analyses built from decoded record messages."""
+
+from datetime import timedelta
+
+import polars as pl
+
+from polarsfit.expressions import elevation_gain, hr_zone
+
+# Durations in seconds of the default power curve, from peak power to an hour
+POWER_CURVE_DURATIONS = [1, 5, 15, 30, 60, 300, 600, 1200, 3600]
+
+# Distances in meters of the default best efforts
+BEST_EFFORT_DISTANCES = [1000, 5000, 10000]
+
+# Longest interval in seconds between records that isn't a pause of the device
+_MAX_RECORD_GAP = 10
+
+# Longest stretch in seconds of moving between two stops that merges them, so
+# creeping forward at a traffic light doesn't split the stop
+_STOP_MERGE_SECONDS = 5
+
+# Longest run of seconds without a record that takes the power recorded
+# before it, as devices with "smart recording" only log changes
+_MAX_FILL_SECONDS = 10
+
+# Gross mechanical efficiency of cycling assumed by calorie estimates
+CYCLING_EFFICIENCY = 0.23
+
+_JOULES_PER_KCAL = 4184
+
+# Altitude change in meters that counts as climbing or descending, like the
+# hysteresis bike computers apply against barometer noise
+ASCENT_THRESHOLD = 3.0
+
+# Keytel et al. (2005) coefficients of energy expenditure in kJ/min from heart
+# rate, weight in kg and age in years: (intercept, heart rate, weight, age)
+_KEYTEL_COEFFICIENTS = {
+    "male": (-55.0969, 0.6309, 0.1988, 0.2017),
+    "female": (-20.4022, 0.4472, -0.1263, 0.074),
+}
+
+
+def power_curve(records: pl.DataFrame, durations: list[int]) -> pl.DataFrame:
+    """
+    Mean-maximal power of decoded records for each duration in seconds.
+
+    Power is resampled to one value per second. Seconds without a record
+    take the power before them for up to ``_MAX_FILL_SECONDS``, longer gaps
+    count as 0 W. Durations longer than the activity are null.
+    """
+    if any(duration < 1 for duration in durations):
+        raise ValueError(f"durations must be positive seconds, got {durations}")
+
+    power = _per_second(records, "power").fill_null(
+        strategy="forward", limit=_MAX_FILL_SECONDS
+    )
+    power = power.fill_null(0.0)
+    best = [
+        power.rolling_mean(window_size=duration).max()
+        if duration <= power.len()
+        else None
+        for duration in durations
+    ]
+    return pl.DataFrame(
+        {"duration": durations, "power": best},
+        schema={"duration": pl.UInt32, "power": pl.Float64},
+    )
+
+
+def time_in_zones(
+    records: pl.DataFrame, channel: str, boundaries: list[float], max_gap: float
+) -> pl.DataFrame:
+    """
+    Seconds of decoded records spent in each zone of a channel.
+
+    Each sample holds its zone until the next sample, so the interval to it
+    counts towards that zone. Intervals longer than ``max_gap`` seconds and
+    those starting at a null value are not counted.
+    """
+    zones = pl.DataFrame(
+        {"zone": range(len(boundaries) + 1)}, schema={"zone": pl.UInt8}
+    )
+    if not {"timestamp", channel} <= set(records.columns):
+        return zones.with_columns(seconds=pl.lit(0.0))
+
+    interval = pl.col("timestamp").diff().shift(-1).dt.total_milliseconds() / 1000
+    spent = (
+        records.filter(pl.col("timestamp").is_not_null())
+        .sort("timestamp")
+        .select(
+            hr_zone(channel, boundaries).alias("zone"),
+            interval.alias("seconds"),
+        )
+        .filter(pl.col("zone").is_not_null() & (pl.col("seconds") <= max_gap))
+        .group_by("zone")
+        .agg(pl.col("seconds").sum())
+    )
+    return zones.join(spent, on="zone", how="left").with_columns(
+        pl.col("seconds").fill_null(0.0)
+    )
+
+
+def detect_stops(
+    records: pl.DataFrame, min_stop_seconds: float, min_speed: float
+) -> pl.DataFrame:
+    """
+    Stop intervals of decoded records, lasting at least ``min_stop_seconds``.
+
+    The interval from a record to the next is stopped when the record's
+    speed is below ``min_speed``, or when it is longer than
+    ``_MAX_RECORD_GAP`` seconds, i.e. the device paused. Runs of stopped
+    intervals less than ``_STOP_MERGE_SECONDS`` apart are merged.
+    """
+    schema = {
+        "start_time": pl.Datetime("ms", "UTC"),
+        "end_time": pl.Datetime("ms", "UTC"),
+        "duration": pl.Float64,
+    }
+    if not {"timestamp", "speed"} <= set(records.columns):
+        return pl.DataFrame(schema=schema)
+
+    next_time = pl.col("timestamp").shift(-1)
+    runs = (
+        records.filter(pl.col("timestamp").is_not_null())
+        .sort("timestamp")
+        .select(
+            pl.col("timestamp").alias("start_time"),
+            next_time.alias("end_time"),
+            (
+                (pl.col("speed") < min_speed).fill_null(False)
+                | (next_time - pl.col("timestamp") > timedelta(seconds=_MAX_RECORD_GAP))
+            ).alias("stopped"),
+        )
+        .filter(pl.col("end_time").is_not_null())
+        .with_columns(run=pl.col("stopped").rle_id())
+        .filter("stopped")
+        .group_by("run")
+        .agg(pl.col("start_time").min(), pl.col("end_time").max())
+        .sort("start_time")
+    )
+    merge = timedelta(seconds=_STOP_MERGE_SECONDS)
+    return (
+        runs.with_columns(
+            stop=(pl.col("start_time") - pl.col("end_time").shift() > merge)
+            .fill_null(True)
+            .cum_sum()
+        )
+        .group_by("stop")
+        .agg(pl.col("start_time").min(), pl.col("end_time").max())
+        .sort("start_time")
+        .with_columns(
+            duration=(pl.col("end_time") - pl.col("start_time")).dt.total_milliseconds()
+            / 1000
+        )
+        .filter(pl.col("duration") >= min_stop_seconds)
+        .select(pl.col(name).cast(dtype) for name, dtype in schema.items())
+    )
+
+
+def best_efforts(records: pl.DataFrame, distances: list[float]) -> pl.DataFrame:
+    """
+    Fastest time of decoded records to cover each distance in meters.
+
+    For every record, the effort ending there starts at the latest record at
+    least the distance back along the cumulative ``distance`` column; the
+    fastest effort wins. Distances longer than the activity are null.
+    """
+    if any(distance <= 0 for distance in distances):
+        raise ValueError(f"distances must be positive meters, got {distances}")
+    schema = {
+        "distance": pl.Float64,
+        "start_index": pl.UInt32,
+        "elapsed_time": pl.Float64,
+    }
+    efforts = [{"distance": float(distance)} for distance in distances]
+
+    if {"timestamp", "distance"} <= set(records.columns):
+        points = (
+            records.with_row_index("index")
+            .select("index", "timestamp", pl.col("distance").cast(pl.Float64))
+            .filter(
+                pl.col("timestamp").is_not_null() & pl.col("distance").is_not_null()
+            )
+            .sort("timestamp")
+            # GPS distance can step back a little; the join needs a sorted key
+            .with_columns(pl.col("distance").cum_max())
+        )
+        starts = points.select(
+            pl.col("index").alias("start_index"),
+            pl.col("timestamp").alias("start_time"),
+            pl.col("distance").alias("start_distance"),
+        )
+        for effort in efforts:
+            fastest = (
+                points.with_columns(
+                    start_distance=pl.col("distance") - effort["distance"]
+                )
+                .join_asof(starts, on="start_distance", strategy="backward")
+                .drop_nulls("start_index")
+                .select(
+                    "start_index",
+                    elapsed_time=(
+                        pl.col("timestamp") - pl.col("start_time")
+                    ).dt.total_milliseconds()
+                    / 1000,
+                )
+                .sort("elapsed_time", "start_index")
+            )
+            if fastest.height:
+                effort.update(fastest.row(0, named=True))
+
+    return pl.DataFrame(efforts, schema=schema).with_columns(
+        pace=pl.col("elapsed_time") / (pl.col("distance") / 1000)
+    )
+
+
+def decoupling(
+    records: pl.DataFrame,
+    channel: str,
+    warmup_seconds: float,
+    cooldown_seconds: float,
+) -> float:
+    """
+    Percent drop of the efficiency ratio of decoded records between halves.
+
+    The ratio is the mean of ``channel`` over the mean heart rate of the
+    records in each half of the time left after trimming the warm-up and
+    cool-down. Records without both values, or without a heart rate, are
+    left out.
+    """
+    if channel not in ("power", "speed"):
+        raise ValueError(f"channel must be 'power' or 'speed', got {channel!r}")
+    if not {"timestamp", "heart_rate", channel} <= set(records.columns):
+        raise ValueError(f"decoupling needs records with heart_rate and {channel}")
+
+    samples = (
+        records.select(
+            "timestamp",
+            pl.col(channel).cast(pl.Float64),
+            pl.col("heart_rate").cast(pl.Float64),
+        )
+        .drop_nulls()
+        .filter(pl.col("heart_rate") > 0)
+    )
+    if samples.is_empty():
+        raise ValueError(f"decoupling needs records with heart_rate and {channel}")
+    start = samples["timestamp"].min() + timedelta(seconds=warmup_seconds)
+    end = samples["timestamp"].max() - timedelta(seconds=cooldown_seconds)
+    if start >= end:
+        raise ValueError("the warm-up and cool-down leave no time to compare")
+
+    middle = start + (end - start) / 2
+    ratios = (
+        samples.filter(pl.col("timestamp").is_between(start, end))
+        .group_by(second=pl.col("timestamp") > middle)
+        .agg(ratio=pl.col(channel).mean() / pl.col("heart_rate").mean())
+    )
+    first = ratios.filter(~pl.col("second"))["ratio"]
+    second = ratios.filter(pl.col("second"))["ratio"]
+    if first.is_empty() or second.is_empty() or first[0] == 0:
+        raise ValueError(f"decoupling needs {channel} in both halves")
+    return (first[0] - second[0]) / first[0] * 100
+
+
+def estimate_calories(
+    records: pl.DataFrame,
+    efficiency: float,
+    weight: float | None,
+    age: float | None,
+    sex: str | None,
+) -> float:
+    """
+    Kilocalories burnt over decoded records, from power or else heart rate.
+
+    The mechanical work is the per-second power summed over the activity,
+    filled as in ``power_curve``; dividing it by ``efficiency`` gives the
+    energy burnt. Without power, the Keytel heart rate equation for the
+    given weight, age and sex is summed per second instead.
+    """
+    if not 0 < efficiency <= 1:
+        raise ValueError(f"efficiency must be in (0, 1], got {efficiency}")
+
+    power = _per_second(records, "power")
+    if power.drop_nulls().len():
+        work = power.fill_null(strategy="forward", limit=_MAX_FILL_SECONDS)
+        return work.fill_null(0.0).sum() / efficiency / _JOULES_PER_KCAL
+
+    if weight is None or age is None or sex is None:
+        raise ValueError("estimating calories without power needs weight, age and sex")
+    if sex not in _KEYTEL_COEFFICIENTS:
+        raise ValueError(f"sex must be 'male' or 'female', got {sex!r}")
+    heart_rate = _per_second(records, "heart_rate")
+    if not heart_rate.drop_nulls().len():
+        raise ValueError("estimating calories needs records with power or heart_rate")
+    intercept, per_bpm, per_kg, per_year = _KEYTEL_COEFFICIENTS[sex]
+    kj_per_minute = (
+        heart_rate.fill_null(strategy="forward", limit=_MAX_FILL_SECONDS) * per_bpm
+        + (intercept + weight * per_kg + age * per_year)
+    ).clip(lower_bound=0.0)
+    return kj_per_minute.fill_null(0.0).sum() / 60 * 1000 / _JOULES_PER_KCAL
+
+
+def ascent_descent(records: pl.DataFrame, threshold: float) -> tuple[float, float]:
+    """
+    Total ascent and descent in meters of decoded records, with hysteresis.
+
+    Altitude, enhanced where recorded, is compared against a reference that
+    starts at the first altitude. A climb or drop of at least ``threshold``
+    from it is counted and moves the reference there, so noise smaller than
+    the threshold adds nothing. Records without an altitude are skipped.
+    """
+    if threshold < 0:
+        raise ValueError(f"threshold must be non-negative meters, got {threshold}")
+    present = [
+        pl.col(name).cast(pl.Float64)
+        for name in ("enhanced_altitude", "altitude")
+        if name in records.columns
+    ]
+    if not present:
+        return 0.0, 0.0
+    if "timestamp" in records.columns:
+        records = records.sort("timestamp", nulls_last=True, maintain_order=True)
+    altitude = pl.coalesce(present)
+    ascent, descent = records.select(
+        elevation_gain(altitude, threshold).alias("ascent"),
+        elevation_gain(altitude, threshold, descent=True).alias("descent"),
+    ).row(0)
+    # Null without any altitude
+    return ascent or 0.0, descent or 0.0
+
+
+def _per_second(records: pl.DataFrame, column: str) -> pl.Series:
+    """Mean of a column per second of the records, null in seconds without any."""
+    if not {"timestamp", column} <= set(records.columns):
+        return pl.Series(column, [], dtype=pl.Float64)
+    return (
+        records.select("timestamp", pl.col(column).cast(pl.Float64))
+        .filter(pl.col("timestamp").is_not_null())
+        .sort("timestamp")
+        .group_by_dynamic("timestamp", every="1s")
+        .agg(pl.col(column).mean())
+        .upsample("timestamp", every="1s")[column]
+    )
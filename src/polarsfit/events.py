@@ -0,0 +1,52 @@
+"""Activity segments built from FIT timer events."""
+
+import polars as pl
+
+# Event field numbers in the FIT profile
+EVENT_EVENT = 0
+EVENT_EVENT_TYPE = 1
+EVENT_TIMESTAMP = 253
+
+# Event types of the timer event that stop it; "stop_all" ends the activity
+_TIMER_STOPS = {"stop", "stop_all", "stop_disable", "stop_disable_all"}
+
+
+def timer_segments(events: pl.DataFrame) -> pl.DataFrame:
+    """
+    Intervals between each start of the timer and the stop after it.
+
+    ``events`` must use raw ``field_X`` column names, with enum and timestamp
+    decoding applied. Starts while the timer runs and stops while it is
+    stopped are ignored; a start without a stop after it has a null end.
+    """
+    schema = {
+        "start_time": pl.Datetime("ms", "UTC"),
+        "end_time": pl.Datetime("ms", "UTC"),
+    }
+    event = f"field_{EVENT_EVENT}"
+    event_type = f"field_{EVENT_EVENT_TYPE}"
+    timestamp = f"field_{EVENT_TIMESTAMP}"
+    segments = []
+    if {timestamp, event, event_type} <= set(events.columns):
+        timer = (
+            events.filter(
+                (pl.col(event).cast(pl.String) == "timer")
+                & pl.col(timestamp).is_not_null()
+            )
+            .select(timestamp, pl.col(event_type).cast(pl.String))
+            .sort(timestamp, maintain_order=True)
+        )
+        start = None
+        for time, kind in timer.iter_rows():
+            if kind == "start" and start is None:
+                start = time
+            elif kind in _TIMER_STOPS and start is not None:
+                segments.append({"start_time": start, "end_time": time})
+                start = None
+        if start is not None:
+            segments.append({"start_time": start, "end_time": None})
+
+    return pl.DataFrame(segments, schema=schema).with_columns(
+        duration=(pl.col("end_time") - pl.col("start_time")).dt.total_milliseconds()
+        / 1000
+    )
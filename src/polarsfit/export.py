@@ -0,0 +1,328 @@
+"""Export of records and laps to track formats other tools read."""
+
+import xml.etree.ElementTree as ET
+from typing import Any
+
+import polars as pl
+
+GPX_NAMESPACE = "http://www.topografix.com/GPX/1/1"
+TRACKPOINT_EXTENSION_NAMESPACE = (
+    "http://www.garmin.com/xmlschemas/TrackPointExtension/v1"
+)
+
+TCX_NAMESPACE = "http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2"
+
+# ISO 8601 UTC times as both formats expect them
+_TIME_FORMAT = "%Y-%m-%dT%H:%M:%SZ"
+
+# TrackPointExtension elements by record column
+_TRACKPOINT_EXTENSIONS = {"heart_rate": "hr", "cadence": "cad"}
+
+# Lap field numbers in the FIT profile. Where a field has an "enhanced"
+# 32-bit variant, it is listed first and preferred.
+LAP_FIELDS: dict[str, list[int]] = {
+    "start_time": [2],
+    "total_elapsed_time": [7],
+    "total_timer_time": [8],
+    "total_distance": [9],
+    "total_calories": [11],
+    "max_speed": [111, 14],
+    "avg_heart_rate": [15],
+    "max_heart_rate": [16],
+    "avg_cadence": [17],
+    "intensity": [23],
+    "lap_trigger": [24],
+}
+
+# TCX sports by FIT sport; every other sport is "Other"
+_TCX_SPORTS = {"running": "Running", "cycling": "Biking"}
+
+# TCX trigger methods by FIT lap trigger; every other trigger is "Manual"
+_TCX_TRIGGERS = {
+    "time": "Time",
+    "distance": "Distance",
+    "position_start": "Location",
+    "position_lap": "Location",
+    "position_waypoint": "Location",
+    "position_marked": "Location",
+}
+
+
+def _gpx(tag: str) -> str:
+    return f"{{{GPX_NAMESPACE}}}{tag}"
+
+
+def _gpxtpx(tag: str) -> str:
+    return f"{{{TRACKPOINT_EXTENSION_NAMESPACE}}}{tag}"
+
+
+def _tcx(tag: str) -> str:
+    return f"{{{TCX_NAMESPACE}}}{tag}"
+
+
+def to_gpx(records: pl.DataFrame) -> ET.ElementTree:
+    """
+    Build a GPX 1.1 track from decoded records with positions in degrees.
+
+    Records without a position are skipped. ``altitude`` and ``timestamp``
+    become ``ele`` and ``time``; heart rate and cadence go into a Garmin
+    TrackPointExtension, power into a plain ``power`` extension as written
+    by Strava and most other tools.
+    """
+    ET.register_namespace("", GPX_NAMESPACE)
+    ET.register_namespace("gpxtpx", TRACKPOINT_EXTENSION_NAMESPACE)
+    root = ET.Element(_gpx("gpx"), {"version": "1.1", "creator": "polarsfit"})
+    segment = ET.SubElement(ET.SubElement(root, _gpx("trk")), _gpx("trkseg"))
+    if not {"position_lat", "position_long"} <= set(records.columns):
+        return ET.ElementTree(root)
+
+    columns = [
+        column
+        for column in ["altitude", "heart_rate", "cadence", "power"]
+        if column in records.columns
+    ]
+    points = records.filter(
+        pl.col("position_lat").is_not_null()
+        & pl.col("position_long").is_not_null()
+    ).select(
+        "position_lat",
+        "position_long",
+        *columns,
+        *(
+            [pl.col("timestamp").dt.strftime(_TIME_FORMAT)]
+            if "timestamp" in records.columns
+            else []
+        ),
+    )
+    for point in points.iter_rows(named=True):
+        trackpoint = ET.SubElement(
+            segment,
+            _gpx("trkpt"),
+            {
+                "lat": f"{point['position_lat']:.7f}",
+                "lon": f"{point['position_long']:.7f}",
+            },
+        )
+        if point.get("altitude") is not None:
+            ET.SubElement(trackpoint, _gpx("ele")).text = (
+                f"{point['altitude']:.1f}"
+            )
+        if point.get("timestamp") is not None:
+            ET.SubElement(trackpoint, _gpx("time")).text = point["timestamp"]
+
+        extensions = {
+            element: point[column]
+            for column, element in _TRACKPOINT_EXTENSIONS.items()
+            if point.get(column) is not None
+        }
+        if point.get("power") is None and not extensions:
+            continue
+        container = ET.SubElement(trackpoint, _gpx("extensions"))
+        if point.get("power") is not None:
+            ET.SubElement(container, _gpx("power")).text = str(
+                round(point["power"])
+            )
+        if extensions:
+            extension = ET.SubElement(container, _gpxtpx("TrackPointExtension"))
+            for element, value in extensions.items():
+                ET.SubElement(extension, _gpxtpx(element)).text = str(
+                    round(value)
+                )
+    return ET.ElementTree(root)
+
+
+def to_geojson(records: pl.DataFrame, sport: str | None) -> dict[str, Any]:
+    """
+    Build a GeoJSON Feature with the track of decoded records as a LineString.
+
+    Positions are in degrees and written as ``[lon, lat]`` as RFC 7946
+    requires; records without a position are skipped. The properties hold
+    the sport, the distance of the last record and the first and last
+    record times, each null when the records don't have it.
+    """
+    if {"position_lat", "position_long"} <= set(records.columns):
+        points = records.filter(
+            pl.col("position_lat").is_not_null()
+            & pl.col("position_long").is_not_null()
+        )
+        coordinates = [
+            [lon, lat]
+            for lat, lon in points.select("position_lat", "position_long").iter_rows()
+        ]
+    else:
+        coordinates = []
+
+    times = (
+        records["timestamp"].drop_nulls()
+        if "timestamp" in records.columns
+        else pl.Series(dtype=pl.Datetime)
+    )
+    distance = records["distance"].max() if "distance" in records.columns else None
+    return {
+        "type": "Feature",
+        "geometry": {"type": "LineString", "coordinates": coordinates},
+        "properties": {
+            "sport": sport,
+            "total_distance": distance,
+            "start_time": times.min().strftime(_TIME_FORMAT) if len(times) else None,
+            "end_time": times.max().strftime(_TIME_FORMAT) if len(times) else None,
+        },
+    }
+
+
+def to_tcx(
+    records: pl.DataFrame, laps: pl.DataFrame, sport: str | None
+) -> ET.ElementTree:
+    """
+    Build a TCX activity from decoded records with a ``lap`` column.
+
+    ``records`` are numbered by lap as ``assign_laps`` does, with positions
+    in degrees; ``laps`` must use raw ``field_X`` column names, with scale,
+    enum and timestamp decoding applied. Records before the first lap are
+    part of it, records without a timestamp are skipped. Without laps, the
+    whole activity is a single lap.
+    """
+    ET.register_namespace("", TCX_NAMESPACE)
+    root = ET.Element(_tcx("TrainingCenterDatabase"))
+    activity = ET.SubElement(
+        ET.SubElement(root, _tcx("Activities")),
+        _tcx("Activity"),
+        {"Sport": _TCX_SPORTS.get(sport, "Other")},
+    )
+    identifier = ET.SubElement(activity, _tcx("Id"))
+
+    if "timestamp" in records.columns:
+        records = records.filter(pl.col("timestamp").is_not_null())
+    else:
+        records = records.clear()
+    summaries = _lap_summaries(records, laps)
+    if summaries.is_empty():
+        return ET.ElementTree(root)
+    identifier.text = summaries["start_time"][0].strftime(_TIME_FORMAT)
+
+    tracks = (
+        records.with_columns(
+            # Without lap messages all records are unassigned
+            pl.col("lap").fill_null(1).clip(1, summaries.height),
+            pl.col("timestamp").dt.strftime(_TIME_FORMAT),
+        ).partition_by("lap", as_dict=True)
+        if not records.is_empty()
+        else {}
+    )
+    for number, summary in enumerate(summaries.iter_rows(named=True), start=1):
+        lap = ET.SubElement(
+            activity,
+            _tcx("Lap"),
+            {"StartTime": summary["start_time"].strftime(_TIME_FORMAT)},
+        )
+        _lap_element(lap, summary)
+        track = tracks.get((number,))
+        if track is not None:
+            _track_element(ET.SubElement(lap, _tcx("Track")), track)
+    return ET.ElementTree(root)
+
+
+def _lap_summaries(records: pl.DataFrame, laps: pl.DataFrame) -> pl.DataFrame:
+    """One row per lap in the order ``assign_laps`` numbers them."""
+    columns = []
+    for name, numbers in LAP_FIELDS.items():
+        present = [
+            pl.col(f"field_{n}") for n in numbers if f"field_{n}" in laps.columns
+        ]
+        columns.append(
+            pl.coalesce(present).alias(name) if present else pl.lit(None).alias(name)
+        )
+    summaries = (
+        laps.select(columns)
+        .filter(pl.col("start_time").is_not_null())
+        .sort("start_time")
+    )
+    if not summaries.is_empty() or records.is_empty():
+        return summaries
+
+    # Without lap messages, the records make up one lap
+    start, end = records["timestamp"].min(), records["timestamp"].max()
+    return pl.DataFrame(
+        {
+            "start_time": [start],
+            "total_timer_time": [(end - start).total_seconds()],
+            "total_distance": [
+                records["distance"].max() if "distance" in records.columns else None
+            ],
+            "max_heart_rate": [
+                records["heart_rate"].max()
+                if "heart_rate" in records.columns
+                else None
+            ],
+        }
+    )
+
+
+def _lap_element(lap: ET.Element, summary: dict) -> None:
+    """Add the lap totals, in the element order the TCX schema requires."""
+    total_time = summary.get("total_timer_time")
+    if total_time is None:
+        total_time = summary.get("total_elapsed_time")
+    ET.SubElement(lap, _tcx("TotalTimeSeconds")).text = f"{total_time or 0:.3f}"
+    distance = summary.get("total_distance")
+    ET.SubElement(lap, _tcx("DistanceMeters")).text = f"{distance or 0:.2f}"
+    if summary.get("max_speed") is not None:
+        ET.SubElement(lap, _tcx("MaximumSpeed")).text = (
+            f"{summary['max_speed']:.3f}"
+        )
+    ET.SubElement(lap, _tcx("Calories")).text = str(
+        round(summary.get("total_calories") or 0)
+    )
+    for column, element in [
+        ("avg_heart_rate", "AverageHeartRateBpm"),
+        ("max_heart_rate", "MaximumHeartRateBpm"),
+    ]:
+        if summary.get(column):
+            value = ET.SubElement(ET.SubElement(lap, _tcx(element)), _tcx("Value"))
+            value.text = str(round(summary[column]))
+    ET.SubElement(lap, _tcx("Intensity")).text = (
+        "Resting" if summary.get("intensity") == "rest" else "Active"
+    )
+    if summary.get("avg_cadence") is not None:
+        ET.SubElement(lap, _tcx("Cadence")).text = str(
+            min(round(summary["avg_cadence"]), 254)
+        )
+    ET.SubElement(lap, _tcx("TriggerMethod")).text = _TCX_TRIGGERS.get(
+        summary.get("lap_trigger"), "Manual"
+    )
+
+
+def _track_element(track: ET.Element, records: pl.DataFrame) -> None:
+    """Add a trackpoint per record, in the element order the TCX schema requires."""
+    for point in records.iter_rows(named=True):
+        trackpoint = ET.SubElement(track, _tcx("Trackpoint"))
+        ET.SubElement(trackpoint, _tcx("Time")).text = point["timestamp"]
+        if (
+            point.get("position_lat") is not None
+            and point.get("position_long") is not None
+        ):
+            position = ET.SubElement(trackpoint, _tcx("Position"))
+            ET.SubElement(position, _tcx("LatitudeDegrees")).text = (
+                f"{point['position_lat']:.7f}"
+            )
+            ET.SubElement(position, _tcx("LongitudeDegrees")).text = (
+                f"{point['position_long']:.7f}"
+            )
+        if point.get("altitude") is not None:
+            ET.SubElement(trackpoint, _tcx("AltitudeMeters")).text = (
+                f"{point['altitude']:.1f}"
+            )
+        if point.get("distance") is not None:
+            ET.SubElement(trackpoint, _tcx("DistanceMeters")).text = (
+                f"{point['distance']:.2f}"
+            )
+        # TCX heart rates start at 1 bpm and cadences end at 254 rpm
+        if point.get("heart_rate"):
+            value = ET.SubElement(
+                ET.SubElement(trackpoint, _tcx("HeartRateBpm")), _tcx("Value")
+            )
+            value.text = str(round(point["heart_rate"]))
+        if point.get("cadence") is not None:
+            ET.SubElement(trackpoint, _tcx("Cadence")).text = str(
+                min(round(point["cadence"]), 254)
+            )
@@ -0,0 +1,698 @@
+"""Polars expressions implemented in the Rust extension."""
+
+from pathlib import Path
+from typing import Literal
+
+import polars as pl
+from polars.plugins import register_plugin_function
+
+# The expression plugins live in the compiled _internal module next to this file
+_PLUGIN_PATH = Path(__file__).parent
+
+
+def semicircle_to_degrees(expr: pl.Expr | str) -> pl.Expr:
+    """
+    Convert a semicircle position column to degrees.
+
+    Use this on data read with ``convert_gps=False`` to do the conversion
+    inside a (lazy) query instead of at read time. The FIT invalid value
+    ``0x7FFFFFFF`` becomes null.
+
+    Parameters
+    ----------
+    expr : polars.Expr | str
+        Integer column of semicircles, or its name.
+
+    Returns
+    -------
+    polars.Expr
+        ``Float64`` degrees.
+
+    Examples
+    --------
+    >>> raw = polarsfit.read_recordmesgs("workout.fit", convert_gps=False)
+    >>> raw.select(polarsfit.semicircle_to_degrees("position_lat"))
+    """
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_semicircle_to_degrees",
+        args=expr,
+        is_elementwise=True,
+    )
+
+
+def haversine_cumulative(lat: pl.Expr | str, lon: pl.Expr | str) -> pl.Expr:
+    """
+    Cumulative distance along a GPS track, in meters.
+
+    Distances between consecutive points use the haversine formula, so the
+    distance can be reconstructed when a device didn't record it or GPS is
+    trusted over a wheel sensor. The first row is 0. Rows without a position
+    keep the previous total, and the next position is measured from the last
+    valid one.
+
+    Parameters
+    ----------
+    lat : polars.Expr | str
+        Latitude in degrees.
+    lon : polars.Expr | str
+        Longitude in degrees.
+
+    Returns
+    -------
+    polars.Expr
+        ``Float64`` cumulative distance in meters.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("workout.fit")
+    >>> records.with_columns(
+    ...     gps_distance=polarsfit.haversine_cumulative("position_lat", "position_long")
+    ... )
+    """
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_haversine_cumulative",
+        args=[lat, lon],
+    )
+
+
+def grade(
+    distance: pl.Expr | str, altitude: pl.Expr | str, window: float = 10.0
+) -> pl.Expr:
+    """
+    Grade of the track in percent, from distance and altitude.
+
+    Each row's grade is the rise over the run from the nearest earlier point
+    at least ``window`` meters back, which smooths out altitude noise that
+    would blow up over short distances. Rows before the first full window,
+    and rows where either input is null, are null.
+
+    Parameters
+    ----------
+    distance : polars.Expr | str
+        Cumulative distance in meters.
+    altitude : polars.Expr | str
+        Altitude in meters.
+    window : float, default 10.0
+        Distance in meters the grade is measured over. Runs shorter than
+        1 m are never used.
+
+    Returns
+    -------
+    polars.Expr
+        ``Float64`` grade in percent.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("workout.fit")
+    >>> records.with_columns(grade=polarsfit.grade("distance", "altitude", window=25))
+    """
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_grade",
+        args=[distance, altitude],
+        kwargs={"window": float(window)},
+    )
+
+
+def normalized_power(power: pl.Expr | str, sample_hz: float = 1.0) -> pl.Expr:
+    """
+    Normalized Power of a power series, in watts.
+
+    The fourth root of the mean fourth power of the 30 second rolling average
+    of power. The first windows average the samples available so far, and
+    null samples are left out.
+
+    Parameters
+    ----------
+    power : polars.Expr | str
+        Power in watts.
+    sample_hz : float, default 1.0
+        Sampling rate of the series, which sets the window length in samples.
+
+    Returns
+    -------
+    polars.Expr
+        Scalar ``Float64``; null if there are no samples.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("ride.fit")
+    >>> records.select(polarsfit.normalized_power("power"))
+    """
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_normalized_power",
+        args=power,
+        kwargs={"sample_hz": float(sample_hz)},
+        returns_scalar=True,
+    )
+
+
+def elevation_gain(
+    altitude: pl.Expr | str, threshold: float = 3.0, *, descent: bool = False
+) -> pl.Expr:
+    """
+    Total ascent of an altitude series, in meters.
+
+    Changes are only counted once the altitude has moved at least
+    ``threshold`` meters from the last committed level, the way bike
+    computers keep GPS and barometer jitter from inflating the total.
+
+    Parameters
+    ----------
+    altitude : polars.Expr | str
+        Altitude in meters.
+    threshold : float, default 3.0
+        Smallest rise or drop in meters that is committed.
+    descent : bool, default False
+        Whether to total the committed drops instead, for the descent.
+
+    Returns
+    -------
+    polars.Expr
+        Scalar ``Float64``; null if there are no altitudes.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("ride.fit")
+    >>> records.select(polarsfit.elevation_gain("altitude", threshold=5.0))
+    """
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_elevation_gain",
+        args=altitude,
+        kwargs={"threshold": float(threshold), "descent": descent},
+        returns_scalar=True,
+    )
+
+
+def hr_zone(hr: pl.Expr | str, boundaries: list[float]) -> pl.Expr:
+    """
+    Heart-rate zone of each sample.
+
+    ``boundaries`` are the ascending lower bounds of zones 1 to N, so a value
+    is in the zone of the highest boundary it reaches. Values below the first
+    boundary are in zone 0, and nulls stay null.
+
+    Parameters
+    ----------
+    hr : polars.Expr | str
+        Heart rate in bpm.
+    boundaries : list[float]
+        Strictly ascending lower bounds of the zones, e.g. from a threshold
+        or max heart rate test.
+
+    Returns
+    -------
+    polars.Expr
+        ``UInt8`` zone index.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("workout.fit")
+    >>> zones = polarsfit.hr_zone("heart_rate", [110, 130, 150, 165, 180])
+    >>> records.group_by(zones.alias("zone")).len().sort("zone")
+    """
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_hr_zone",
+        args=hr,
+        kwargs={"boundaries": [float(b) for b in boundaries]},
+        is_elementwise=True,
+    )
+
+
+def pace(
+    speed: pl.Expr | str,
+    unit: Literal["min_per_km", "min_per_mile"] = "min_per_km",
+) -> pl.Expr:
+    """
+    Pace from speed, in minutes per kilometer or mile.
+
+    Samples without a positive speed, e.g. while stopped, are null rather
+    than infinite.
+
+    Parameters
+    ----------
+    speed : polars.Expr | str
+        Speed in m/s.
+    unit : {"min_per_km", "min_per_mile"}, default "min_per_km"
+        Distance unit of the pace.
+
+    Returns
+    -------
+    polars.Expr
+        ``Float64`` decimal minutes per unit, e.g. 5.5 for 5:30 min/km.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("run.fit")
+    >>> records.with_columns(pace=polarsfit.pace("speed"))
+    """
+    if unit not in ("min_per_km", "min_per_mile"):
+        raise ValueError(
+            f"unit must be 'min_per_km' or 'min_per_mile', got {unit!r}"
+        )
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_pace",
+        args=speed,
+        kwargs={"unit": unit},
+        is_elementwise=True,
+    )
+
+
+def rolling_smooth(
+    expr: pl.Expr | str,
+    window: int,
+    method: Literal["mean", "median", "ewma"] = "mean",
+    alpha: float | None = None,
+) -> pl.Expr:
+    """
+    Smooth a noisy sensor channel such as power, cadence or altitude.
+
+    ``mean`` and ``median`` use a window of ``window`` samples centered on
+    each row, which shrinks at the start and end of the series; nulls are
+    skipped. ``ewma`` is an exponentially weighted moving average that runs
+    forward through the series and carries its value over nulls.
+
+    Parameters
+    ----------
+    expr : polars.Expr | str
+        Numeric column to smooth.
+    window : int
+        Window length in samples.
+    method : {"mean", "median", "ewma"}, default "mean"
+        Smoothing method.
+    alpha : float | None, optional
+        Smoothing factor of ``ewma`` in (0, 1]. Defaults to
+        ``2 / (window + 1)``.
+
+    Returns
+    -------
+    polars.Expr
+        ``Float64`` smoothed values, the same length as the input.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("ride.fit")
+    >>> records.with_columns(power_3s=polarsfit.rolling_smooth("power", 3))
+    """
+    if method not in ("mean", "median", "ewma"):
+        raise ValueError(
+            f"method must be 'mean', 'median' or 'ewma', got {method!r}"
+        )
+    if window < 1:
+        raise ValueError(f"window must be positive, got {window}")
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_rolling_smooth",
+        args=expr,
+        kwargs={
+            "window": int(window),
+            "method": method,
+            "alpha": None if alpha is None else float(alpha),
+        },
+    )
+
+
+def is_moving(speed: pl.Expr | str, min_speed: float = 0.5) -> pl.Expr:
+    """
+    Flag the samples where the athlete is moving.
+
+    Parameters
+    ----------
+    speed : polars.Expr | str
+        Speed in m/s.
+    min_speed : float, default 0.5
+        Speed in m/s a sample has to exceed to count as moving.
+
+    Returns
+    -------
+    polars.Expr
+        ``Boolean``; null where the speed is null.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("run.fit")
+    >>> records.filter(polarsfit.is_moving("speed"))
+    """
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_is_moving",
+        args=speed,
+        kwargs={"min_speed": float(min_speed)},
+        is_elementwise=True,
+    )
+
+
+def moving_time(
+    timestamp: pl.Expr | str,
+    speed: pl.Expr | str,
+    min_speed: float = 0.5,
+    max_gap: float = 10.0,
+) -> pl.Expr:
+    """
+    Total moving time, in seconds.
+
+    Unlike elapsed time, only the intervals between consecutive samples that
+    end in a moving sample (see ``is_moving``) are counted. Intervals longer
+    than ``max_gap``, e.g. while the device was paused or auto-paused, are
+    never counted.
+
+    Parameters
+    ----------
+    timestamp : polars.Expr | str
+        ``Datetime`` timestamps, or numeric seconds.
+    speed : polars.Expr | str
+        Speed in m/s.
+    min_speed : float, default 0.5
+        Speed in m/s a sample has to exceed to count as moving.
+    max_gap : float, default 10.0
+        Longest interval in seconds between samples that is counted.
+
+    Returns
+    -------
+    polars.Expr
+        Scalar ``Float64`` seconds.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("run.fit")
+    >>> records.select(polarsfit.moving_time("timestamp", "speed"))
+    """
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_moving_time",
+        args=[timestamp, speed],
+        kwargs={"min_speed": float(min_speed), "max_gap": float(max_gap)},
+        returns_scalar=True,
+    )
+
+
+def simplify_track(
+    lat: pl.Expr | str, lon: pl.Expr | str, tolerance_m: float = 5.0
+) -> pl.Expr:
+    """
+    Flag the points of a GPS track to keep when simplifying it.
+
+    Uses the Douglas-Peucker algorithm: the points kept describe the track
+    such that no dropped point is more than ``tolerance_m`` meters from it,
+    which cuts a 1 Hz recording to a fraction of its points for maps and
+    storage. The first and last positions are always kept, straight
+    stretches shrink to their ends and corners survive.
+
+    Parameters
+    ----------
+    lat : polars.Expr | str
+        Latitude in degrees.
+    lon : polars.Expr | str
+        Longitude in degrees.
+    tolerance_m : float, default 5.0
+        Largest distance in meters a dropped point may lie off the
+        simplified track.
+
+    Returns
+    -------
+    polars.Expr
+        ``Boolean`` keep-mask; false where the position is null.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("ride.fit")
+    >>> track = records.filter(
+    ...     polarsfit.simplify_track("position_lat", "position_long", 10.0)
+    ... )
+    """
+    if tolerance_m < 0:
+        raise ValueError(f"tolerance_m must not be negative, got {tolerance_m}")
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_simplify_track",
+        args=[lat, lon],
+        kwargs={"tolerance": float(tolerance_m)},
+    )
+
+
+def wprime_balance(
+    timestamp: pl.Expr | str, power: pl.Expr | str, cp: float, wprime: float
+) -> pl.Expr:
+    """
+    Remaining anaerobic work capacity (W'bal), in joules.
+
+    Implements the differential form of Skiba's W'bal model: power above
+    critical power ``cp`` depletes ``wprime`` by the excess work, power at or
+    below it lets the balance recover exponentially towards ``wprime``, the
+    faster the further below ``cp``. Each sample's power applies over the
+    interval since the previous sample, so any recording rate works. The
+    balance starts full; samples without a timestamp or power carry it
+    forward.
+
+    Parameters
+    ----------
+    timestamp : polars.Expr | str
+        ``Datetime`` timestamps, or numeric seconds.
+    power : polars.Expr | str
+        Power in W.
+    cp : float
+        Critical power in W.
+    wprime : float
+        Anaerobic work capacity W' in J, e.g. 20000.
+
+    Returns
+    -------
+    polars.Expr
+        ``Float64`` W'bal in J; negative when the athlete outperformed the
+        model, which suggests ``cp`` or ``wprime`` are set too low.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("ride.fit")
+    >>> records.with_columns(
+    ...     wbal=polarsfit.wprime_balance("timestamp", "power", 280, 22000)
+    ... )
+    """
+    if wprime <= 0:
+        raise ValueError(f"wprime must be positive, got {wprime}")
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_wprime_balance",
+        args=[timestamp, power],
+        kwargs={"cp": float(cp), "wprime": float(wprime)},
+    )
+
+
+def interpolate_gps(expr: pl.Expr | str, max_gap: int = 30) -> pl.Expr:
+    """
+    Fill short gaps in a GPS position column by linear interpolation.
+
+    Devices lose their fix in tunnels or under trees, leaving runs of null
+    positions. Runs of at most ``max_gap`` rows between two valid fixes are
+    filled in evenly, in degrees, so the track is continuous on a map.
+    Longer runs, and nulls before the first or after the last fix, stay
+    null. Apply it to latitude and longitude alike; longitudes crossing the
+    antimeridian are interpolated the short way round.
+
+    Parameters
+    ----------
+    expr : polars.Expr | str
+        Latitude or longitude in degrees.
+    max_gap : int, default 30
+        Longest run of missing positions, in rows, that is filled.
+
+    Returns
+    -------
+    polars.Expr
+        ``Float64`` degrees.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("ride.fit")
+    >>> records.with_columns(
+    ...     polarsfit.interpolate_gps("position_lat"),
+    ...     polarsfit.interpolate_gps("position_long"),
+    ... )
+    """
+    if max_gap < 0:
+        raise ValueError(f"max_gap must not be negative, got {max_gap}")
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_interpolate_gps",
+        args=expr,
+        kwargs={"max_gap": int(max_gap)},
+    )
+
+
+def bearing(lat: pl.Expr | str, lon: pl.Expr | str) -> pl.Expr:
+    """
+    Compass heading of travel between consecutive GPS points, in degrees.
+
+    The heading is the initial great-circle course from the previous
+    position to each position: 0 is north, 90 east, 180 south and 270 west.
+    Rows without a position are skipped and the next one is measured from
+    the last valid position, so steps across the antimeridian still point
+    the right way. Useful for wind analysis and detecting turns.
+
+    Parameters
+    ----------
+    lat : polars.Expr | str
+        Latitude in degrees.
+    lon : polars.Expr | str
+        Longitude in degrees.
+
+    Returns
+    -------
+    polars.Expr
+        ``Float64`` degrees from 0 to 360; null for the first position,
+        rows without a position and positions that didn't move.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("ride.fit")
+    >>> records.with_columns(
+    ...     heading=polarsfit.bearing("position_lat", "position_long")
+    ... )
+    """
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_bearing",
+        args=[lat, lon],
+    )
+
+
+def vertical_speed(
+    altitude: pl.Expr | str, timestamp: pl.Expr | str, window: float = 30.0
+) -> pl.Expr:
+    """
+    Vertical speed (VAM), in meters climbed per hour.
+
+    Measured from the nearest earlier sample at least ``window`` seconds
+    back, which smooths out altitude noise; descents are negative. Samples
+    whose window reaches back over a pause of more than twice its length
+    are null rather than averaging across the gap.
+
+    Parameters
+    ----------
+    altitude : polars.Expr | str
+        Altitude in meters.
+    timestamp : polars.Expr | str
+        ``Datetime`` timestamps, or numeric seconds.
+    window : float, default 30.0
+        Seconds the climb is measured over.
+
+    Returns
+    -------
+    polars.Expr
+        ``Float64`` m/h; null until a full window has been covered and
+        where altitude or timestamp is null.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("climb.fit")
+    >>> records.with_columns(vam=polarsfit.vertical_speed("altitude", "timestamp"))
+    """
+    if window <= 0:
+        raise ValueError(f"window must be positive, got {window}")
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_vertical_speed",
+        args=[altitude, timestamp],
+        kwargs={"window": float(window)},
+    )
+
+
+def find_peaks(
+    expr: pl.Expr | str, min_prominence: float = 0.0, min_distance: int = 1
+) -> pl.Expr:
+    """
+    Flag the peaks of a signal, e.g. to count breaths, strides or intervals.
+
+    A peak is a local maximum standing out by at least ``min_prominence``:
+    its height above the higher of the lowest points on either side before
+    the signal rises above it again. Of peaks closer than ``min_distance``
+    rows, only the highest is kept. A flat top peaks at its middle.
+
+    Parameters
+    ----------
+    expr : polars.Expr | str
+        Numeric signal, e.g. respiration rate or vertical oscillation.
+    min_prominence : float, default 0.0
+        Smallest prominence of a peak, in the units of the signal.
+    min_distance : int, default 1
+        Fewest rows between two peaks.
+
+    Returns
+    -------
+    polars.Expr
+        ``Boolean`` mask of the peaks; false where the signal is null.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("intervals.fit")
+    >>> peaks = polarsfit.find_peaks("heart_rate", min_prominence=15, min_distance=60)
+    >>> records.select(peaks.sum())  # number of intervals
+    """
+    if min_distance < 1:
+        raise ValueError(f"min_distance must be positive, got {min_distance}")
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_find_peaks",
+        args=expr,
+        kwargs={
+            "min_prominence": float(min_prominence),
+            "min_distance": int(min_distance),
+        },
+    )
+
+
+def grade_adjusted_pace(
+    speed: pl.Expr | str,
+    grade: pl.Expr | str,
+    unit: Literal["min_per_km", "min_per_mile"] = "min_per_km",
+) -> pl.Expr:
+    """
+    Grade-adjusted pace (GAP), the flat-ground pace of equal effort.
+
+    The energy cost of running at each grade follows Minetti et al. (2002):
+    climbing costs more than the flat, so GAP is faster than the actual
+    pace; gentle descents cost less, so GAP is slower, until below about
+    -20% braking makes steeper descents costlier again. Grades beyond the
+    measured ±45% count as ±45%.
+
+    Parameters
+    ----------
+    speed : polars.Expr | str
+        Speed in m/s.
+    grade : polars.Expr | str
+        Grade in percent, e.g. from ``grade``.
+    unit : {"min_per_km", "min_per_mile"}, default "min_per_km"
+        Distance unit of the pace.
+
+    Returns
+    -------
+    polars.Expr
+        ``Float64`` decimal minutes per unit; null where either input is
+        null or the speed isn't positive.
+
+    Examples
+    --------
+    >>> records = polarsfit.read_recordmesgs("trail_run.fit")
+    >>> records.with_columns(
+    ...     gap=polarsfit.grade_adjusted_pace(
+    ...         "speed", polarsfit.grade("distance", "altitude", window=25)
+    ...     )
+    ... )
+    """
+    if unit not in ("min_per_km", "min_per_mile"):
+        raise ValueError(
+            f"unit must be 'min_per_km' or 'min_per_mile', got {unit!r}"
+        )
+    return register_plugin_function(
+        plugin_path=_PLUGIN_PATH,
+        function_name="pl_grade_adjusted_pace",
+        args=[speed, grade],
+        kwargs={"unit": unit},
+        is_elementwise=True,
+    )
@@ -7,7 +7,7 @@ with the official FIT protocol specification.
 """
 
 from enum import Enum
-from typing import Dict, List
+from typing import Dict, List, Optional
 
 try:
     from garmin_fit_sdk import Profile
@@ -44,14 +44,73 @@ MESSAGE_TYPE_MAP = {
 }
 
 
-def get_field_mapping(message_type: MessageType) -> Dict[int, str]:
+# Global message numbers by message type name without underscores, which
+# matches both the profile names ("file_id") and the names get_message_types
+# returns ("fileid")
+_MESSAGE_NUMBERS: Dict[str, int] = {
+    name.replace("_", "").lower(): mesg_num
+    for name, mesg_num in Profile["mesg_num"].items()
+    if isinstance(mesg_num, int)
+}
+
+# Other names of message types, e.g. "spo2" for the pulse oximeter readings
+_MESSAGE_ALIASES = {"spo2": "spo2data"}
+_MESSAGE_NUMBERS.update(
+    (alias, _MESSAGE_NUMBERS[name])
+    for alias, name in _MESSAGE_ALIASES.items()
+    if name in _MESSAGE_NUMBERS
+)
+
+
+def get_message_number(message_type: str) -> Optional[int]:
+    """
+    Get the global message number of a message type.
+
+    Parameters
+    ----------
+    message_type : str
+        Message type name, as a ``MessageType``, a profile name such as
+        ``"device_info"``, a name returned by ``get_message_types`` such as
+        ``"deviceinfo"``, or ``"message_<number>"``
+
+    Returns
+    -------
+    Optional[int]
+        The global message number, or None if the name is unknown
+    """
+    name = str(getattr(message_type, "value", message_type)).lower()
+    number = name.removeprefix("message_")
+    if name.startswith("message_") and number.isdigit():
+        return int(number)
+    return _MESSAGE_NUMBERS.get(name.replace("_", ""))
+
+
+def _profile_fields(message_type: str) -> Dict[int, dict]:
+    """Profile field definitions of a message type by field number."""
+    mesg_num = get_message_number(message_type)
+    if mesg_num is None:
+        raise ValueError(f"Unsupported message type: {message_type}")
+    return _message_fields(mesg_num)
+
+
+def _message_fields(mesg_num: int) -> Dict[int, dict]:
+    message_def = Profile["messages"].get(mesg_num, {})
+    return {
+        int(field_num): field_info
+        for field_num, field_info in message_def.get("fields", {}).items()
+        if isinstance(field_info, dict)
+    }
+
+
+def get_field_mapping(message_type: MessageType | str) -> Dict[int, str]:
     """
     Get field mapping for a specific message type using the official Garmin FIT SDK.
 
     Parameters
     ----------
-    message_type : MessageType
-        The message type to get field mappings for
+    message_type : MessageType | str
+        The message type to get field mappings for. Any message type of the
+        FIT profile is supported, see ``get_message_number``.
 
     Returns
     -------
@@ -63,32 +122,72 @@ def get_field_mapping(message_type: MessageType) -> Dict[int, str]:
     ValueError
         If the message type is not supported
     """
-    if message_type not in MESSAGE_TYPE_MAP:
-        raise ValueError(f"Unsupported message type: {message_type}")
+    return {
+        field_num: field_info["name"]
+        for field_num, field_info in _profile_fields(message_type).items()
+        if "name" in field_info
+    }
 
-    mesg_num = MESSAGE_TYPE_MAP[message_type]
 
-    # Get the message definition from the SDK Profile
-    try:
-        messages = Profile["messages"]
-        if mesg_num not in messages:
-            return {}
+def get_field_units(message_type: MessageType | str) -> Dict[int, str]:
+    """
+    Get the units of the fields of a message type from the official Garmin FIT SDK.
 
-        message_def = messages[mesg_num]
-        if "fields" not in message_def:
-            return {}
+    Parameters
+    ----------
+    message_type : MessageType | str
+        The message type to get field units for. Any message type of the
+        FIT profile is supported, see ``get_message_number``.
+
+    Returns
+    -------
+    Dict[int, str]
+        Dictionary mapping field numbers to unit strings, for fields with units
+
+    Raises
+    ------
+    ValueError
+        If the message type is not supported
+    """
+    field_units = {}
+    for field_num, field_info in _profile_fields(message_type).items():
+        units = field_info.get("units")
+        # Fields with components list the units of each component
+        if isinstance(units, list):
+            units = units[0] if units else None
+        if units:
+            field_units[field_num] = units
+
+    return field_units
 
-        # Extract field mappings
-        field_mapping = {}
-        for field_num, field_info in message_def["fields"].items():
-            if isinstance(field_info, dict) and "name" in field_info:
-                field_mapping[int(field_num)] = field_info["name"]
 
-        return field_mapping
+def get_profile_field_info(mesg_num: int, field_numbers: List[int]) -> List[tuple]:
+    """
+    Look up the profile names and units of fields of a message.
+
+    Parameters
+    ----------
+    mesg_num : int
+        Global message number of the message type
+    field_numbers : List[int]
+        Field definition numbers to look up
 
-    except (KeyError, TypeError, ValueError):
-        # Return empty dict if there's any issue accessing the SDK data
-        return {}
+    Returns
+    -------
+    List[tuple]
+        ``(number, name, units)`` per field. Fields missing from the profile
+        are named ``field_<number>``; fields without units have ``""``.
+    """
+    profile_fields = _message_fields(mesg_num)
+    info = []
+    for number in field_numbers:
+        field_info = profile_fields.get(number, {})
+        units = field_info.get("units")
+        # Fields with components list the units of each component
+        if isinstance(units, list):
+            units = units[0] if units else None
+        info.append((number, field_info.get("name", f"field_{number}"), units or ""))
+    return info
 
 
 def get_available_message_types() -> List[str]:
@@ -0,0 +1,112 @@
+"""Assignment of record messages to the laps they were recorded in."""
+
+import polars as pl
+
+# Lap field numbers in the FIT profile
+LAP_START_TIME = 2
+LAP_TOTAL_ELAPSED_TIME = 7
+
+
+def assign_laps(
+    records: pl.DataFrame,
+    laps: pl.DataFrame,
+    fields: dict[str, int] | None = None,
+) -> pl.DataFrame:
+    """
+    Add a ``lap`` column to decoded records with a ``timestamp`` column.
+
+    ``laps`` must use raw ``field_X`` column names, with scale and timestamp
+    decoding applied. See ``read_records_with_laps`` for the rule. Each lap
+    field number of ``fields`` is added as ``lap_<name>`` with the value of
+    the record's lap, null for lap 0 and for fields the laps don't have.
+    """
+    fields = fields or {}
+    aggregates = [
+        (
+            pl.col(f"field_{number}")
+            if f"field_{number}" in laps.columns
+            else pl.lit(None, dtype=pl.Float64)
+        ).alias(f"lap_{name}")
+        for name, number in fields.items()
+    ]
+    start = f"field_{LAP_START_TIME}"
+    elapsed = f"field_{LAP_TOTAL_ELAPSED_TIME}"
+    if records.is_empty() or start not in laps.columns:
+        return records.with_columns(
+            pl.lit(None, dtype=pl.UInt32).alias("lap"),
+            *(pl.lit(None, dtype=pl.Float64).alias(f"lap_{name}") for name in fields),
+        )
+
+    bounds = (
+        laps.select(
+            pl.col(start).alias("lap_start"),
+            *aggregates,
+            (
+                pl.col(elapsed) * 1000
+                if elapsed in laps.columns
+                else pl.lit(None, dtype=pl.Float64)
+            ).alias("elapsed_ms"),
+        )
+        .filter(pl.col("lap_start").is_not_null())
+        .sort("lap_start")
+        .with_row_index("lap", offset=1)
+        .with_columns(
+            lap_end=pl.coalesce(
+                pl.col("lap_start")
+                + pl.duration(milliseconds=pl.col("elapsed_ms").cast(pl.Int64)),
+                # Without an elapsed time, a lap lasts until the next one starts
+                pl.col("lap_start").shift(-1),
+                pl.col("lap_start"),
+            )
+        )
+        .drop("elapsed_ms")
+    )
+
+    order = "__record_order"
+    timed = (
+        records.with_row_index(order)
+        .select(order, "timestamp")
+        .filter(pl.col("timestamp").is_not_null())
+        .sort("timestamp")
+    )
+    previous = timed.join_asof(
+        bounds, left_on="timestamp", right_on="lap_start", strategy="backward"
+    )
+    following = timed.join_asof(
+        bounds.select(
+            pl.col("lap").alias("next_lap"), pl.col("lap_start").alias("next_start")
+        ),
+        left_on="timestamp",
+        right_on="next_start",
+        strategy="forward",
+    )
+    t = pl.col("timestamp")
+    lap = (
+        previous.join(following.drop("timestamp"), on=order)
+        .select(
+            order,
+            pl.when(pl.col("lap").is_null())
+            .then(0)
+            .when((t < pl.col("lap_end")) | pl.col("next_lap").is_null())
+            .then(pl.col("lap"))
+            .when(t - pl.col("lap_end") <= pl.col("next_start") - t)
+            .then(pl.col("lap"))
+            .otherwise(pl.col("next_lap"))
+            .cast(pl.UInt32)
+            .alias("lap"),
+        )
+    )
+
+    records = (
+        records.with_row_index(order)
+        .join(lap, on=order, how="left", maintain_order="left")
+        .drop(order)
+    )
+    if not fields:
+        return records
+    return records.join(
+        bounds.select("lap", *(f"lap_{name}" for name in fields)),
+        on="lap",
+        how="left",
+        maintain_order="left",
+    )
@@ -0,0 +1,57 @@
+"""Wall-clock time of activities from the activity message's local timestamp."""
+
+import polars as pl
+
+# Activity field numbers in the FIT profile
+ACTIVITY_LOCAL_TIMESTAMP = 5
+ACTIVITY_TIMESTAMP = 253
+
+# Largest offset in seconds of a real timezone, from UTC-12 to UTC+14
+_MAX_UTC_OFFSET = 14 * 3600
+
+
+def utc_offset(activity: pl.DataFrame | None) -> int | None:
+    """
+    Seconds the recording timezone is ahead of UTC, from the activity message.
+
+    ``activity`` must use raw ``field_X`` column names and raw integer
+    timestamps. The offset is ``local_timestamp - timestamp`` of the first
+    activity message with both; it is None without one, or when the
+    difference is larger than any real timezone, e.g. an unset local clock.
+    """
+    timestamp = f"field_{ACTIVITY_TIMESTAMP}"
+    local_timestamp = f"field_{ACTIVITY_LOCAL_TIMESTAMP}"
+    if activity is None or not {timestamp, local_timestamp} <= set(activity.columns):
+        return None
+    both = activity.select(
+        pl.col(local_timestamp).cast(pl.Int64) - pl.col(timestamp).cast(pl.Int64)
+    ).drop_nulls()
+    if both.is_empty():
+        return None
+    offset = both.item(0, 0)
+    return offset if abs(offset) <= _MAX_UTC_OFFSET else None
+
+
+def with_local_time(
+    records: pl.DataFrame, timestamp: str, offset: int | None
+) -> pl.DataFrame:
+    """
+    Add a ``local_time`` column of ``timestamp`` shifted by ``offset`` seconds.
+
+    Decoded UTC timestamps become timezone-naive wall-clock datetimes; raw
+    integer timestamps stay integer seconds. ``local_time`` is null when the
+    offset is None, and is not added without a ``timestamp`` column.
+    """
+    if timestamp not in records.columns:
+        return records
+    dtype = records.schema[timestamp]
+    decoded = isinstance(dtype, pl.Datetime)
+    if offset is None:
+        dtype = pl.Datetime(dtype.time_unit) if decoded else pl.Int64
+        return records.with_columns(local_time=pl.lit(None, dtype))
+    if decoded:
+        local = pl.col(timestamp).dt.replace_time_zone(None)
+        local += pl.duration(seconds=offset)
+    else:
+        local = pl.col(timestamp).cast(pl.Int64) + offset
+    return records.with_columns(local_time=local)
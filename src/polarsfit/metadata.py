@@ -0,0 +1,112 @@
+"""File and device identification from file_id and device_info messages."""
+
+from typing import Any
+
+import polars as pl
+from garmin_fit_sdk import Profile
+
+from polarsfit._internal import decode_manufacturer
+
+# Manufacturers whose product ids are listed in the garmin_product table
+_GARMIN_PRODUCT_MANUFACTURERS = {"garmin", "dynastream", "dynastream_oem", "tacx"}
+
+# device_info field numbers by column
+DEVICE_INFO_FIELDS: dict[str, int] = {
+    "device_index": 0,
+    "device_type": 1,
+    "manufacturer": 2,
+    "serial_number": 3,
+    "product": 4,
+    "software_version": 5,
+    "product_name": 27,
+}
+
+_DEVICE_INFO_DTYPES: dict[str, pl.DataType] = {
+    "device_index": pl.UInt32(),
+    "device_type": pl.UInt32(),
+    "manufacturer": pl.String(),
+    "serial_number": pl.UInt32(),
+    "product": pl.UInt32(),
+    "software_version": pl.Float64(),
+    "product_name": pl.String(),
+}
+
+
+def product_name(manufacturer: str | None, product: int | None) -> str | None:
+    """Look up the profile name of a product id, if the profile lists one."""
+    if product is None:
+        return None
+    if manufacturer in _GARMIN_PRODUCT_MANUFACTURERS:
+        table = Profile["types"].get("garmin_product", {})
+    elif manufacturer == "favero_electronics":
+        table = Profile["types"].get("favero_product", {})
+    else:
+        return None
+    return table.get(str(product), table.get(product))
+
+
+def manufacturer_name(manufacturer: str | None) -> str | None:
+    """Name ids the profile lacks, which enum decoding keeps as numeric strings."""
+    if manufacturer is not None and manufacturer.isdigit():
+        return decode_manufacturer(int(manufacturer))
+    return manufacturer
+
+
+def _first(df: pl.DataFrame | None, column: str) -> Any:
+    if df is None or column not in df.columns:
+        return None
+    values = df[column].drop_nulls()
+    return values[0] if len(values) else None
+
+
+def file_metadata(
+    file_id: pl.DataFrame | None, device_info: pl.DataFrame | None
+) -> dict[str, Any]:
+    """
+    Build the metadata dict from decoded file_id and device_info messages.
+
+    Both frames must use raw ``field_X`` column names, with scale, offset,
+    enum and timestamp decoding applied.
+    """
+    manufacturer = manufacturer_name(_first(file_id, "field_1"))
+    product = _first(file_id, "field_2")
+
+    devices = _device_table(device_info)
+    # Device index 0 is the device that created the file
+    creator = devices.filter(pl.col("device_index") == 0)
+
+    return {
+        "file_type": _first(file_id, "field_0"),
+        "manufacturer": manufacturer,
+        "product": product,
+        "product_name": _first(file_id, "field_8")
+        or product_name(manufacturer, product),
+        "serial_number": _first(file_id, "field_3"),
+        "time_created": _first(file_id, "field_4"),
+        "software_version": _first(creator, "software_version"),
+        "devices": devices,
+    }
+
+
+def _device_table(device_info: pl.DataFrame | None) -> pl.DataFrame:
+    """One row per distinct device, in order of first appearance."""
+    if device_info is None or device_info.is_empty():
+        return pl.DataFrame(schema=_DEVICE_INFO_DTYPES)
+    devices = device_info.select(
+        pl.col(f"field_{number}").cast(_DEVICE_INFO_DTYPES[name]).alias(name)
+        if f"field_{number}" in device_info.columns
+        else pl.lit(None, dtype=_DEVICE_INFO_DTYPES[name]).alias(name)
+        for name, number in DEVICE_INFO_FIELDS.items()
+    )
+    devices = devices.unique(maintain_order=True).with_columns(
+        pl.col("manufacturer").map_elements(manufacturer_name, return_dtype=pl.String)
+    )
+    names = [
+        name or product_name(manufacturer, product)
+        for name, manufacturer, product in devices.select(
+            "product_name", "manufacturer", "product"
+        ).iter_rows()
+    ]
+    return devices.with_columns(
+        pl.Series("product_name", names, dtype=pl.String)
+    )
@@ -0,0 +1,56 @@
+"""Resampling of record messages to a fixed time grid."""
+
+from datetime import timedelta
+from typing import Literal
+
+import polars as pl
+
+# Record fields that only ever grow, so a window keeps its last value
+CUMULATIVE_RECORD_FIELDS = {
+    "distance",
+    "accumulated_power",
+    "total_cycles",
+    "calories",
+}
+
+Aggregation = Literal["mean", "median", "min", "max", "first", "last"]
+
+
+def resample(
+    records: pl.DataFrame, interval_seconds: float, agg: Aggregation = "mean"
+) -> pl.DataFrame:
+    """
+    Resample decoded records with a ``timestamp`` column to a regular grid.
+
+    Numeric columns are aggregated with ``agg``, cumulative ones and all
+    other columns keep the last value in each window. Windows without any
+    records are kept as rows of nulls so the grid has no holes.
+    """
+    if agg not in ("mean", "median", "min", "max", "first", "last"):
+        raise ValueError(
+            "agg must be one of 'mean', 'median', 'min', 'max', 'first' or "
+            f"'last', got {agg!r}"
+        )
+    if interval_seconds <= 0:
+        raise ValueError(
+            f"interval_seconds must be positive, got {interval_seconds}"
+        )
+    if "timestamp" not in records.columns:
+        raise ValueError("records have no timestamp column to resample on")
+
+    every = timedelta(seconds=interval_seconds)
+    aggregations = [
+        getattr(pl.col(name), agg)()
+        if dtype.is_numeric() and name not in CUMULATIVE_RECORD_FIELDS
+        else pl.col(name).last()
+        for name, dtype in records.schema.items()
+        if name != "timestamp"
+    ]
+
+    return (
+        records.filter(pl.col("timestamp").is_not_null())
+        .sort("timestamp")
+        .group_by_dynamic("timestamp", every=every)
+        .agg(aggregations)
+        .upsample("timestamp", every=every)
+    )
@@ -0,0 +1,51 @@
+"""Partitioning of record messages by the session they were recorded in."""
+
+import polars as pl
+
+# Session field numbers in the FIT profile
+SESSION_START_TIME = 2
+SESSION_SPORT = 5
+
+
+def split_by_session(
+    records: pl.DataFrame, sessions: pl.DataFrame
+) -> list[tuple[str, pl.DataFrame]]:
+    """
+    Split decoded records with a ``timestamp`` column into one frame per session.
+
+    ``sessions`` must use raw ``field_X`` column names, with enum and
+    timestamp decoding applied. See ``split_by_session`` of the package for
+    the rule.
+    """
+    start = f"field_{SESSION_START_TIME}"
+    sport = f"field_{SESSION_SPORT}"
+    if start not in sessions.columns:
+        return []
+    starts = (
+        sessions.select(
+            pl.col(start).alias("start"),
+            (
+                pl.col(sport).cast(pl.String)
+                if sport in sessions.columns
+                else pl.lit(None, dtype=pl.String)
+            ).alias("sport"),
+        )
+        .drop_nulls("start")
+        .sort("start", maintain_order=True)
+    )
+    if starts.is_empty():
+        return []
+
+    if "timestamp" in records.columns:
+        records = records.filter(pl.col("timestamp").is_not_null())
+        index = starts["start"].search_sorted(records["timestamp"], side="right")
+    else:
+        records = records.clear()
+        index = pl.Series([], dtype=pl.UInt32)
+    # Each record belongs to the last session started before it, or to the
+    # first session if it precedes them all
+    index = (index.cast(pl.Int64) - 1).clip(lower_bound=0)
+    return [
+        (name or "generic", records.filter(index == i))
+        for i, name in enumerate(starts["sport"])
+    ]
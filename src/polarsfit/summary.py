@@ -0,0 +1,181 @@
+"""Tidy per-session and per-length summaries built from FIT messages."""
+
+import polars as pl
+
+# Session field numbers in the FIT profile, by summary column. Where a
+# field has an "enhanced" 32-bit variant, it is listed first and preferred.
+SESSION_SUMMARY_FIELDS: dict[str, list[int]] = {
+    "sport": [5],
+    "sub_sport": [6],
+    "start_time": [2],
+    "total_elapsed_time": [7],
+    "total_timer_time": [8],
+    "total_distance": [9],
+    "total_calories": [11],
+    "avg_speed": [124, 14],
+    "max_speed": [125, 15],
+    "avg_heart_rate": [16],
+    "max_heart_rate": [17],
+    "avg_power": [20],
+    "max_power": [21],
+    "total_ascent": [22],
+    "total_descent": [23],
+    "pool_length": [44],
+}
+
+# Length field numbers in the FIT profile, by column of ``read_lengths``
+LENGTH_FIELDS: dict[str, list[int]] = {
+    "start_time": [2],
+    "total_elapsed_time": [3],
+    "total_timer_time": [4],
+    "length_type": [12],
+    "swim_stroke": [7],
+    "total_strokes": [5],
+    "avg_speed": [6],
+    "avg_swimming_cadence": [9],
+}
+
+# Course point field numbers of each column
+COURSE_POINT_FIELDS: dict[str, list[int]] = {
+    "timestamp": [1],
+    "name": [6],
+    "type": [5],
+    "position_lat": [2],
+    "position_long": [3],
+    "distance": [4],
+}
+
+# Workout step field numbers of each column
+WORKOUT_STEP_FIELDS: dict[str, list[int]] = {
+    "message_index": [254],
+    "name": [0],
+    "duration_type": [1],
+    "duration_value": [2],
+    "target_type": [3],
+    "target_value": [4],
+    "custom_target_value_low": [5],
+    "custom_target_value_high": [6],
+    "intensity": [7],
+}
+
+# Scale of the duration value of a workout step, by duration type: times are
+# stored in ms and distances in cm
+_WORKOUT_DURATION_SCALES = {"time": 1000, "distance": 100}
+
+_SUMMARY_DTYPES: dict[str, pl.DataType] = {
+    "sport": pl.String(),
+    "sub_sport": pl.String(),
+    "start_time": pl.Datetime("ms", "UTC"),
+    "total_calories": pl.UInt32(),
+    "avg_heart_rate": pl.UInt32(),
+    "max_heart_rate": pl.UInt32(),
+    "avg_power": pl.UInt32(),
+    "max_power": pl.UInt32(),
+    "total_ascent": pl.UInt32(),
+    "total_descent": pl.UInt32(),
+    "length_type": pl.String(),
+    "swim_stroke": pl.String(),
+    "total_strokes": pl.UInt32(),
+    "avg_swimming_cadence": pl.UInt32(),
+    "timestamp": pl.Datetime("ms", "UTC"),
+    "name": pl.String(),
+    "type": pl.String(),
+    "message_index": pl.UInt32(),
+    "duration_type": pl.String(),
+    "target_type": pl.String(),
+    "target_value": pl.UInt32(),
+    "custom_target_value_low": pl.UInt32(),
+    "custom_target_value_high": pl.UInt32(),
+    "intensity": pl.String(),
+}
+
+
+def summarize_sessions(sessions: pl.DataFrame) -> pl.DataFrame:
+    """
+    Build the summary frame from decoded session messages.
+
+    ``sessions`` must use raw ``field_X`` column names, with scale, offset,
+    enum and timestamp decoding applied.
+    """
+    return (
+        sessions.select(_stable_columns(sessions, SESSION_SUMMARY_FIELDS))
+        .with_columns(
+            # Fall back to distance over timer time when no speed is recorded
+            pl.coalesce(
+                "avg_speed",
+                pl.when(pl.col("total_timer_time") > 0).then(
+                    pl.col("total_distance") / pl.col("total_timer_time")
+                ),
+            ).alias("avg_speed"),
+        )
+        .with_columns(
+            end_time=pl.col("start_time")
+            + pl.duration(
+                milliseconds=(pl.col("total_elapsed_time") * 1000).cast(pl.Int64)
+            ),
+            avg_pace=pl.when(pl.col("avg_speed") > 0).then(
+                1000.0 / pl.col("avg_speed")
+            ),
+        )
+    )
+
+
+def summarize_lengths(lengths: pl.DataFrame) -> pl.DataFrame:
+    """
+    Build the lengths frame from decoded length messages.
+
+    ``lengths`` must use raw ``field_X`` column names, with scale, offset,
+    enum and timestamp decoding applied.
+    """
+    return lengths.select(_stable_columns(lengths, LENGTH_FIELDS)).with_columns(
+        end_time=pl.col("start_time")
+        + pl.duration(
+            milliseconds=(pl.col("total_elapsed_time") * 1000).cast(pl.Int64)
+        ),
+    )
+
+
+def summarize_course_points(points: pl.DataFrame) -> pl.DataFrame:
+    """
+    Build the course points frame from decoded course_point messages.
+
+    ``points`` must use raw ``field_X`` column names, with scale, enum,
+    position and timestamp decoding applied.
+    """
+    return points.select(_stable_columns(points, COURSE_POINT_FIELDS))
+
+
+def summarize_workout_steps(steps: pl.DataFrame) -> pl.DataFrame:
+    """
+    Build the workout steps frame from decoded workout_step messages.
+
+    ``steps`` must use raw ``field_X`` column names, with enum decoding
+    applied. Durations of time and distance steps are scaled to s and m.
+    """
+    scales = [
+        pl.when(pl.col("duration_type") == duration_type).then(scale)
+        for duration_type, scale in _WORKOUT_DURATION_SCALES.items()
+    ]
+    return steps.select(_stable_columns(steps, WORKOUT_STEP_FIELDS)).with_columns(
+        pl.col("duration_value") / pl.coalesce(*scales, pl.lit(1))
+    )
+
+
+def _stable_columns(
+    messages: pl.DataFrame, fields: dict[str, list[int]]
+) -> list[pl.Expr]:
+    """One column per name, from the first of its fields present, else null."""
+    columns = []
+    for name, numbers in fields.items():
+        dtype = _SUMMARY_DTYPES.get(name, pl.Float64())
+        present = [
+            pl.col(f"field_{n}").cast(dtype)
+            for n in numbers
+            if f"field_{n}" in messages.columns
+        ]
+        if present:
+            columns.append(pl.coalesce(present).alias(name))
+        else:
+            # Repeated per message, so a file without any gives no rows
+            columns.append(pl.repeat(None, pl.len(), dtype=dtype).alias(name))
+    return columns
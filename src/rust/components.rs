@@ -0,0 +1,138 @@
+use crate::decoder::Value;
+
+/// One value packed into a component field by the FIT profile
+#[derive(Clone, Copy, Debug)]
+pub struct Component {
+    /// Field number of the field the value is decoded as
+    pub destination: u8,
+    pub bits: u8,
+    pub scale: f64,
+    pub offset: f64,
+    /// Whether the value is a counter that rolls over at `bits` bits and is
+    /// summed up across messages
+    pub accumulate: bool,
+}
+
+const fn component(destination: u8, bits: u8, scale: f64, accumulate: bool) -> Component {
+    Component { destination, bits, scale, offset: 0.0, accumulate }
+}
+
+const RECORD_SPEED: Component = component(6, 12, 100.0, false);
+const RECORD_DISTANCE: Component = component(5, 12, 16.0, true);
+const RECORD_TOTAL_CYCLES: Component = component(19, 8, 1.0, true);
+const RECORD_ACCUMULATED_POWER: Component = component(29, 16, 1.0, true);
+const EVENT_DATA: Component = component(3, 16, 1.0, false);
+const HR_FRACTIONAL_TIMESTAMP: Component = component(0, 8, 256.0, false);
+const HR_EVENT_TIMESTAMP: Component = component(9, 12, 1024.0, true);
+// The 16-bit monitoring counters share the units of the totals they roll into
+const MONITORING_DISTANCE: Component = component(2, 16, 100.0, true);
+const MONITORING_CYCLES: Component = component(3, 16, 2.0, true);
+const MONITORING_ACTIVE_TIME: Component = component(4, 16, 1000.0, true);
+const MONITORING_ACTIVITY_TYPE: Component = component(5, 5, 1.0, false);
+const MONITORING_INTENSITY: Component = component(28, 3, 1.0, false);
+
+/// Components of the event subfields gear_change_data and sport_point
+pub const GEAR_CHANGE_COMPONENTS: &[Component] = &[
+    component(11, 8, 1.0, false), // rear_gear_num
+    component(12, 8, 1.0, false), // rear_gear
+    component(9, 8, 1.0, false),  // front_gear_num
+    component(10, 8, 1.0, false), // front_gear
+];
+pub const SPORT_POINT_COMPONENTS: &[Component] = &[
+    component(7, 16, 1.0, false), // score
+    component(8, 16, 1.0, false), // opponent_score
+];
+
+/// Components of a field, in packing order from the least significant bit.
+/// Fields whose only component copies them into their 32-bit "enhanced"
+/// variant are left out, those are filled in by `prefer_enhanced`.
+pub fn components(global_num: u16, field: u8) -> &'static [Component] {
+    match (global_num, field) {
+        // record: compressed_speed_distance, cycles, compressed_accumulated_power
+        (20, 8) => &[RECORD_SPEED, RECORD_DISTANCE],
+        (20, 18) => &[RECORD_TOTAL_CYCLES],
+        (20, 28) => &[RECORD_ACCUMULATED_POWER],
+        // event: data16
+        (21, 2) => &[EVENT_DATA],
+        // hr: time256, event_timestamp_12
+        (132, 1) => &[HR_FRACTIONAL_TIMESTAMP],
+        (132, 10) => &[HR_EVENT_TIMESTAMP; 8],
+        // monitoring: distance_16, cycles_16, active_time_16, current_activity_type_intensity
+        (55, 8) => &[MONITORING_DISTANCE],
+        (55, 9) => &[MONITORING_CYCLES],
+        (55, 10) => &[MONITORING_ACTIVE_TIME],
+        (55, 24) => &[MONITORING_ACTIVITY_TYPE, MONITORING_INTENSITY],
+        _ => &[],
+    }
+}
+
+/// Whether a field is a cumulative total that accumulated components of the
+/// same message count on from. Monitoring messages report their daily totals
+/// now and then and only the 16-bit increments in between. Record distance is
+/// left out as its compressed component counts in different units.
+pub fn is_accumulated_total(global_num: u16, field: u8) -> bool {
+    matches!((global_num, field), (20, 19) | (20, 29) | (55, 2..=4))
+}
+
+/// Read the raw component values from the little-endian bits of a field. A
+/// field too short for all components yields only the leading ones.
+pub fn unpack(value: &Value, components: &[Component]) -> Vec<u64> {
+    let mut bytes = Vec::new();
+    if !append_bytes(&mut bytes, value) {
+        return Vec::new();
+    }
+
+    let mut position = 0;
+    let mut values = Vec::with_capacity(components.len());
+    for component in components {
+        let bits = component.bits as usize;
+        if position + bits > bytes.len() * 8 {
+            break;
+        }
+        let value = (0..bits).fold(0u64, |value, i| {
+            let bit = (bytes[(position + i) / 8] >> ((position + i) % 8)) & 1;
+            value | (u64::from(bit) << i)
+        });
+        values.push(value);
+        position += bits;
+    }
+    values
+}
+
+/// Append the little-endian bytes of an integer value, false for other values
+fn append_bytes(bytes: &mut Vec<u8>, value: &Value) -> bool {
+    match value {
+        Value::U8(v) => bytes.push(*v),
+        Value::I8(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+        Value::U16(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+        Value::I16(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+        Value::U32(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+        Value::I32(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+        Value::U64(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+        Value::I64(v) => bytes.extend_from_slice(&v.to_le_bytes()),
+        Value::Array(values) => return values.iter().all(|value| append_bytes(bytes, value)),
+        Value::F32(_) | Value::F64(_) | Value::String(_) => return false,
+    }
+    true
+}
+
+/// Running total of an accumulated component
+#[derive(Clone, Copy, Debug)]
+pub struct Accumulator {
+    last: u64,
+    total: u64,
+}
+
+impl Accumulator {
+    pub fn new(raw: u64) -> Self {
+        Accumulator { last: raw, total: raw }
+    }
+
+    /// Add the increase since the last raw value, allowing for one rollover
+    pub fn add(&mut self, raw: u64, bits: u8) -> u64 {
+        let mask = if bits >= 64 { u64::MAX } else { (1 << bits) - 1 };
+        self.total += raw.wrapping_sub(self.last) & mask;
+        self.last = raw;
+        self.total
+    }
+}
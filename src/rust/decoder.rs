@@ -0,0 +1,885 @@
+use fitsdk::{match_messagetype, MessageType};
+use std::collections::HashMap;
+use std::borrow::Cow;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Seconds between the Unix epoch and the FIT epoch (1989-12-31 00:00:00 UTC)
+pub const FIT_EPOCH_OFFSET: u32 = 631_065_600;
+
+/// Field number used for the timestamp of most messages
+pub const TIMESTAMP_FIELD: u8 = 253;
+
+/// Global message number of field_description, which describes developer fields
+const FIELD_DESCRIPTION_MESSAGE: u16 = 206;
+
+/// Global message number and timestamp_16 field of monitoring messages, which
+/// carry the low 16 bits of their timestamp between full timestamps
+const MONITORING_MESSAGE: u16 = 55;
+const MONITORING_TIMESTAMP_16_FIELD: u8 = 26;
+
+const COMPRESSED_HEADER_MASK: u8 = 0x80;
+const DEFINITION_MASK: u8 = 0x40;
+const DEVELOPER_DATA_MASK: u8 = 0x20;
+const LOCAL_MESSAGE_MASK: u8 = 0x0F;
+const COMPRESSED_LOCAL_MESSAGE_MASK: u8 = 0x60;
+const COMPRESSED_TIME_OFFSET_MASK: u8 = 0x1F;
+
+/// Errors raised while decoding a FIT byte stream
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("Not a FIT file: {0}")]
+    InvalidHeader(&'static str),
+    #[error("Unexpected end of data at byte {0}")]
+    UnexpectedEof(usize),
+    #[error("Invalid architecture byte {1} in definition message at byte {0}")]
+    InvalidArchitecture(usize, u8),
+    #[error("Data message at byte {0} uses undefined local message type {1}")]
+    UndefinedLocalMessage(usize, u8),
+    #[error("{0} CRC mismatch: expected {1:#06x}, computed {2:#06x}")]
+    CrcMismatch(&'static str, u16, u16),
+}
+
+/// The 12 or 14 byte header at the start of every FIT file
+#[derive(Clone, Debug)]
+pub struct FileHeader {
+    pub header_size: u8,
+    pub protocol_version: u8,
+    pub profile_version: u16,
+    pub data_size: u32,
+    pub crc: Option<u16>,
+}
+
+impl FileHeader {
+    /// Parse the header from the start of a FIT file
+    pub fn parse(data: &[u8]) -> Result<Self, DecodeError> {
+        let header_size = *data.first().ok_or(DecodeError::InvalidHeader("file is empty"))?;
+        if header_size != 12 && header_size != 14 {
+            return Err(DecodeError::InvalidHeader("unsupported header size"));
+        }
+        if data.len() < header_size as usize {
+            return Err(DecodeError::InvalidHeader("file is shorter than its header"));
+        }
+        if &data[8..12] != b".FIT" {
+            return Err(DecodeError::InvalidHeader("missing .FIT signature"));
+        }
+        let crc = if header_size == 14 {
+            Some(u16::from_le_bytes([data[12], data[13]]))
+        } else {
+            None
+        };
+        Ok(FileHeader {
+            header_size,
+            protocol_version: data[1],
+            profile_version: u16::from_le_bytes([data[2], data[3]]),
+            data_size: u32::from_le_bytes([data[4], data[5], data[6], data[7]]),
+            crc,
+        })
+    }
+
+    /// Protocol version as major.minor, e.g. 2.0
+    pub fn protocol(&self) -> f64 {
+        (self.protocol_version >> 4) as f64 + (self.protocol_version & 0x0F) as f64 / 10.0
+    }
+
+    /// Profile version as major.minor, e.g. 21.32. Profiles from 22.0 on store the
+    /// minor version in three digits.
+    pub fn profile(&self) -> f64 {
+        if self.profile_version > 2199 {
+            self.profile_version as f64 / 1000.0
+        } else {
+            self.profile_version as f64 / 100.0
+        }
+    }
+
+    /// Check the header CRC and the CRC that follows the data records
+    pub fn verify_crc(&self, data: &[u8]) -> Result<(), DecodeError> {
+        // A header CRC of zero means the writer didn't compute one
+        if let Some(expected) = self.crc.filter(|crc| *crc != 0) {
+            let computed = fit_crc(&data[..12]);
+            if computed != expected {
+                return Err(DecodeError::CrcMismatch("Header", expected, computed));
+            }
+        }
+        let end = self.header_size as usize + self.data_size as usize;
+        let trailer = data.get(end..end + 2).ok_or(DecodeError::UnexpectedEof(data.len()))?;
+        let expected = u16::from_le_bytes([trailer[0], trailer[1]]);
+        let computed = fit_crc(&data[..end]);
+        if computed != expected {
+            return Err(DecodeError::CrcMismatch("File", expected, computed));
+        }
+        Ok(())
+    }
+}
+
+const CRC_TABLE: [u16; 16] = [
+    0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800, 0xB401, 0x5000, 0x9C01,
+    0x8801, 0x4400,
+];
+
+/// The CRC-16 used for FIT headers and files
+pub fn fit_crc(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |crc, byte| {
+        let crc = (crc >> 4) ^ CRC_TABLE[(crc & 0xF) as usize] ^ CRC_TABLE[(byte & 0xF) as usize];
+        (crc >> 4) ^ CRC_TABLE[(crc & 0xF) as usize] ^ CRC_TABLE[((byte >> 4) & 0xF) as usize]
+    })
+}
+
+/// Character encoding of string fields
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StringEncoding {
+    /// UTF-8 as the FIT protocol specifies, with invalid bytes replaced by U+FFFD
+    #[default]
+    Utf8,
+    /// One character per byte, for older devices writing Latin-1 names
+    Latin1,
+}
+
+impl StringEncoding {
+    /// Decode the bytes of a string field up to its first null byte, which
+    /// ends the string and pads fixed-width fields
+    pub fn decode(self, bytes: &[u8]) -> String {
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        match self {
+            StringEncoding::Utf8 => String::from_utf8_lossy(&bytes[..end]).into_owned(),
+            StringEncoding::Latin1 => bytes[..end].iter().map(|b| char::from(*b)).collect(),
+        }
+    }
+}
+
+/// FIT base types as declared in field definitions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BaseType {
+    Enum,
+    SInt8,
+    UInt8,
+    SInt16,
+    UInt16,
+    SInt32,
+    UInt32,
+    String,
+    Float32,
+    Float64,
+    UInt8z,
+    UInt16z,
+    UInt32z,
+    Byte,
+    SInt64,
+    UInt64,
+    UInt64z,
+}
+
+impl BaseType {
+    /// Decode the base type byte of a field definition; unknown types are treated as bytes
+    pub fn from_byte(byte: u8) -> Self {
+        match byte & 0x1F {
+            0x00 => BaseType::Enum,
+            0x01 => BaseType::SInt8,
+            0x02 => BaseType::UInt8,
+            0x03 => BaseType::SInt16,
+            0x04 => BaseType::UInt16,
+            0x05 => BaseType::SInt32,
+            0x06 => BaseType::UInt32,
+            0x07 => BaseType::String,
+            0x08 => BaseType::Float32,
+            0x09 => BaseType::Float64,
+            0x0A => BaseType::UInt8z,
+            0x0B => BaseType::UInt16z,
+            0x0C => BaseType::UInt32z,
+            0x0E => BaseType::SInt64,
+            0x0F => BaseType::UInt64,
+            0x10 => BaseType::UInt64z,
+            _ => BaseType::Byte,
+        }
+    }
+
+    /// The base type byte of a field definition, the inverse of `from_byte`
+    pub fn to_byte(self) -> u8 {
+        match self {
+            BaseType::Enum => 0x00,
+            BaseType::SInt8 => 0x01,
+            BaseType::UInt8 => 0x02,
+            BaseType::SInt16 => 0x83,
+            BaseType::UInt16 => 0x84,
+            BaseType::SInt32 => 0x85,
+            BaseType::UInt32 => 0x86,
+            BaseType::String => 0x07,
+            BaseType::Float32 => 0x88,
+            BaseType::Float64 => 0x89,
+            BaseType::UInt8z => 0x0A,
+            BaseType::UInt16z => 0x8B,
+            BaseType::UInt32z => 0x8C,
+            BaseType::Byte => 0x0D,
+            BaseType::SInt64 => 0x8E,
+            BaseType::UInt64 => 0x8F,
+            BaseType::UInt64z => 0x90,
+        }
+    }
+
+    /// This type's "invalid" sentinel, which marks a missing value
+    pub fn invalid(self) -> Value {
+        match self {
+            BaseType::Enum | BaseType::UInt8 | BaseType::Byte => Value::U8(u8::MAX),
+            BaseType::SInt8 => Value::I8(i8::MAX),
+            BaseType::SInt16 => Value::I16(i16::MAX),
+            BaseType::UInt16 => Value::U16(u16::MAX),
+            BaseType::SInt32 => Value::I32(i32::MAX),
+            BaseType::UInt32 => Value::U32(u32::MAX),
+            BaseType::Float32 => Value::F32(f32::from_bits(u32::MAX)),
+            BaseType::Float64 => Value::F64(f64::from_bits(u64::MAX)),
+            BaseType::UInt8z => Value::U8(0),
+            BaseType::UInt16z => Value::U16(0),
+            BaseType::UInt32z => Value::U32(0),
+            BaseType::SInt64 => Value::I64(i64::MAX),
+            BaseType::UInt64 => Value::U64(u64::MAX),
+            BaseType::UInt64z => Value::U64(0),
+            BaseType::String => Value::String(String::new()),
+        }
+    }
+
+    /// Size in bytes of a single value of this type
+    pub fn size(self) -> usize {
+        match self {
+            BaseType::Enum | BaseType::SInt8 | BaseType::UInt8 | BaseType::UInt8z | BaseType::Byte | BaseType::String => 1,
+            BaseType::SInt16 | BaseType::UInt16 | BaseType::UInt16z => 2,
+            BaseType::SInt32 | BaseType::UInt32 | BaseType::UInt32z | BaseType::Float32 => 4,
+            BaseType::Float64 | BaseType::SInt64 | BaseType::UInt64 | BaseType::UInt64z => 8,
+        }
+    }
+
+    /// Whether a scalar value equals this type's "invalid" sentinel
+    pub fn is_invalid(self, value: &Value) -> bool {
+        match (self, value) {
+            (BaseType::Enum | BaseType::UInt8 | BaseType::Byte, Value::U8(v)) => *v == u8::MAX,
+            (BaseType::SInt8, Value::I8(v)) => *v == i8::MAX,
+            (BaseType::SInt16, Value::I16(v)) => *v == i16::MAX,
+            (BaseType::UInt16, Value::U16(v)) => *v == u16::MAX,
+            (BaseType::SInt32, Value::I32(v)) => *v == i32::MAX,
+            (BaseType::UInt32, Value::U32(v)) => *v == u32::MAX,
+            (BaseType::Float32, Value::F32(v)) => v.to_bits() == u32::MAX,
+            (BaseType::Float64, Value::F64(v)) => v.to_bits() == u64::MAX,
+            (BaseType::UInt8z, Value::U8(v)) => *v == 0,
+            (BaseType::UInt16z, Value::U16(v)) => *v == 0,
+            (BaseType::UInt32z, Value::U32(v)) => *v == 0,
+            (BaseType::SInt64, Value::I64(v)) => *v == i64::MAX,
+            (BaseType::UInt64, Value::U64(v)) => *v == u64::MAX,
+            (BaseType::UInt64z, Value::U64(v)) => *v == 0,
+            (BaseType::String, Value::String(v)) => v.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// A raw field value as stored in the file
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Array(Vec<Value>),
+}
+
+/// A single field of a data message
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub number: u8,
+    pub base_type: BaseType,
+    pub value: Value,
+}
+
+impl Field {
+    /// Whether the field holds at least one non-sentinel value
+    pub fn is_valid(&self) -> bool {
+        match &self.value {
+            Value::Array(values) => values.iter().any(|v| !self.base_type.is_invalid(v)),
+            value => !self.base_type.is_invalid(value),
+        }
+    }
+}
+
+/// Name and encoding of a developer field from its field_description message
+#[derive(Clone, Debug)]
+pub struct FieldDescription {
+    pub name: Option<String>,
+    pub base_type: BaseType,
+    pub scale: Option<u8>,
+    pub offset: Option<i8>,
+    /// Profile field the developer field records, e.g. running dynamics of a
+    /// foot pod, in the units of that field
+    pub native_field: Option<u8>,
+    /// Message of the profile field, if restricted to one
+    pub native_message: Option<u16>,
+}
+
+impl FieldDescription {
+    fn from_message(message: &Message) -> Option<(u8, u8, Self)> {
+        let u8_field = |number| match message.field(number)?.value {
+            Value::U8(v) => Some(v),
+            _ => None,
+        };
+        let string_field = |number| match message.field(number) {
+            Some(field) if field.is_valid() => match &field.value {
+                Value::String(v) => Some(v.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let description = FieldDescription {
+            name: string_field(3),
+            base_type: BaseType::from_byte(u8_field(2)?),
+            scale: u8_field(6).filter(|v| *v != u8::MAX),
+            offset: match message.field(7).map(|f| &f.value) {
+                Some(Value::I8(v)) if *v != i8::MAX => Some(*v),
+                _ => None,
+            },
+            native_field: u8_field(15).filter(|v| *v != u8::MAX),
+            native_message: match message.field(14).map(|f| &f.value) {
+                Some(Value::U16(v)) if *v != u16::MAX => Some(*v),
+                _ => None,
+            },
+        };
+        Some((u8_field(0)?, u8_field(1)?, description))
+    }
+}
+
+/// A developer field of a data message, decoded with the base type of its description
+#[derive(Clone, Debug)]
+pub struct DeveloperField {
+    pub developer_data_index: u8,
+    pub field: Field,
+    pub description: Option<Arc<FieldDescription>>,
+}
+
+/// A decoded data message
+#[derive(Clone, Debug)]
+pub struct Message {
+    /// Global message number, which also identifies types missing from the profile
+    pub global_num: u16,
+    pub kind: MessageType,
+    pub fields: Vec<Field>,
+    pub developer_fields: Vec<DeveloperField>,
+}
+
+impl Message {
+    /// Look up a field by its field definition number
+    pub fn field(&self, number: u8) -> Option<&Field> {
+        self.fields.iter().find(|f| f.number == number)
+    }
+
+    /// The message's timestamp in seconds since the FIT epoch, if it has a valid one
+    pub fn timestamp(&self) -> Option<u32> {
+        match self.field(TIMESTAMP_FIELD) {
+            Some(field @ Field { value: Value::U32(timestamp), .. }) if field.is_valid() => Some(*timestamp),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct FieldDefinition {
+    number: u8,
+    size: u8,
+    base_type: BaseType,
+}
+
+#[derive(Clone, Debug)]
+struct DeveloperFieldDefinition {
+    number: u8,
+    size: u8,
+    developer_data_index: u8,
+}
+
+#[derive(Clone, Debug)]
+struct Definition {
+    big_endian: bool,
+    global_num: u16,
+    fields: Vec<FieldDefinition>,
+    developer_fields: Vec<DeveloperFieldDefinition>,
+}
+
+/// Iterator over the data messages of an in-memory FIT file, borrowed or owned
+pub struct FitDecoder<'a> {
+    data: Cow<'a, [u8]>,
+    header: FileHeader,
+    pos: usize,
+    end: usize,
+    definitions: [Option<Definition>; 16],
+    descriptions: HashMap<(u8, u8), Arc<FieldDescription>>,
+    last_timestamp: Option<u32>,
+    failed: bool,
+    encoding: StringEncoding,
+}
+
+impl<'a> FitDecoder<'a> {
+    /// Create a decoder over the complete bytes of a FIT file
+    pub fn new(data: impl Into<Cow<'a, [u8]>>) -> Result<Self, DecodeError> {
+        let data = data.into();
+        let header = FileHeader::parse(&data)?;
+        let pos = header.header_size as usize;
+        let end = pos + header.data_size as usize;
+        Ok(FitDecoder {
+            data,
+            header,
+            pos,
+            end,
+            definitions: Default::default(),
+            descriptions: HashMap::new(),
+            last_timestamp: None,
+            failed: false,
+            encoding: StringEncoding::default(),
+        })
+    }
+
+    /// Decode string fields with this encoding instead of UTF-8
+    pub fn with_encoding(mut self, encoding: StringEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Check the header and file CRCs before decoding any messages
+    pub fn verify_crc(&self) -> Result<(), DecodeError> {
+        self.header.verify_crc(&self.data)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&[u8], DecodeError> {
+        take(&self.data, &mut self.pos, len)
+    }
+
+    fn read_definition(&mut self, header_byte: u8) -> Result<(), DecodeError> {
+        let start = self.pos;
+        let fixed = self.take(5)?;
+        let big_endian = match fixed[1] {
+            0 => false,
+            1 => true,
+            other => return Err(DecodeError::InvalidArchitecture(start, other)),
+        };
+        let global_num = if big_endian {
+            u16::from_be_bytes([fixed[2], fixed[3]])
+        } else {
+            u16::from_le_bytes([fixed[2], fixed[3]])
+        };
+        let num_fields = fixed[4] as usize;
+        let fields = self
+            .take(num_fields * 3)?
+            .chunks_exact(3)
+            .map(|f| FieldDefinition {
+                number: f[0],
+                size: f[1],
+                base_type: BaseType::from_byte(f[2]),
+            })
+            .collect();
+        let developer_fields = if header_byte & DEVELOPER_DATA_MASK != 0 {
+            let num_dev_fields = self.take(1)?[0] as usize;
+            self.take(num_dev_fields * 3)?
+                .chunks_exact(3)
+                .map(|f| DeveloperFieldDefinition {
+                    number: f[0],
+                    size: f[1],
+                    developer_data_index: f[2],
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.definitions[(header_byte & LOCAL_MESSAGE_MASK) as usize] = Some(Definition {
+            big_endian,
+            global_num,
+            fields,
+            developer_fields,
+        });
+        Ok(())
+    }
+
+    fn read_data_message(&mut self, local: u8, time_offset: Option<u8>) -> Result<Message, DecodeError> {
+        let start = self.pos - 1;
+        let definition = self.definitions[local as usize]
+            .as_ref()
+            .ok_or(DecodeError::UndefinedLocalMessage(start, local))?;
+        let encoding = self.encoding;
+        // The definition stays borrowed, so bytes are taken from the data directly
+        let (data, pos) = (&self.data, &mut self.pos);
+
+        let mut fields = Vec::with_capacity(definition.fields.len());
+        for fd in &definition.fields {
+            let bytes = take(data, pos, fd.size as usize)?;
+            let (base_type, value) = decode_field(bytes, fd.base_type, definition.big_endian, encoding);
+            fields.push(Field {
+                number: fd.number,
+                base_type,
+                value,
+            });
+        }
+
+        let mut developer_fields = Vec::with_capacity(definition.developer_fields.len());
+        for dd in &definition.developer_fields {
+            let description = self.descriptions.get(&(dd.developer_data_index, dd.number)).cloned();
+            let bytes = take(data, pos, dd.size as usize)?;
+            let declared = description.as_ref().map_or(BaseType::Byte, |d| d.base_type);
+            let (base_type, value) = decode_field(bytes, declared, definition.big_endian, encoding);
+            developer_fields.push(DeveloperField {
+                developer_data_index: dd.developer_data_index,
+                field: Field {
+                    number: dd.number,
+                    base_type,
+                    value,
+                },
+                description,
+            });
+        }
+
+        let mut message = Message {
+            global_num: definition.global_num,
+            kind: match_messagetype(definition.global_num),
+            fields,
+            developer_fields,
+        };
+        if definition.global_num == FIELD_DESCRIPTION_MESSAGE {
+            if let Some((index, number, description)) = FieldDescription::from_message(&message) {
+                self.descriptions.insert((index, number), Arc::new(description));
+            }
+        }
+        self.track_timestamp(&mut message, time_offset);
+        Ok(message)
+    }
+
+    /// Remember the last full timestamp and expand timestamp_16 fields and compressed
+    /// timestamp headers against it
+    fn track_timestamp(&mut self, message: &mut Message, time_offset: Option<u8>) {
+        if let Some(field) = message.field(TIMESTAMP_FIELD) {
+            if let Value::U32(timestamp) = field.value {
+                if field.is_valid() {
+                    self.last_timestamp = Some(timestamp);
+                }
+            }
+            return;
+        }
+        if let (Some(timestamp_16), Some(last)) = (timestamp_16(message), self.last_timestamp) {
+            let delta = u32::from(timestamp_16).wrapping_sub(last) & u32::from(u16::MAX);
+            let timestamp = last + delta;
+            self.last_timestamp = Some(timestamp);
+            message.fields.push(Field {
+                number: TIMESTAMP_FIELD,
+                base_type: BaseType::UInt32,
+                value: Value::U32(timestamp),
+            });
+            return;
+        }
+        if let (Some(offset), Some(last)) = (time_offset, self.last_timestamp) {
+            let delta = (offset as u32).wrapping_sub(last) & COMPRESSED_TIME_OFFSET_MASK as u32;
+            let timestamp = last + delta;
+            self.last_timestamp = Some(timestamp);
+            message.fields.push(Field {
+                number: TIMESTAMP_FIELD,
+                base_type: BaseType::UInt32,
+                value: Value::U32(timestamp),
+            });
+        }
+    }
+
+    /// The global numbers of the remaining data messages, without decoding their
+    /// fields. Stops at the first malformed record, so the result is only a hint.
+    pub fn message_numbers(mut self) -> impl Iterator<Item = u16> + 'a {
+        std::iter::from_fn(move || self.next_number().ok().flatten())
+    }
+
+    fn next_number(&mut self) -> Result<Option<u16>, DecodeError> {
+        while self.pos < self.end {
+            let header_byte = self.take(1)?[0];
+            let local = if header_byte & COMPRESSED_HEADER_MASK != 0 {
+                (header_byte & COMPRESSED_LOCAL_MESSAGE_MASK) >> 5
+            } else if header_byte & DEFINITION_MASK != 0 {
+                self.read_definition(header_byte)?;
+                continue;
+            } else {
+                header_byte & LOCAL_MESSAGE_MASK
+            };
+            let definition = self.definitions[local as usize]
+                .as_ref()
+                .ok_or(DecodeError::UndefinedLocalMessage(self.pos - 1, local))?;
+            let global_num = definition.global_num;
+            let size = definition.fields.iter().map(|f| f.size as usize).sum::<usize>()
+                + definition.developer_fields.iter().map(|f| f.size as usize).sum::<usize>();
+            self.take(size)?;
+            return Ok(Some(global_num));
+        }
+        Ok(None)
+    }
+
+    fn next_message(&mut self) -> Result<Option<Message>, DecodeError> {
+        while self.pos < self.end {
+            let header_byte = self.take(1)?[0];
+            if header_byte & COMPRESSED_HEADER_MASK != 0 {
+                let local = (header_byte & COMPRESSED_LOCAL_MESSAGE_MASK) >> 5;
+                let offset = header_byte & COMPRESSED_TIME_OFFSET_MASK;
+                return self.read_data_message(local, Some(offset)).map(Some);
+            }
+            if header_byte & DEFINITION_MASK != 0 {
+                self.read_definition(header_byte)?;
+            } else {
+                return self
+                    .read_data_message(header_byte & LOCAL_MESSAGE_MASK, None)
+                    .map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Iterator for FitDecoder<'_> {
+    type Item = Result<Message, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.next_message() {
+            Ok(message) => message.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Take the next `len` bytes of the data at `pos`, moving past them
+fn take<'d>(data: &'d [u8], pos: &mut usize, len: usize) -> Result<&'d [u8], DecodeError> {
+    let bytes = data.get(*pos..*pos + len).ok_or(DecodeError::UnexpectedEof(*pos))?;
+    *pos += len;
+    Ok(bytes)
+}
+
+/// The valid timestamp_16 field of a monitoring message, the low 16 bits of its timestamp
+fn timestamp_16(message: &Message) -> Option<u16> {
+    if message.global_num != MONITORING_MESSAGE {
+        return None;
+    }
+    match message.field(MONITORING_TIMESTAMP_16_FIELD)? {
+        field @ Field { value: Value::U16(timestamp_16), .. } if field.is_valid() => Some(*timestamp_16),
+        _ => None,
+    }
+}
+
+/// Decode the bytes of one field into a scalar or array value
+fn decode_field(bytes: &[u8], base_type: BaseType, big_endian: bool, encoding: StringEncoding) -> (BaseType, Value) {
+    if base_type == BaseType::String {
+        return (base_type, Value::String(encoding.decode(bytes)));
+    }
+
+    // A size that doesn't fit the base type is kept as raw bytes
+    let size = base_type.size();
+    let base_type = if bytes.is_empty() || !bytes.len().is_multiple_of(size) {
+        BaseType::Byte
+    } else {
+        base_type
+    };
+
+    let mut values: Vec<Value> = bytes
+        .chunks_exact(base_type.size())
+        .map(|chunk| decode_scalar(chunk, base_type, big_endian))
+        .collect();
+    let value = if values.len() == 1 {
+        values.remove(0)
+    } else {
+        Value::Array(values)
+    };
+    (base_type, value)
+}
+
+fn decode_scalar(bytes: &[u8], base_type: BaseType, big_endian: bool) -> Value {
+    macro_rules! number {
+        ($ty:ty) => {{
+            let array = bytes.try_into().expect("chunk size matches base type");
+            if big_endian {
+                <$ty>::from_be_bytes(array)
+            } else {
+                <$ty>::from_le_bytes(array)
+            }
+        }};
+    }
+    match base_type {
+        BaseType::Enum | BaseType::UInt8 | BaseType::UInt8z | BaseType::Byte | BaseType::String => Value::U8(bytes[0]),
+        BaseType::SInt8 => Value::I8(bytes[0] as i8),
+        BaseType::SInt16 => Value::I16(number!(i16)),
+        BaseType::UInt16 | BaseType::UInt16z => Value::U16(number!(u16)),
+        BaseType::SInt32 => Value::I32(number!(i32)),
+        BaseType::UInt32 | BaseType::UInt32z => Value::U32(number!(u32)),
+        BaseType::Float32 => Value::F32(number!(f32)),
+        BaseType::Float64 => Value::F64(number!(f64)),
+        BaseType::SInt64 => Value::I64(number!(i64)),
+        BaseType::UInt64 | BaseType::UInt64z => Value::U64(number!(u64)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UINT8: u8 = 0x02;
+    const UINT16: u8 = 0x84;
+    const UINT32: u8 = 0x86;
+    const STRING: u8 = 0x07;
+    const RECORD: u16 = 20;
+
+    /// A definition message for little-endian fields of `(number, size, base type)`
+    fn definition(header_byte: u8, global_num: u16, fields: &[(u8, u8, u8)]) -> Vec<u8> {
+        let mut bytes = vec![header_byte, 0, 0];
+        bytes.extend_from_slice(&global_num.to_le_bytes());
+        bytes.push(fields.len() as u8);
+        for &(number, size, base_type) in fields {
+            bytes.extend_from_slice(&[number, size, base_type]);
+        }
+        bytes
+    }
+
+    /// A complete file with a 14 byte header around the records
+    fn fit_file(records: &[u8]) -> Vec<u8> {
+        let mut data = vec![14, 0x20];
+        data.extend_from_slice(&2132u16.to_le_bytes());
+        data.extend_from_slice(&(records.len() as u32).to_le_bytes());
+        data.extend_from_slice(b".FIT");
+        let header_crc = fit_crc(&data);
+        data.extend_from_slice(&header_crc.to_le_bytes());
+        data.extend_from_slice(records);
+        let file_crc = fit_crc(&data);
+        data.extend_from_slice(&file_crc.to_le_bytes());
+        data
+    }
+
+    /// Records of `count` record messages with a timestamp and a heart rate
+    fn heart_rate_records(count: u8) -> Vec<u8> {
+        let mut records = definition(0x40, RECORD, &[(TIMESTAMP_FIELD, 4, UINT32), (3, 1, UINT8)]);
+        for i in 0..count {
+            records.push(0);
+            records.extend_from_slice(&(1000 + i as u32).to_le_bytes());
+            records.push(120 + i);
+        }
+        records
+    }
+
+    fn decode(data: &[u8]) -> Vec<Message> {
+        FitDecoder::new(data).unwrap().collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn header_is_parsed_and_checked() {
+        let data = fit_file(&heart_rate_records(1));
+        let header = FileHeader::parse(&data).unwrap();
+        assert_eq!(header.header_size, 14);
+        assert_eq!(header.protocol(), 2.0);
+        assert_eq!(header.profile(), 21.32);
+        assert_eq!(header.data_size as usize, data.len() - 16);
+
+        assert!(matches!(FileHeader::parse(&[]), Err(DecodeError::InvalidHeader(_))));
+        assert!(matches!(FileHeader::parse(&data[..10]), Err(DecodeError::InvalidHeader(_))));
+        let mut unsigned = data.clone();
+        unsigned[8..12].copy_from_slice(b".TXT");
+        assert!(matches!(FileHeader::parse(&unsigned), Err(DecodeError::InvalidHeader(_))));
+        let mut oversized = data;
+        oversized[0] = 16;
+        assert!(matches!(FileHeader::parse(&oversized), Err(DecodeError::InvalidHeader(_))));
+    }
+
+    #[test]
+    fn crc_mismatches_are_detected() {
+        let data = fit_file(&heart_rate_records(3));
+        FitDecoder::new(&data[..]).unwrap().verify_crc().unwrap();
+
+        let mut corrupt = data.clone();
+        corrupt[20] ^= 0xFF;
+        let error = FitDecoder::new(&corrupt[..]).unwrap().verify_crc().unwrap_err();
+        assert!(matches!(error, DecodeError::CrcMismatch("File", _, _)));
+
+        let mut header = data.clone();
+        header[2] ^= 0x01;
+        let error = FitDecoder::new(&header[..]).unwrap().verify_crc().unwrap_err();
+        assert!(matches!(error, DecodeError::CrcMismatch("Header", _, _)));
+
+        // A zero header CRC wasn't computed and isn't checked
+        let mut uncomputed = header;
+        uncomputed[12..14].copy_from_slice(&[0, 0]);
+        let end = uncomputed.len() - 2;
+        let file_crc = fit_crc(&uncomputed[..end]);
+        uncomputed[end..].copy_from_slice(&file_crc.to_le_bytes());
+        FitDecoder::new(&uncomputed[..]).unwrap().verify_crc().unwrap();
+    }
+
+    #[test]
+    fn decoding_stops_at_the_end_of_the_data() {
+        let messages = decode(&fit_file(&heart_rate_records(3)));
+
+        assert_eq!(messages.len(), 3);
+        assert!(messages.iter().all(|m| m.kind == MessageType::Record));
+        assert_eq!(messages[2].timestamp(), Some(1002));
+        assert_eq!(messages[2].field(3).unwrap().value, Value::U8(122));
+    }
+
+    #[test]
+    fn compressed_timestamps_count_on_from_the_last_timestamp() {
+        let mut records = heart_rate_records(1);
+        records.extend(definition(0x41, RECORD, &[(3, 1, UINT8)]));
+        // 1000 is 8 past a multiple of 32: offset 10 is 2 seconds on, and offset 3
+        // rolls over to 25 seconds after that
+        for (offset, heart_rate) in [(10, 130), (3, 131)] {
+            records.extend_from_slice(&[COMPRESSED_HEADER_MASK | (1 << 5) | offset, heart_rate]);
+        }
+
+        let messages = decode(&fit_file(&records));
+
+        let timestamps: Vec<_> = messages.iter().map(Message::timestamp).collect();
+        assert_eq!(timestamps, [Some(1000), Some(1002), Some(1027)]);
+        assert_eq!(messages[2].field(3).unwrap().value, Value::U8(131));
+    }
+
+    #[test]
+    fn developer_fields_decode_with_their_description() {
+        let mut records = definition(
+            0x40,
+            FIELD_DESCRIPTION_MESSAGE,
+            &[(0, 1, UINT8), (1, 1, UINT8), (2, 1, UINT8), (3, 8, STRING)],
+        );
+        records.extend_from_slice(&[0, 0, 7, UINT16]);
+        records.extend_from_slice(b"power2\0\0");
+        let mut record = definition(0x41 | DEVELOPER_DATA_MASK, RECORD, &[(3, 1, UINT8)]);
+        record.extend_from_slice(&[1, 7, 2, 0]);
+        records.extend(record);
+        records.extend_from_slice(&[1, 125]);
+        records.extend_from_slice(&250u16.to_le_bytes());
+
+        let messages = decode(&fit_file(&records));
+
+        let record = &messages[1];
+        assert_eq!(record.field(3).unwrap().value, Value::U8(125));
+        let [developer] = record.developer_fields.as_slice() else {
+            panic!("expected one developer field");
+        };
+        assert_eq!(developer.developer_data_index, 0);
+        assert_eq!(developer.field.number, 7);
+        assert_eq!(developer.field.value, Value::U16(250));
+        let description = developer.description.as_ref().unwrap();
+        assert_eq!(description.name.as_deref(), Some("power2"));
+        assert_eq!(description.base_type, BaseType::UInt16);
+    }
+
+    #[test]
+    fn truncated_files_keep_the_complete_messages() {
+        let data = fit_file(&heart_rate_records(3));
+        // Cut the last record in half, along with the file CRC
+        let truncated = &data[..data.len() - 5];
+
+        let mut decoder = FitDecoder::new(truncated).unwrap();
+
+        assert_eq!(decoder.next().unwrap().unwrap().timestamp(), Some(1000));
+        assert_eq!(decoder.next().unwrap().unwrap().timestamp(), Some(1001));
+        assert!(matches!(decoder.next(), Some(Err(DecodeError::UnexpectedEof(_)))));
+        assert!(decoder.next().is_none());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::decoder::{fit_crc, BaseType, Value};
+
+/// Protocol version written to the header, 2.0 as major << 4 | minor
+const PROTOCOL_VERSION: u8 = 0x20;
+
+/// Profile version written to the header, 21.00 as major * 100 + minor
+const PROFILE_VERSION: u16 = 2100;
+
+const HEADER_SIZE: u8 = 14;
+const DEFINITION_MASK: u8 = 0x40;
+const LOCAL_MESSAGE_MASK: u8 = 0x0F;
+
+/// Definition of one field of a message to encode
+#[derive(Clone, Debug)]
+pub struct FieldDefinition {
+    pub number: u8,
+    pub base_type: BaseType,
+    /// Size in bytes: one value of the base type, a multiple for arrays, or the
+    /// length of a string including its terminator
+    pub size: u8,
+}
+
+/// Builds the bytes of a FIT file from definition and data messages, all
+/// little-endian
+#[derive(Default)]
+pub struct FitEncoder {
+    records: Vec<u8>,
+}
+
+impl FitEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write a definition message for a local message type
+    pub fn define(&mut self, local: u8, global_num: u16, fields: &[FieldDefinition]) {
+        self.records.push(DEFINITION_MASK | (local & LOCAL_MESSAGE_MASK));
+        self.records.push(0); // reserved
+        self.records.push(0); // little-endian
+        self.records.extend_from_slice(&global_num.to_le_bytes());
+        self.records.push(fields.len() as u8);
+        for field in fields {
+            self.records.extend_from_slice(&[field.number, field.size, field.base_type.to_byte()]);
+        }
+    }
+
+    /// Write a data message with one value per field of its definition
+    pub fn data(&mut self, local: u8, fields: &[FieldDefinition], values: &[Value]) {
+        self.records.push(local & LOCAL_MESSAGE_MASK);
+        for (field, value) in fields.iter().zip(values) {
+            encode_field(&mut self.records, field, value);
+        }
+    }
+
+    /// The complete file: header, records and the file CRC
+    pub fn finish(self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(HEADER_SIZE as usize + self.records.len() + 2);
+        data.push(HEADER_SIZE);
+        data.push(PROTOCOL_VERSION);
+        data.extend_from_slice(&PROFILE_VERSION.to_le_bytes());
+        data.extend_from_slice(&(self.records.len() as u32).to_le_bytes());
+        data.extend_from_slice(b".FIT");
+        let header_crc = fit_crc(&data);
+        data.extend_from_slice(&header_crc.to_le_bytes());
+        data.extend_from_slice(&self.records);
+        let file_crc = fit_crc(&data);
+        data.extend_from_slice(&file_crc.to_le_bytes());
+        data
+    }
+}
+
+/// Append a value padded to the field size: arrays with invalid elements,
+/// strings with zero bytes
+fn encode_field(out: &mut Vec<u8>, field: &FieldDefinition, value: &Value) {
+    let start = out.len();
+    let end = start + field.size as usize;
+    match value {
+        Value::String(s) => {
+            // Keep room for the terminator
+            let bytes = s.as_bytes();
+            out.extend_from_slice(&bytes[..bytes.len().min(field.size.saturating_sub(1) as usize)]);
+        }
+        Value::Array(values) => {
+            for value in values {
+                if out.len() + field.base_type.size() > end {
+                    break;
+                }
+                encode_scalar(out, value);
+            }
+        }
+        value => encode_scalar(out, value),
+    }
+    if field.base_type == BaseType::String {
+        out.resize(end, 0);
+    }
+    while out.len() + field.base_type.size() <= end {
+        encode_scalar(out, &field.base_type.invalid());
+    }
+    out.resize(end, 0);
+}
+
+fn encode_scalar(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::U8(v) => out.push(*v),
+        Value::I8(v) => out.push(*v as u8),
+        Value::U16(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::I16(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::U32(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::I32(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::U64(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::I64(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::F32(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::F64(v) => out.extend_from_slice(&v.to_le_bytes()),
+        Value::String(s) => out.extend_from_slice(s.as_bytes()),
+        Value::Array(values) => values.iter().for_each(|value| encode_scalar(out, value)),
+    }
+}
@@ -1,2 +1,808 @@
-// This file is placeholder for future expression implementations
-// for lazy evaluation support
+//! Polars expression plugins, registered from `polarsfit.expressions`
+use polars::prelude::*;
+use pyo3_polars::derive::polars_expr;
+use serde::Deserialize;
+
+use crate::io::DEGREES_PER_SEMICIRCLE;
+
+/// Invalid sentinel of the sint32 position fields
+const INVALID_SEMICIRCLE: i32 = i32::MAX;
+
+/// Convert semicircle positions to degrees, with the invalid sentinel as null
+#[polars_expr(output_type = Float64)]
+fn pl_semicircle_to_degrees(inputs: &[Series]) -> PolarsResult<Series> {
+    let semicircles = inputs[0].cast(&DataType::Int32)?;
+    let degrees: Float64Chunked = semicircles
+        .i32()?
+        .into_iter()
+        .map(|value| {
+            value
+                .filter(|&value| value != INVALID_SEMICIRCLE)
+                .map(|value| value as f64 * DEGREES_PER_SEMICIRCLE)
+        })
+        .collect();
+    Ok(degrees.with_name(semicircles.name().clone()).into_series())
+}
+
+/// Mean Earth radius in meters
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Great-circle distance in meters between two points given in degrees
+fn haversine(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+/// Cumulative haversine distance in meters along a track of latitude/longitude in
+/// degrees, starting at 0. Rows without a position carry the distance forward and
+/// the next position is measured from the last valid one.
+#[polars_expr(output_type = Float64)]
+fn pl_haversine_cumulative(inputs: &[Series]) -> PolarsResult<Series> {
+    let lat = inputs[0].cast(&DataType::Float64)?;
+    let lon = inputs[1].cast(&DataType::Float64)?;
+
+    let mut last: Option<(f64, f64)> = None;
+    let mut total = 0.0;
+    let distance: Float64Chunked = lat
+        .f64()?
+        .into_iter()
+        .zip(lon.f64()?)
+        .map(|point| {
+            if let (Some(lat), Some(lon)) = point {
+                if let Some((last_lat, last_lon)) = last {
+                    total += haversine(last_lat, last_lon, lat, lon);
+                }
+                last = Some((lat, lon));
+            }
+            Some(total)
+        })
+        .collect();
+    Ok(distance.with_name(lat.name().clone()).into_series())
+}
+
+/// Shortest run in meters a grade is computed over, so tiny steps don't spike
+const MIN_GRADE_RUN: f64 = 1.0;
+
+#[derive(Deserialize)]
+struct GradeKwargs {
+    window: f64,
+}
+
+/// Grade in percent from cumulative distance and altitude in meters, measured from
+/// the nearest earlier point at least `window` meters back. Null until a full
+/// window has been covered and where either input is null.
+#[polars_expr(output_type = Float64)]
+fn pl_grade(inputs: &[Series], kwargs: GradeKwargs) -> PolarsResult<Series> {
+    grade(&inputs[0], &inputs[1], &kwargs)
+}
+
+/// The computation of `pl_grade`, callable without the plugin interface
+fn grade(distance: &Series, altitude: &Series, kwargs: &GradeKwargs) -> PolarsResult<Series> {
+    let distance = distance.cast(&DataType::Float64)?;
+    let altitude = altitude.cast(&DataType::Float64)?;
+    let window = kwargs.window.max(MIN_GRADE_RUN);
+
+    // Valid points so far, and the index of the start of the current window
+    let mut points: Vec<(f64, f64)> = Vec::new();
+    let mut start = 0;
+    let grade: Float64Chunked = distance
+        .f64()?
+        .into_iter()
+        .zip(altitude.f64()?)
+        .map(|point| {
+            let (Some(distance), Some(altitude)) = point else {
+                return None;
+            };
+            points.push((distance, altitude));
+            while start + 1 < points.len() && distance - points[start + 1].0 >= window {
+                start += 1;
+            }
+            let (start_distance, start_altitude) = points[start];
+            let run = distance - start_distance;
+            (run >= window).then(|| (altitude - start_altitude) / run * 100.0)
+        })
+        .collect();
+    Ok(grade.with_name(distance.name().clone()).into_series())
+}
+
+/// Length of the rolling average behind normalized power, in seconds
+const NORMALIZED_POWER_WINDOW: f64 = 30.0;
+
+#[derive(Deserialize)]
+struct NormalizedPowerKwargs {
+    sample_hz: f64,
+}
+
+/// Normalized power of a power series sampled at `sample_hz`: the fourth root of the
+/// mean fourth power of its 30 s rolling average. Windows at the start use the
+/// samples available so far, and nulls are left out of the averages.
+#[polars_expr(output_type = Float64)]
+fn pl_normalized_power(inputs: &[Series], kwargs: NormalizedPowerKwargs) -> PolarsResult<Series> {
+    let power = inputs[0].cast(&DataType::Float64)?;
+    let power = power.f64()?;
+    let window = ((NORMALIZED_POWER_WINDOW * kwargs.sample_hz).round() as usize).max(1);
+
+    let samples: Vec<Option<f64>> = power.into_iter().collect();
+    let (mut sum, mut count) = (0.0, 0usize);
+    let (mut total, mut windows) = (0.0, 0usize);
+    for (i, sample) in samples.iter().enumerate() {
+        if let Some(value) = sample {
+            sum += value;
+            count += 1;
+        }
+        if i >= window {
+            if let Some(value) = samples[i - window] {
+                sum -= value;
+                count -= 1;
+            }
+        }
+        if count > 0 {
+            total += (sum / count as f64).powi(4);
+            windows += 1;
+        }
+    }
+
+    let normalized = (windows > 0).then(|| (total / windows as f64).powf(0.25));
+    Ok(Series::new(power.name().clone(), [normalized]))
+}
+
+#[derive(Deserialize)]
+struct ElevationGainKwargs {
+    threshold: f64,
+    /// Total the committed drops instead of the rises
+    #[serde(default)]
+    descent: bool,
+}
+
+/// Total ascent, or descent, in meters of an altitude series. The altitude has to move at least
+/// `threshold` meters away from a reference before the rise (or drop) is committed
+/// and the reference moves, so jitter below the threshold never adds up.
+#[polars_expr(output_type = Float64)]
+fn pl_elevation_gain(inputs: &[Series], kwargs: ElevationGainKwargs) -> PolarsResult<Series> {
+    let total = elevation_changes(&inputs[0], kwargs.threshold)?
+        .map(|(ascent, descent)| if kwargs.descent { descent } else { ascent });
+    Ok(Series::new(inputs[0].name().clone(), [total]))
+}
+
+/// The ascent and descent totals of `pl_elevation_gain`, or None without altitudes
+fn elevation_changes(altitude: &Series, threshold: f64) -> PolarsResult<Option<(f64, f64)>> {
+    let altitude = altitude.cast(&DataType::Float64)?;
+
+    let mut reference: Option<f64> = None;
+    let (mut ascent, mut descent) = (0.0, 0.0);
+    for value in altitude.f64()?.into_iter().flatten() {
+        match reference {
+            None => reference = Some(value),
+            Some(level) if value - level >= threshold => {
+                ascent += value - level;
+                reference = Some(value);
+            }
+            Some(level) if level - value >= threshold => {
+                descent += level - value;
+                reference = Some(value);
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(reference.map(|_| (ascent, descent)))
+}
+
+#[derive(Deserialize)]
+struct HrZoneKwargs {
+    boundaries: Vec<f64>,
+}
+
+/// Heart-rate zone of each value, given the ascending lower bounds of zones 1..N.
+/// Values below the first boundary are in zone 0.
+#[polars_expr(output_type = UInt8)]
+fn pl_hr_zone(inputs: &[Series], kwargs: HrZoneKwargs) -> PolarsResult<Series> {
+    let boundaries = kwargs.boundaries;
+    polars_ensure!(
+        boundaries.windows(2).all(|pair| pair[0] < pair[1]),
+        ComputeError: "zone boundaries must be strictly ascending"
+    );
+    polars_ensure!(
+        boundaries.len() <= u8::MAX as usize,
+        ComputeError: "at most {} zone boundaries are supported", u8::MAX
+    );
+
+    let hr = inputs[0].cast(&DataType::Float64)?;
+    let zones: UInt8Chunked = hr
+        .f64()?
+        .into_iter()
+        .map(|value| value.map(|value| boundaries.partition_point(|&bound| bound <= value) as u8))
+        .collect();
+    Ok(zones.with_name(hr.name().clone()).into_series())
+}
+
+/// Meters per statute mile
+const METERS_PER_MILE: f64 = 1609.344;
+
+#[derive(Deserialize)]
+struct PaceKwargs {
+    unit: String,
+}
+
+/// Pace in minutes per kilometer or mile from speed in m/s. Stopped samples (no
+/// positive speed) have no pace and are null.
+#[polars_expr(output_type = Float64)]
+fn pl_pace(inputs: &[Series], kwargs: PaceKwargs) -> PolarsResult<Series> {
+    let meters = pace_meters(&kwargs.unit)?;
+    let speed = inputs[0].cast(&DataType::Float64)?;
+    let pace: Float64Chunked = speed
+        .f64()?
+        .into_iter()
+        .map(|value| {
+            value
+                .filter(|&value| value > 0.0)
+                .map(|value| meters / value / 60.0)
+        })
+        .collect();
+    Ok(pace.with_name(speed.name().clone()).into_series())
+}
+
+/// Meters in the distance unit of a pace
+fn pace_meters(unit: &str) -> PolarsResult<f64> {
+    match unit {
+        "min_per_km" => Ok(1000.0),
+        "min_per_mile" => Ok(METERS_PER_MILE),
+        unit => {
+            polars_bail!(ComputeError: "unknown pace unit '{}', expected 'min_per_km' or 'min_per_mile'", unit)
+        }
+    }
+}
+
+/// Steepest grade, as a fraction, the Minetti cost of running was measured at
+const MAX_MINETTI_GRADE: f64 = 0.45;
+
+/// Energy cost of running in J/kg/m at a grade given as a fraction, fitted by
+/// Minetti et al. (2002) to measurements from -45% to +45%
+fn minetti_cost(grade: f64) -> f64 {
+    let i = grade.clamp(-MAX_MINETTI_GRADE, MAX_MINETTI_GRADE);
+    155.4 * i.powi(5) - 30.4 * i.powi(4) - 43.3 * i.powi(3) + 46.3 * i.powi(2) + 19.5 * i + 3.6
+}
+
+/// Grade-adjusted pace in minutes per kilometer or mile from speed in m/s and
+/// grade in percent: the flat-ground pace of equal effort under the Minetti cost
+/// of running. Steeper grades count as the steepest measured. Null where either
+/// input is null or the speed isn't positive.
+#[polars_expr(output_type = Float64)]
+fn pl_grade_adjusted_pace(inputs: &[Series], kwargs: PaceKwargs) -> PolarsResult<Series> {
+    let meters = pace_meters(&kwargs.unit)?;
+    let speed = inputs[0].cast(&DataType::Float64)?;
+    let grade = inputs[1].cast(&DataType::Float64)?;
+    let flat_cost = minetti_cost(0.0);
+    let pace: Float64Chunked = speed
+        .f64()?
+        .into_iter()
+        .zip(grade.f64()?)
+        .map(|point| {
+            let (Some(speed), Some(grade)) = point else {
+                return None;
+            };
+            let flat_speed = speed * minetti_cost(grade / 100.0) / flat_cost;
+            (speed > 0.0).then(|| meters / flat_speed / 60.0)
+        })
+        .collect();
+    Ok(pace.with_name(speed.name().clone()).into_series())
+}
+
+#[derive(Deserialize)]
+struct RollingSmoothKwargs {
+    window: usize,
+    method: String,
+    alpha: Option<f64>,
+}
+
+/// Smooth a series over a fixed number of samples. `mean` and `median` use a
+/// centered window that shrinks at the edges and skips nulls; `ewma` is an
+/// exponentially weighted moving average that carries its value over nulls.
+#[polars_expr(output_type = Float64)]
+fn pl_rolling_smooth(inputs: &[Series], kwargs: RollingSmoothKwargs) -> PolarsResult<Series> {
+    polars_ensure!(kwargs.window > 0, ComputeError: "window must be positive");
+    let input = inputs[0].cast(&DataType::Float64)?;
+    let values: Vec<Option<f64>> = input.f64()?.into_iter().collect();
+
+    let smoothed: Float64Chunked = match kwargs.method.as_str() {
+        "mean" | "median" => {
+            let before = (kwargs.window - 1) / 2;
+            let after = kwargs.window / 2;
+            let median = kwargs.method == "median";
+            (0..values.len())
+                .map(|i| {
+                    let end = (i + after + 1).min(values.len());
+                    let mut window: Vec<f64> = values[i.saturating_sub(before)..end]
+                        .iter()
+                        .flatten()
+                        .copied()
+                        .collect();
+                    if window.is_empty() {
+                        None
+                    } else if median {
+                        window.sort_by(f64::total_cmp);
+                        let mid = window.len() / 2;
+                        Some(if window.len().is_multiple_of(2) {
+                            (window[mid - 1] + window[mid]) / 2.0
+                        } else {
+                            window[mid]
+                        })
+                    } else {
+                        Some(window.iter().sum::<f64>() / window.len() as f64)
+                    }
+                })
+                .collect()
+        }
+        "ewma" => {
+            let alpha = kwargs.alpha.unwrap_or(2.0 / (kwargs.window as f64 + 1.0));
+            polars_ensure!(alpha > 0.0 && alpha <= 1.0, ComputeError: "alpha must be in (0, 1], got {}", alpha);
+            let mut average: Option<f64> = None;
+            values
+                .iter()
+                .map(|value| {
+                    if let Some(value) = value {
+                        average = Some(
+                            average
+                                .map_or(*value, |average| alpha * value + (1.0 - alpha) * average),
+                        );
+                    }
+                    average
+                })
+                .collect()
+        }
+        method => {
+            polars_bail!(ComputeError: "unknown smoothing method '{}', expected 'mean', 'median' or 'ewma'", method)
+        }
+    };
+    Ok(smoothed.with_name(input.name().clone()).into_series())
+}
+
+#[derive(Deserialize)]
+struct IsMovingKwargs {
+    min_speed: f64,
+}
+
+/// Whether each sample is moving, i.e. its speed in m/s is above `min_speed`
+#[polars_expr(output_type = Boolean)]
+fn pl_is_moving(inputs: &[Series], kwargs: IsMovingKwargs) -> PolarsResult<Series> {
+    let speed = inputs[0].cast(&DataType::Float64)?;
+    let moving: BooleanChunked = speed
+        .f64()?
+        .into_iter()
+        .map(|value| value.map(|value| value > kwargs.min_speed))
+        .collect();
+    Ok(moving.with_name(speed.name().clone()).into_series())
+}
+
+#[derive(Deserialize)]
+struct MovingTimeKwargs {
+    min_speed: f64,
+    max_gap: f64,
+}
+
+/// Seconds of a timestamp column, from datetimes or numeric seconds
+fn timestamp_seconds(timestamp: &Series) -> PolarsResult<Float64Chunked> {
+    let seconds = match timestamp.dtype() {
+        DataType::Datetime(unit, _) => {
+            let per_second = match unit {
+                TimeUnit::Milliseconds => 1e3,
+                TimeUnit::Microseconds => 1e6,
+                TimeUnit::Nanoseconds => 1e9,
+            };
+            timestamp.cast(&DataType::Int64)?.cast(&DataType::Float64)? / per_second
+        }
+        _ => timestamp.cast(&DataType::Float64)?,
+    };
+    Ok(seconds.f64()?.clone())
+}
+
+/// Total moving time in seconds. Each interval between consecutive samples counts
+/// when the sample ending it is moving, unless it is longer than `max_gap` seconds,
+/// e.g. when the device was paused.
+#[polars_expr(output_type = Float64)]
+fn pl_moving_time(inputs: &[Series], kwargs: MovingTimeKwargs) -> PolarsResult<Series> {
+    let seconds = timestamp_seconds(&inputs[0])?;
+    let speed = inputs[1].cast(&DataType::Float64)?;
+
+    let mut last: Option<f64> = None;
+    let mut total = 0.0;
+    for (time, speed) in seconds.into_iter().zip(speed.f64()?) {
+        let Some(time) = time else { continue };
+        if let (Some(last), Some(speed)) = (last, speed) {
+            let interval = time - last;
+            if speed > kwargs.min_speed && interval > 0.0 && interval <= kwargs.max_gap {
+                total += interval;
+            }
+        }
+        last = Some(time);
+    }
+
+    Ok(Series::new(inputs[0].name().clone(), [total]))
+}
+
+#[derive(Deserialize)]
+struct SimplifyTrackKwargs {
+    tolerance: f64,
+}
+
+/// Distance in meters from a point to the segment between two others, all in
+/// local planar meters
+fn segment_distance(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = dx * dx + dy * dy;
+    let t = if length > 0.0 {
+        (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (point.0 - start.0 - t * dx).hypot(point.1 - start.1 - t * dy)
+}
+
+/// Douglas-Peucker keep-mask of a track of latitude/longitude in degrees: the
+/// points retaining its shape within `tolerance` meters. Positions are
+/// projected to planar meters around the first one, which is accurate for the
+/// extent of an activity. Both endpoints are kept, rows without a position never.
+#[polars_expr(output_type = Boolean)]
+fn pl_simplify_track(inputs: &[Series], kwargs: SimplifyTrackKwargs) -> PolarsResult<Series> {
+    let lat = inputs[0].cast(&DataType::Float64)?;
+    let lon = inputs[1].cast(&DataType::Float64)?;
+
+    let positions: Vec<(usize, f64, f64)> = lat
+        .f64()?
+        .into_iter()
+        .zip(lon.f64()?)
+        .enumerate()
+        .filter_map(|(row, point)| match point {
+            (Some(lat), Some(lon)) => Some((row, lat, lon)),
+            _ => None,
+        })
+        .collect();
+    let mut keep = vec![false; lat.len()];
+    if let Some(&(_, origin_lat, origin_lon)) = positions.first() {
+        let x_scale = EARTH_RADIUS * origin_lat.to_radians().cos();
+        let points: Vec<(f64, f64)> = positions
+            .iter()
+            .map(|&(_, lat, lon)| ((lon - origin_lon).to_radians() * x_scale, (lat - origin_lat).to_radians() * EARTH_RADIUS))
+            .collect();
+
+        let mut kept = vec![false; points.len()];
+        kept[0] = true;
+        kept[points.len() - 1] = true;
+        // Spans still to split, by first and last point
+        let mut spans = vec![(0, points.len() - 1)];
+        while let Some((first, last)) = spans.pop() {
+            let farthest = (first + 1..last)
+                .map(|i| (i, segment_distance(points[i], points[first], points[last])))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((i, distance)) = farthest {
+                if distance > kwargs.tolerance {
+                    kept[i] = true;
+                    spans.push((first, i));
+                    spans.push((i, last));
+                }
+            }
+        }
+        for (&(row, _, _), kept) in positions.iter().zip(kept) {
+            keep[row] = kept;
+        }
+    }
+
+    let mask: BooleanChunked = keep.into_iter().map(Some).collect();
+    Ok(mask.with_name(lat.name().clone()).into_series())
+}
+
+#[derive(Deserialize)]
+struct WPrimeBalanceKwargs {
+    cp: f64,
+    wprime: f64,
+}
+
+/// Remaining anaerobic work capacity in joules by the differential form of Skiba's
+/// W'bal model. Each sample's power applies over the interval since the previous
+/// sample: above critical power the excess depletes W', below it W' recovers
+/// exponentially with a time constant of W' / (CP - P). Samples without a power
+/// or timestamp carry the balance forward.
+#[polars_expr(output_type = Float64)]
+fn pl_wprime_balance(inputs: &[Series], kwargs: WPrimeBalanceKwargs) -> PolarsResult<Series> {
+    wprime_balance(&inputs[0], &inputs[1], &kwargs)
+}
+
+/// The computation of `pl_wprime_balance`, callable without the plugin interface
+fn wprime_balance(timestamp: &Series, power: &Series, kwargs: &WPrimeBalanceKwargs) -> PolarsResult<Series> {
+    polars_ensure!(kwargs.wprime > 0.0, ComputeError: "wprime must be positive");
+    let seconds = timestamp_seconds(timestamp)?;
+    let power = power.cast(&DataType::Float64)?;
+
+    let mut last: Option<f64> = None;
+    let mut balance = kwargs.wprime;
+    let wbal: Float64Chunked = seconds
+        .into_iter()
+        .zip(power.f64()?)
+        .map(|(time, power)| {
+            if let (Some(time), Some(power)) = (time, power) {
+                let interval = last.map_or(0.0, |last| (time - last).max(0.0));
+                if power > kwargs.cp {
+                    balance -= (power - kwargs.cp) * interval;
+                } else {
+                    let recovered = (-(kwargs.cp - power) * interval / kwargs.wprime).exp();
+                    balance = kwargs.wprime - (kwargs.wprime - balance) * recovered;
+                }
+                last = Some(time);
+            }
+            Some(balance)
+        })
+        .collect();
+    Ok(wbal.with_name(power.name().clone()).into_series())
+}
+
+#[derive(Deserialize)]
+struct InterpolateGpsKwargs {
+    max_gap: usize,
+}
+
+/// Linearly interpolate runs of at most `max_gap` null positions in degrees between
+/// the valid fixes around them, leaving leading, trailing and longer runs null.
+/// Steps across the antimeridian go the short way round.
+#[polars_expr(output_type = Float64)]
+fn pl_interpolate_gps(inputs: &[Series], kwargs: InterpolateGpsKwargs) -> PolarsResult<Series> {
+    let degrees = inputs[0].cast(&DataType::Float64)?;
+    let mut values: Vec<Option<f64>> = degrees.f64()?.into_iter().collect();
+
+    let mut last: Option<(usize, f64)> = None;
+    for row in 0..values.len() {
+        let Some(end) = values[row] else { continue };
+        if let Some((start_row, start)) = last {
+            let gap = row - start_row - 1;
+            if gap > 0 && gap <= kwargs.max_gap {
+                let mut step = end - start;
+                if step > 180.0 {
+                    step -= 360.0;
+                } else if step < -180.0 {
+                    step += 360.0;
+                }
+                for missing in 1..=gap {
+                    let value = start + step * missing as f64 / (gap + 1) as f64;
+                    values[start_row + missing] = Some(if value < -180.0 {
+                        value + 360.0
+                    } else if value >= 180.0 {
+                        value - 360.0
+                    } else {
+                        value
+                    });
+                }
+            }
+        }
+        last = Some((row, end));
+    }
+
+    let interpolated: Float64Chunked = values.into_iter().collect();
+    Ok(interpolated.with_name(degrees.name().clone()).into_series())
+}
+
+/// Compass bearing in degrees from 0 to 360 of each position, from the last valid
+/// position before it, by the initial great-circle course. Null for the first
+/// position, rows without one and positions that didn't move.
+#[polars_expr(output_type = Float64)]
+fn pl_bearing(inputs: &[Series]) -> PolarsResult<Series> {
+    let lat = inputs[0].cast(&DataType::Float64)?;
+    let lon = inputs[1].cast(&DataType::Float64)?;
+
+    let mut last: Option<(f64, f64)> = None;
+    let bearing: Float64Chunked = lat
+        .f64()?
+        .into_iter()
+        .zip(lon.f64()?)
+        .map(|point| {
+            let (Some(lat), Some(lon)) = point else { return None };
+            let (last_lat, last_lon) = last.replace((lat, lon))?;
+            let (lat1, lat2) = (last_lat.to_radians(), lat.to_radians());
+            let d_lon = (lon - last_lon).to_radians();
+            let y = d_lon.sin() * lat2.cos();
+            let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+            if x == 0.0 && y == 0.0 {
+                return None;
+            }
+            Some(y.atan2(x).to_degrees().rem_euclid(360.0))
+        })
+        .collect();
+    Ok(bearing.with_name(lat.name().clone()).into_series())
+}
+
+#[derive(Deserialize)]
+struct VerticalSpeedKwargs {
+    window: f64,
+}
+
+/// Vertical speed (VAM) in meters per hour from altitude in meters, measured from
+/// the nearest earlier point at least `window` seconds back. Null until a full
+/// window has been covered, where either input is null and where that point is
+/// over two windows back, e.g. after a pause, so gaps don't smear or spike.
+#[polars_expr(output_type = Float64)]
+fn pl_vertical_speed(inputs: &[Series], kwargs: VerticalSpeedKwargs) -> PolarsResult<Series> {
+    polars_ensure!(kwargs.window > 0.0, ComputeError: "window must be positive");
+    let altitude = inputs[0].cast(&DataType::Float64)?;
+    let seconds = timestamp_seconds(&inputs[1])?;
+    let window = kwargs.window;
+
+    // Valid points so far, and the index of the start of the current window
+    let mut points: Vec<(f64, f64)> = Vec::new();
+    let mut start = 0;
+    let vam: Float64Chunked = seconds
+        .into_iter()
+        .zip(altitude.f64()?)
+        .map(|point| {
+            let (Some(time), Some(altitude)) = point else {
+                return None;
+            };
+            points.push((time, altitude));
+            while start + 1 < points.len() && time - points[start + 1].0 >= window {
+                start += 1;
+            }
+            let (start_time, start_altitude) = points[start];
+            let span = time - start_time;
+            (span >= window && span <= 2.0 * window).then(|| (altitude - start_altitude) / span * 3600.0)
+        })
+        .collect();
+    Ok(vam.with_name(altitude.name().clone()).into_series())
+}
+
+#[derive(Deserialize)]
+struct FindPeaksKwargs {
+    min_prominence: f64,
+    min_distance: usize,
+}
+
+/// Mask of the local maxima of a series with a prominence of at least
+/// `min_prominence`, at least `min_distance` rows apart. A plateau peaks at its
+/// middle. Prominence is the height above the higher of the lowest points either
+/// side before the series rises above the peak. Of peaks too close together the
+/// highest is kept. Nulls are skipped.
+#[polars_expr(output_type = Boolean)]
+fn pl_find_peaks(inputs: &[Series], kwargs: FindPeaksKwargs) -> PolarsResult<Series> {
+    find_peaks(&inputs[0], &kwargs)
+}
+
+/// The computation of `pl_find_peaks`, callable without the plugin interface
+fn find_peaks(input: &Series, kwargs: &FindPeaksKwargs) -> PolarsResult<Series> {
+    let input = input.cast(&DataType::Float64)?;
+    let (rows, values): (Vec<usize>, Vec<f64>) = input
+        .f64()?
+        .into_iter()
+        .enumerate()
+        .filter_map(|(row, value)| value.map(|value| (row, value)))
+        .unzip();
+
+    // Local maxima, by index into the valid values
+    let mut peaks = Vec::new();
+    let mut i = 1;
+    while i + 1 < values.len() {
+        if values[i] > values[i - 1] {
+            let mut end = i;
+            while end + 1 < values.len() && values[end + 1] == values[i] {
+                end += 1;
+            }
+            if end + 1 < values.len() && values[end + 1] < values[i] {
+                peaks.push((i + end) / 2);
+            }
+            i = end;
+        }
+        i += 1;
+    }
+
+    let prominent: Vec<usize> = peaks
+        .into_iter()
+        .filter(|&peak| {
+            let height = values[peak];
+            let base = |range: &mut dyn Iterator<Item = usize>| {
+                range.map(|j| values[j]).take_while(|&value| value <= height).fold(height, f64::min)
+            };
+            let left = base(&mut (0..peak).rev());
+            let right = base(&mut (peak + 1..values.len()));
+            height - left.max(right) >= kwargs.min_prominence
+        })
+        .collect();
+
+    // Keep the highest peaks first, dropping the lower ones too close to them
+    let mut by_height = prominent.clone();
+    by_height.sort_by(|&a, &b| values[b].total_cmp(&values[a]).then(a.cmp(&b)));
+    let mut kept: Vec<usize> = Vec::new();
+    for peak in by_height {
+        if kept.iter().all(|&other| rows[peak].abs_diff(rows[other]) >= kwargs.min_distance) {
+            kept.push(peak);
+        }
+    }
+
+    let mut mask = vec![false; input.len()];
+    for peak in kept {
+        mask[rows[peak]] = true;
+    }
+    let mask: BooleanChunked = mask.into_iter().map(Some).collect();
+    Ok(mask.with_name(input.name().clone()).into_series())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(series: PolarsResult<Series>) -> Vec<Option<f64>> {
+        series.unwrap().f64().unwrap().into_iter().collect()
+    }
+
+    fn mask(series: PolarsResult<Series>) -> Vec<bool> {
+        series.unwrap().bool().unwrap().into_iter().map(Option::unwrap).collect()
+    }
+
+    fn assert_close(actual: Vec<Option<f64>>, expected: &[Option<f64>]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            match (actual, expected) {
+                (Some(a), Some(e)) => assert!((a - e).abs() < 1e-9, "{a} != {e}"),
+                _ => assert_eq!(actual, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn grade_is_measured_over_the_window() {
+        let distance = Series::new("distance".into(), [0.0, 5.0, 10.0, 15.0, 20.0]);
+        let altitude = Series::new("altitude".into(), [Some(100.0), None, Some(101.0), Some(102.0), Some(103.0)]);
+
+        let grade = grade(&distance, &altitude, &GradeKwargs { window: 10.0 });
+
+        // The first point is short of a window and the second has no altitude;
+        // the last is measured from 10 m back
+        assert_close(values(grade), &[None, None, Some(10.0), Some(200.0 / 15.0), Some(20.0)]);
+    }
+
+    #[test]
+    fn elevation_changes_commit_moves_past_the_threshold() {
+        let altitude = Series::new("altitude".into(), [Some(100.0), None, Some(102.0), Some(104.0), Some(99.0), Some(98.0)]);
+
+        // The first 2 m rise is jitter; the climb to 104 m and the drop to 99 m
+        // commit, and the last meter down doesn't
+        assert_eq!(elevation_changes(&altitude, 3.0).unwrap(), Some((4.0, 5.0)));
+        assert_eq!(elevation_changes(&altitude, 0.0).unwrap(), Some((4.0, 6.0)));
+        let empty = Series::new("altitude".into(), [None::<f64>]);
+        assert_eq!(elevation_changes(&empty, 3.0).unwrap(), None);
+    }
+
+    #[test]
+    fn find_peaks_keeps_prominent_peaks_far_enough_apart() {
+        let series = Series::new("power".into(), [0.0, 3.0, 1.0, 5.0, 5.0, 5.0, 0.0, 2.0, 0.0]);
+        let peaks = |min_prominence, min_distance| {
+            let kwargs = FindPeaksKwargs { min_prominence, min_distance };
+            let mask = mask(find_peaks(&series, &kwargs));
+            mask.iter().enumerate().filter(|(_, peak)| **peak).map(|(row, _)| row).collect::<Vec<_>>()
+        };
+
+        // The plateau on rows 3 to 5 peaks in its middle
+        assert_eq!(peaks(1.0, 1), [1, 4, 7]);
+        assert_eq!(peaks(2.5, 1), [4]);
+        assert_eq!(peaks(1.0, 3), [1, 4, 7]);
+        assert_eq!(peaks(1.0, 4), [4]);
+
+        let with_null = Series::new("power".into(), [Some(0.0), None, Some(3.0), Some(1.0)]);
+        let kwargs = FindPeaksKwargs { min_prominence: 1.0, min_distance: 1 };
+        assert_eq!(mask(find_peaks(&with_null, &kwargs)), [false, false, true, false]);
+    }
+
+    #[test]
+    fn wprime_balance_depletes_above_and_recovers_below_cp() {
+        let timestamp = Series::new("timestamp".into(), [0i64, 1, 2, 3, 4]);
+        let power = Series::new("power".into(), [Some(300.0), Some(300.0), Some(300.0), None, Some(100.0)]);
+        let kwargs = WPrimeBalanceKwargs { cp: 200.0, wprime: 20_000.0 };
+
+        let balance = wprime_balance(&timestamp, &power, &kwargs);
+
+        // The sample without power carries the balance forward, and recovery
+        // covers the two seconds since the last power sample
+        let recovered = 20_000.0 - 200.0 * (-100.0 * 2.0 / 20_000.0f64).exp();
+        assert_close(values(balance), &[Some(20_000.0), Some(19_900.0), Some(19_800.0), Some(19_800.0), Some(recovered)]);
+
+        let invalid = WPrimeBalanceKwargs { cp: 200.0, wprime: 0.0 };
+        assert!(wprime_balance(&timestamp, &power, &invalid).is_err());
+    }
+}
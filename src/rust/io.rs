@@ -1,143 +1,1824 @@
-use std::path::PathBuf;
 use pyo3::prelude::*;
 use pyo3_polars::PyDataFrame;
 use polars::prelude::*;
-use fit::{Fit, Value};
-use std::collections::HashMap;
+use pyo3::types::{PyBytes, PyDict};
+use fitsdk::{match_message_field, match_message_offset, match_message_scale, match_predefined_field_value, match_messagetype, FieldType, MessageType};
+use flate2::read::MultiGzDecoder;
+use indexmap::IndexMap;
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::components::{components, is_accumulated_total, unpack, Accumulator, Component};
+use crate::decoder::{BaseType, DecodeError, Field, FileHeader, FitDecoder, Message, StringEncoding, Value, FIT_EPOCH_OFFSET, TIMESTAMP_FIELD};
+use crate::encoder::{FieldDefinition, FitEncoder};
+use crate::manufacturers::{manufacturer_name, product_name};
+use crate::profile::{message_alias, profile_message, MessageProfile};
+use crate::subfields::{active_subfield, SubField};
+
+/// Size of the longer of the two FIT header layouts
+const MAX_HEADER_SIZE: u64 = 14;
+
+/// Global message numbers of the messages written by `write_records_to_fit`
+const FILE_ID_MESSAGE: u16 = 0;
+const RECORD_MESSAGE: u16 = 20;
+
+/// Record fields of the GPS position, the only positions `convert_gps` applies to
+const RECORD_POSITION_FIELDS: [usize; 2] = [0, 1];
+
+/// file_id values marking a written file as an activity from a development device
+const FILE_TYPE_ACTIVITY: u8 = 4;
+const MANUFACTURER_DEVELOPMENT: u16 = 255;
+
+/// Leading bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Messages decoded between calls of the `progress` callback
+const PROGRESS_INTERVAL: usize = 10_000;
+
+/// Degrees per semicircle, the unit FIT uses for positions
+pub(crate) const DEGREES_PER_SEMICIRCLE: f64 = 180.0 / 2147483648.0;
+
+/// Options controlling how field values are decoded into columns
+#[derive(Clone, Debug)]
+pub struct ReadOptions {
+    /// Emit timestamp fields as UTC Datetime columns instead of raw integers
+    pub decode_timestamps: bool,
+    /// Apply the FIT profile scale and offset to numeric fields
+    pub apply_scale: bool,
+    /// Convert the record position fields from semicircles to degrees. The
+    /// positions of other message types are always converted.
+    pub convert_gps: bool,
+    /// Decode enum fields to their FIT profile names
+    pub decode_enums: bool,
+    /// Reject files whose header or file CRC doesn't match
+    pub verify_crc: bool,
+    /// Dtypes to cast columns to, by column name, instead of the inferred ones
+    pub schema: Option<HashMap<String, DataType>>,
+    /// Skip messages timestamped before this many Unix seconds
+    pub start_time: Option<f64>,
+    /// Skip messages timestamped after this many Unix seconds
+    pub end_time: Option<f64>,
+    /// Add a leading `message_index` column numbering the messages of each type
+    pub add_index: bool,
+    /// Treat the FIT "invalid" sentinel of each base type as a missing value
+    pub mask_invalid: bool,
+    /// Only decode the fields with these column names, after mapping
+    pub fields: Option<HashSet<String>>,
+    /// Called with the number of messages decoded so far, every `PROGRESS_INTERVAL`
+    /// messages and once at the end
+    pub progress: Option<PyObject>,
+    /// Stop at the first malformed message with a warning, keeping the messages
+    /// before it, instead of failing
+    pub truncated_ok: bool,
+    /// Stop after this many rows of each message type
+    pub limit: Option<usize>,
+    /// Build columns of decoded enum names as Categorical instead of String
+    pub categorical_enums: bool,
+    /// Sort the rows of each message type by timestamp
+    pub sort_by_time: bool,
+    /// Drop rows whose timestamp repeats an earlier row's
+    pub dedup_time: bool,
+    /// Keep the last rather than the first row of each repeated timestamp
+    pub dedup_keep_last: bool,
+    /// Cast every numeric column to Float64, except those given in `schema`
+    pub all_float: bool,
+    /// Also emit the undecoded value of scaled, semicircle and enum fields in a
+    /// `<name>_raw` column
+    pub keep_raw: bool,
+    /// Character encoding of string fields
+    pub encoding: StringEncoding,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions {
+            decode_timestamps: true,
+            apply_scale: true,
+            convert_gps: true,
+            decode_enums: true,
+            verify_crc: false,
+            schema: None,
+            start_time: None,
+            end_time: None,
+            add_index: false,
+            mask_invalid: true,
+            fields: None,
+            progress: None,
+            truncated_ok: true,
+            limit: None,
+            categorical_enums: true,
+            sort_by_time: false,
+            dedup_time: false,
+            dedup_keep_last: false,
+            all_float: false,
+            keep_raw: false,
+            encoding: StringEncoding::Utf8,
+        }
+    }
+}
+
+impl ReadOptions {
+    /// Build options from the keyword arguments passed to a read function
+    pub fn from_kwargs(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let mut options = ReadOptions::default();
+        let Some(kwargs) = kwargs else {
+            return Ok(options);
+        };
+        for (key, value) in kwargs.iter() {
+            let key: String = key.extract()?;
+            match key.as_str() {
+                "decode_timestamps" => options.decode_timestamps = value.extract()?,
+                "apply_scale" => options.apply_scale = value.extract()?,
+                "convert_gps" => options.convert_gps = value.extract()?,
+                "decode_enums" => options.decode_enums = value.extract()?,
+                "verify_crc" => options.verify_crc = value.extract()?,
+                "start_time" => options.start_time = value.extract()?,
+                "end_time" => options.end_time = value.extract()?,
+                "add_index" => options.add_index = value.extract()?,
+                "mask_invalid" => options.mask_invalid = value.extract()?,
+                "progress" => options.progress = value.extract()?,
+                "truncated_ok" => options.truncated_ok = value.extract()?,
+                "limit" => options.limit = value.extract()?,
+                "categorical_enums" => options.categorical_enums = value.extract()?,
+                "sort_by_time" => options.sort_by_time = value.extract()?,
+                "dedup_time" => options.dedup_time = value.extract()?,
+                "all_float" => options.all_float = value.extract()?,
+                "keep_raw" => options.keep_raw = value.extract()?,
+                "dedup_keep" => {
+                    let keep: String = value.extract()?;
+                    options.dedup_keep_last = match keep.as_str() {
+                        "first" => false,
+                        "last" => true,
+                        _ => {
+                            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                                "dedup_keep must be 'first' or 'last', got '{}'",
+                                keep
+                            )))
+                        }
+                    };
+                }
+                "encoding" => {
+                    let encoding: String = value.extract()?;
+                    options.encoding = match encoding.to_lowercase().replace('_', "-").as_str() {
+                        "utf-8" | "utf8" => StringEncoding::Utf8,
+                        "latin-1" | "latin1" | "iso-8859-1" => StringEncoding::Latin1,
+                        _ => {
+                            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                                "encoding must be 'utf-8' or 'latin-1', got '{}'",
+                                encoding
+                            )))
+                        }
+                    };
+                }
+                "fields" => {
+                    let fields: Option<Vec<String>> = value.extract()?;
+                    options.fields = fields.map(|fields| fields.into_iter().collect());
+                }
+                "schema" => {
+                    let schema: Option<HashMap<String, String>> = value.extract()?;
+                    options.schema = schema
+                        .map(|schema| {
+                            schema
+                                .into_iter()
+                                .map(|(column, dtype)| Ok((column, parse_dtype(&dtype)?)))
+                                .collect::<PyResult<_>>()
+                        })
+                        .transpose()?;
+                }
+                _ => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                        "Unexpected keyword argument '{}'",
+                        key
+                    )))
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    /// Pass the number of messages decoded so far to the progress callback, if
+    /// one is due. The GIL is only taken for the call itself.
+    fn report_progress(&self, count: usize, done: bool) -> PyResult<()> {
+        let Some(callback) = &self.progress else {
+            return Ok(());
+        };
+        // The final count is skipped if it was just reported
+        let on_interval = count.is_multiple_of(PROGRESS_INTERVAL);
+        let due = if done { !on_interval } else { on_interval };
+        if due {
+            Python::with_gil(|py| callback.call1(py, (count,)))?;
+        }
+        Ok(())
+    }
+
+    /// Whether a column is among the requested fields
+    fn wants(&self, column: &str) -> bool {
+        self.fields.as_ref().is_none_or(|fields| fields.contains(column))
+    }
+
+    /// Whether a message falls in the requested time range. When a range is
+    /// given, messages without a timestamp are outside it.
+    fn in_time_range(&self, message: &Message) -> bool {
+        if self.start_time.is_none() && self.end_time.is_none() {
+            return true;
+        }
+        let Some(timestamp) = message.timestamp() else {
+            return false;
+        };
+        let unix_seconds = timestamp as f64 + FIT_EPOCH_OFFSET as f64;
+        self.start_time.is_none_or(|start| unix_seconds >= start) && self.end_time.is_none_or(|end| unix_seconds <= end)
+    }
+}
+
+/// Parse a dtype name as accepted by the `schema` option, e.g. "f64" or "list[u8]"
+fn parse_dtype(name: &str) -> PyResult<DataType> {
+    let name = name.trim().to_lowercase();
+    if let Some(inner) = name.strip_prefix("list[").and_then(|rest| rest.strip_suffix(']')) {
+        return Ok(DataType::List(Box::new(parse_dtype(inner)?)));
+    }
+    let dtype = match name.as_str() {
+        "i8" | "int8" => DataType::Int8,
+        "i16" | "int16" => DataType::Int16,
+        "i32" | "int32" => DataType::Int32,
+        "i64" | "int64" => DataType::Int64,
+        "u8" | "uint8" => DataType::UInt8,
+        "u16" | "uint16" => DataType::UInt16,
+        "u32" | "uint32" => DataType::UInt32,
+        "u64" | "uint64" => DataType::UInt64,
+        "f32" | "float32" => DataType::Float32,
+        "f64" | "float64" => DataType::Float64,
+        "bool" | "boolean" => DataType::Boolean,
+        "str" | "string" | "utf8" => DataType::String,
+        "cat" | "categorical" => DataType::Categorical(None, CategoricalOrdering::Physical),
+        "datetime" => DataType::Datetime(TimeUnit::Milliseconds, Some("UTC".into())),
+        _ => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Unknown dtype '{}' in schema",
+                name
+            )))
+        }
+    };
+    Ok(dtype)
+}
+
+/// Name of a dtype in the vocabulary of the `schema` option
+fn dtype_name(dtype: &DataType) -> String {
+    match dtype {
+        DataType::Int8 => "i8".to_string(),
+        DataType::Int16 => "i16".to_string(),
+        DataType::Int32 => "i32".to_string(),
+        DataType::Int64 => "i64".to_string(),
+        DataType::UInt8 => "u8".to_string(),
+        DataType::UInt16 => "u16".to_string(),
+        DataType::UInt32 => "u32".to_string(),
+        DataType::UInt64 => "u64".to_string(),
+        DataType::Float32 => "f32".to_string(),
+        DataType::Float64 => "f64".to_string(),
+        DataType::Boolean => "bool".to_string(),
+        DataType::String => "str".to_string(),
+        DataType::Categorical(_, _) => "cat".to_string(),
+        DataType::Datetime(_, _) => "datetime".to_string(),
+        DataType::List(inner) => format!("list[{}]", dtype_name(inner)),
+        other => other.to_string(),
+    }
+}
 
 /// Read record messages from a .fit file and return as a Polars DataFrame
 /// with optional field mapping
 #[pyfunction]
-#[pyo3(signature = (file_path, field_mapping = None))]
-pub fn read_recordmesgs(file_path: &str, field_mapping: Option<HashMap<String, String>>) -> PyResult<PyDataFrame> {
-    read_generic_messages(file_path, "record", field_mapping)
+#[pyo3(signature = (file_path, field_mapping = None, **options))]
+pub fn read_recordmesgs(py: Python<'_>, file_path: &str, field_mapping: Option<HashMap<String, String>>, options: Option<&Bound<'_, PyDict>>) -> PyResult<PyDataFrame> {
+    let options = ReadOptions::from_kwargs(options)?;
+    py.allow_threads(|| {
+        let data = read_file(file_path)?;
+        read_generic_messages(&data, "record", field_mapping.as_ref(), &options)
+    })
+}
+
+/// Read record messages from the bytes of a FIT file and return as a Polars DataFrame
+/// with optional field mapping
+#[pyfunction]
+#[pyo3(signature = (data, field_mapping = None, **options))]
+pub fn read_recordmesgs_from_bytes(py: Python<'_>, data: &[u8], field_mapping: Option<HashMap<String, String>>, options: Option<&Bound<'_, PyDict>>) -> PyResult<PyDataFrame> {
+    let options = ReadOptions::from_kwargs(options)?;
+    py.allow_threads(|| {
+        let data = maybe_decompress(data)?;
+        read_generic_messages(&data, "record", field_mapping.as_ref(), &options)
+    })
 }
 
 /// Get all available message types in a FIT file
 #[pyfunction]
-pub fn get_message_types(file_path: &str) -> PyResult<Vec<String>> {
-    let path = PathBuf::from(file_path);
-    let fit = Fit::new(&path);
+pub fn get_message_types(py: Python<'_>, file_path: &str) -> PyResult<Vec<String>> {
+    py.allow_threads(|| {
+        let data = read_file(file_path)?;
+        let fit = FitDecoder::new(&data).map_err(decode_error)?;
 
-    let mut message_types = std::collections::HashSet::new();
+        let mut message_types = HashSet::new();
 
-    for message in fit {
-        let msg_type = format!("{:?}", message.kind).to_lowercase();
-        message_types.insert(msg_type);
-    }
+        for message in fit {
+            let message = message.map_err(decode_error)?;
+            message_types.insert(message.global_num);
+        }
 
-    let mut result: Vec<String> = message_types.into_iter().collect();
-    result.sort();
-    Ok(result)
+        let mut result: Vec<String> = message_types.into_iter().map(message_type_name).collect();
+        result.sort();
+        Ok(result)
+    })
+}
+
+/// Read the header of a FIT file, without reading the rest of the file
+#[pyfunction]
+pub fn read_header(py: Python<'_>, file_path: &str) -> PyResult<PyObject> {
+    let start = read_file_start(file_path, MAX_HEADER_SIZE)?;
+    let header = FileHeader::parse(&start).map_err(decode_error)?;
+
+    let result = PyDict::new_bound(py);
+    result.set_item("header_size", header.header_size)?;
+    result.set_item("protocol_version", header.protocol())?;
+    result.set_item("profile_version", header.profile())?;
+    result.set_item("data_size", header.data_size)?;
+    result.set_item("data_type", ".FIT")?;
+    result.set_item("header_crc", header.crc)?;
+    Ok(result.into())
+}
+
+/// Count the messages of each type in a FIT file
+#[pyfunction]
+pub fn get_message_counts(py: Python<'_>, file_path: &str) -> PyResult<HashMap<String, usize>> {
+    py.allow_threads(|| {
+        let data = read_file(file_path)?;
+        let fit = FitDecoder::new(&data).map_err(decode_error)?;
+
+        let mut counts: HashMap<u16, usize> = HashMap::new();
+
+        for message in fit {
+            let message = message.map_err(decode_error)?;
+            *counts.entry(message.global_num).or_insert(0) += 1;
+        }
+
+        Ok(counts.into_iter().map(|(global_num, count)| (message_type_name(global_num), count)).collect())
+    })
+}
+
+/// Get the global message number of a message type and the numbers of the fields
+/// holding a value in at least one of its messages, in ascending order
+#[pyfunction]
+pub fn get_field_numbers(py: Python<'_>, file_path: &str, message_type: &str) -> PyResult<(Option<u16>, Vec<u8>)> {
+    py.allow_threads(|| {
+        let data = read_file(file_path)?;
+        let global_num = parse_message_type(message_type);
+        let fit = FitDecoder::new(&data).map_err(decode_error)?;
+
+        let mut numbers = BTreeSet::new();
+        for message in fit {
+            let message = message.map_err(decode_error)?;
+            if Some(message.global_num) == global_num {
+                numbers.extend(message.fields.iter().filter(|field| field.is_valid()).map(|field| field.number));
+            }
+        }
+
+        Ok((global_num, numbers.into_iter().collect()))
+    })
+}
+
+/// Name of a manufacturer id from the FIT profile, e.g. "garmin" for 1
+#[pyfunction]
+pub fn decode_manufacturer(manufacturer: u16) -> String {
+    manufacturer_name(manufacturer)
+}
+
+/// Name of a product id of a manufacturer from the FIT profile, e.g. "edge1000"
+/// for Garmin product 1836
+#[pyfunction]
+pub fn decode_product(manufacturer: u16, product: u16) -> String {
+    product_name(manufacturer, product)
 }
 
 /// Read messages of a specific type from a .fit file and return as a Polars DataFrame
 /// with optional field mapping
 #[pyfunction]
-#[pyo3(signature = (file_path, message_type, field_mapping = None))]
-pub fn read_data(file_path: &str, message_type: &str, field_mapping: Option<HashMap<String, String>>) -> PyResult<PyDataFrame> {
-    read_generic_messages(file_path, message_type, field_mapping)
+#[pyo3(signature = (file_path, message_type, field_mapping = None, **options))]
+pub fn read_data(py: Python<'_>, file_path: &str, message_type: &str, field_mapping: Option<HashMap<String, String>>, options: Option<&Bound<'_, PyDict>>) -> PyResult<PyDataFrame> {
+    let options = ReadOptions::from_kwargs(options)?;
+    py.allow_threads(|| {
+        let data = read_file(file_path)?;
+        read_generic_messages(&data, message_type, field_mapping.as_ref(), &options)
+    })
 }
 
-/// Internal function to read generic messages from a FIT file
-fn read_generic_messages(file_path: &str, message_type: &str, field_mapping: Option<HashMap<String, String>>) -> PyResult<PyDataFrame> {
-    let path = PathBuf::from(file_path);
+/// Read messages of several types from a .fit file in one pass, returning a Polars
+/// DataFrame per type with optional field mappings by type
+#[pyfunction]
+#[pyo3(signature = (file_path, message_types, field_mappings = None, **options))]
+pub fn read_data_by_type(py: Python<'_>, file_path: &str, message_types: Vec<String>, field_mappings: Option<HashMap<String, HashMap<String, String>>>, options: Option<&Bound<'_, PyDict>>) -> PyResult<HashMap<String, PyDataFrame>> {
+    let options = ReadOptions::from_kwargs(options)?;
+    py.allow_threads(|| {
+        let data = read_file(file_path)?;
+        let fit = open_decoder(&data, &options)?;
+        let mut builders: Vec<(&String, Option<u16>, FrameBuilder)> = message_types
+            .iter()
+            .map(|message_type| {
+                let global_num = parse_message_type(message_type);
+                let field_mapping = field_mappings.as_ref().and_then(|mappings| mappings.get(message_type));
+                let capacity = count_messages(&data, global_num).min(options.limit.unwrap_or(usize::MAX));
+                let builder = FrameBuilder::new(field_mapping, &options, capacity);
+                (message_type, global_num, builder)
+            })
+            .collect();
 
-    // Parse the FIT file
-    let fit = Fit::new(&path);
+        let mut messages_read = 0;
+        for message in read_messages(fit, &options) {
+            let message = message?;
+            messages_read += 1;
+            options.report_progress(messages_read, false)?;
+            for (_, global_num, builder) in &mut builders {
+                if Some(message.global_num) == *global_num {
+                    builder.push(&message);
+                }
+            }
+            if builders.iter().all(|(_, _, builder)| builder.is_full()) {
+                break;
+            }
+        }
+        options.report_progress(messages_read, true)?;
+
+        builders
+            .into_iter()
+            .map(|(message_type, _, builder)| Ok((message_type.clone(), PyDataFrame(builder.finish()?))))
+            .collect()
+    })
+}
+
+/// Read messages of a specific type from the bytes of a FIT file and return as a
+/// Polars DataFrame with optional field mapping
+#[pyfunction]
+#[pyo3(signature = (data, message_type, field_mapping = None, **options))]
+pub fn read_data_from_bytes(py: Python<'_>, data: &[u8], message_type: &str, field_mapping: Option<HashMap<String, String>>, options: Option<&Bound<'_, PyDict>>) -> PyResult<PyDataFrame> {
+    let options = ReadOptions::from_kwargs(options)?;
+    py.allow_threads(|| {
+        let data = maybe_decompress(data)?;
+        read_generic_messages(&data, message_type, field_mapping.as_ref(), &options)
+    })
+}
+
+/// Read messages of a specific type from a .fit file and return them as the bytes
+/// of an Arrow IPC stream, for Arrow consumers other than Polars
+#[pyfunction]
+#[pyo3(signature = (file_path, message_type, field_mapping = None, **options))]
+pub fn read_data_arrow_ipc(py: Python<'_>, file_path: &str, message_type: &str, field_mapping: Option<HashMap<String, String>>, options: Option<&Bound<'_, PyDict>>) -> PyResult<Py<PyBytes>> {
+    let options = ReadOptions::from_kwargs(options)?;
+    let stream = py.allow_threads(|| {
+        let data = read_file(file_path)?;
+        let PyDataFrame(mut df) = read_generic_messages(&data, message_type, field_mapping.as_ref(), &options)?;
+        let mut stream = Vec::new();
+        IpcStreamWriter::new(&mut stream).finish(&mut df).map_err(polars_error)?;
+        Ok::<_, PyErr>(stream)
+    })?;
+    Ok(PyBytes::new_bound(py, &stream).unbind())
+}
+
+/// Read messages of a specific type from many .fit files in parallel, returning one
+/// entry per file in input order: a DataFrame, or the exception raised for that file
+#[pyfunction]
+#[pyo3(signature = (file_paths, message_type, field_mapping = None, **options))]
+pub fn read_many_data(py: Python<'_>, file_paths: Vec<String>, message_type: &str, field_mapping: Option<HashMap<String, String>>, options: Option<&Bound<'_, PyDict>>) -> PyResult<Vec<PyObject>> {
+    let options = ReadOptions::from_kwargs(options)?;
 
-    // Prepare data structures for DataFrame construction
-    let mut columns: HashMap<String, Vec<AnyValue>> = HashMap::new();
-    let mut column_order = Vec::new();
+    // Files are independent, so parse them on the rayon pool without holding the GIL
+    let results: Vec<PyResult<PyDataFrame>> = py.allow_threads(|| {
+        file_paths
+            .par_iter()
+            .map(|file_path| {
+                let data = read_file(file_path)?;
+                read_generic_messages(&data, message_type, field_mapping.as_ref(), &options)
+            })
+            .collect()
+    });
+
+    Ok(results
+        .into_iter()
+        .map(|result| match result {
+            Ok(df) => df.into_py(py),
+            Err(e) => e.into_value(py).into_py(py),
+        })
+        .collect())
+}
+
+/// Get the column names and dtypes a `read_data` call would produce, in column order
+#[pyfunction]
+#[pyo3(signature = (file_path, message_type, field_mapping = None, **options))]
+pub fn get_schema(py: Python<'_>, file_path: &str, message_type: &str, field_mapping: Option<HashMap<String, String>>, options: Option<&Bound<'_, PyDict>>) -> PyResult<Vec<(String, String)>> {
+    let options = ReadOptions::from_kwargs(options)?;
+    py.allow_threads(|| {
+        let data = read_file(file_path)?;
+        let PyDataFrame(df) = read_generic_messages(&data, message_type, field_mapping.as_ref(), &options)?;
+        Ok(df
+            .get_columns()
+            .iter()
+            .map(|column| (column.name().to_string(), dtype_name(column.dtype())))
+            .collect())
+    })
+}
+
+/// Read all message types from a .fit file in a single pass and return a Polars
+/// DataFrame per message type with optional field mapping
+#[pyfunction]
+#[pyo3(signature = (file_path, field_mapping = None, **options))]
+pub fn read_all_messages(py: Python<'_>, file_path: &str, field_mapping: Option<HashMap<String, String>>, options: Option<&Bound<'_, PyDict>>) -> PyResult<HashMap<String, PyDataFrame>> {
+    let options = ReadOptions::from_kwargs(options)?;
+    py.allow_threads(|| {
+        let data = read_file(file_path)?;
+        let fit = open_decoder(&data, &options)?;
+        let mut counts: HashMap<u16, usize> = HashMap::new();
+        for global_num in FitDecoder::new(&data).map_err(decode_error)?.message_numbers() {
+            *counts.entry(global_num).or_insert(0) += 1;
+        }
+
+        // Bin messages by type
+        let mut builders: HashMap<u16, FrameBuilder> = HashMap::new();
+        let mut messages_read = 0;
+        for message in read_messages(fit, &options) {
+            let message = message?;
+            messages_read += 1;
+            options.report_progress(messages_read, false)?;
+            let capacity = counts.get(&message.global_num).copied().unwrap_or(0).min(options.limit.unwrap_or(usize::MAX));
+            builders
+                .entry(message.global_num)
+                .or_insert_with(|| FrameBuilder::new(field_mapping.as_ref(), &options, capacity))
+                .push(&message);
+        }
+        options.report_progress(messages_read, true)?;
+
+        builders
+            .into_iter()
+            .map(|(global_num, builder)| Ok((message_type_name(global_num), PyDataFrame(builder.finish()?))))
+            .collect()
+    })
+}
+
+/// Stack DataFrames whose columns differ, e.g. read from files of different devices.
+/// "diagonal" takes the union of the columns in first-seen order, with nulls where a
+/// frame lacks one, "vertical" requires the same columns. Either way, each column is
+/// cast to a dtype holding its values in all frames, e.g. Float64 for integers in
+/// some frames and floats in others.
+#[pyfunction]
+#[pyo3(signature = (frames, how = "diagonal"))]
+pub fn concat_frames(py: Python<'_>, frames: Vec<PyDataFrame>, how: &str) -> PyResult<PyDataFrame> {
+    let vertical = match how {
+        "diagonal" => false,
+        "vertical" => true,
+        _ => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "how must be 'diagonal' or 'vertical', got '{}'",
+                how
+            )))
+        }
+    };
+    let frames: Vec<DataFrame> = frames.into_iter().map(|PyDataFrame(df)| df).collect();
+    py.allow_threads(|| stack_frames(&frames, vertical).map(PyDataFrame))
+}
+
+/// Write a DataFrame of record messages to a new FIT file, after a file_id message.
+/// Columns are named `field_<n>` by record field number; other columns are skipped.
+#[pyfunction]
+pub fn write_records_to_fit(py: Python<'_>, df: PyDataFrame, file_path: &str) -> PyResult<()> {
+    let PyDataFrame(df) = df;
+    py.allow_threads(|| {
+        let data = encode_records(&df)?;
+        std::fs::write(file_path, data).map_err(|e| write_error(file_path, e))
+    })
+}
+
+/// Read messages of a specific type from a .fit file and write them straight to a
+/// Parquet file, compressed with "snappy" or "zstd"
+#[pyfunction]
+#[pyo3(signature = (file_path, message_type, out_path, field_mapping = None, compression = "snappy", **options))]
+pub fn write_data_to_parquet(py: Python<'_>, file_path: &str, message_type: &str, out_path: &str, field_mapping: Option<HashMap<String, String>>, compression: &str, options: Option<&Bound<'_, PyDict>>) -> PyResult<()> {
+    let compression = parse_parquet_compression(compression)?;
+    let options = ReadOptions::from_kwargs(options)?;
+    py.allow_threads(|| {
+        let data = read_file(file_path)?;
+        let PyDataFrame(mut df) = read_generic_messages(&data, message_type, field_mapping.as_ref(), &options)?;
+        let file = std::fs::File::create(out_path).map_err(|e| write_error(out_path, e))?;
+        ParquetWriter::new(file).with_compression(compression).finish(&mut df).map_err(polars_error)?;
+        Ok(())
+    })
+}
+
+fn parse_parquet_compression(name: &str) -> PyResult<ParquetCompression> {
+    match name {
+        "snappy" => Ok(ParquetCompression::Snappy),
+        "zstd" => Ok(ParquetCompression::Zstd(None)),
+        _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Unsupported Parquet compression '{}', expected 'snappy' or 'zstd'",
+            name
+        ))),
+    }
+}
+
+/// Iterator over the messages of a specific type in a .fit file, yielding a Polars
+/// DataFrame of at most `batch_size` rows at a time. `limit` caps the rows of all
+/// batches together, and `progress` is called once per batch.
+#[pyclass]
+pub struct MessageBatchReader {
+    fit: FitDecoder<'static>,
+    global_num: Option<u16>,
+    field_mapping: Option<HashMap<String, String>>,
+    options: ReadOptions,
+    batch_size: usize,
+    messages_seen: u32,
+    /// Messages of the type not yet read, to size each batch's columns
+    messages_left: usize,
+    /// Rows yielded so far, which `limit` caps across all batches
+    rows_read: usize,
+    /// Messages of any type decoded so far, for the progress callback
+    messages_decoded: usize,
+    /// Accumulated component totals carried from one batch to the next
+    accumulators: HashMap<u8, Accumulator>,
+}
+
+#[pymethods]
+impl MessageBatchReader {
+    #[new]
+    #[pyo3(signature = (file_path, message_type, field_mapping = None, batch_size = 65536, **options))]
+    fn new(file_path: &str, message_type: &str, field_mapping: Option<HashMap<String, String>>, batch_size: usize, options: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        if batch_size == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("batch_size must be positive"));
+        }
+        let options = ReadOptions::from_kwargs(options)?;
+        let global_num = parse_message_type(message_type);
+        let data = read_file(file_path)?;
+        let messages_left = count_messages(&data, global_num);
+        let fit = open_decoder(data, &options)?;
+        Ok(MessageBatchReader {
+            fit,
+            global_num,
+            field_mapping,
+            options,
+            batch_size,
+            messages_seen: 0,
+            messages_left,
+            rows_read: 0,
+            messages_decoded: 0,
+            accumulators: HashMap::new(),
+        })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyDataFrame>> {
+        py.allow_threads(|| {
+            let rows_left = self.options.limit.map_or(usize::MAX, |limit| limit.saturating_sub(self.rows_read));
+            if rows_left == 0 {
+                return Ok(None);
+            }
+            let batch_size = self.batch_size.min(rows_left);
+            let capacity = batch_size.min(self.messages_left);
+            let mut builder = FrameBuilder::new(self.field_mapping.as_ref(), &self.options, capacity);
+            // Keep numbering messages and accumulating totals where the previous batch stopped
+            builder.message_count = self.messages_seen;
+            builder.accumulators = std::mem::take(&mut self.accumulators);
+            let mut messages_decoded = 0;
+            for message in read_messages(self.fit.by_ref(), &self.options) {
+                let message = message?;
+                messages_decoded += 1;
+                if Some(message.global_num) == self.global_num {
+                    builder.push(&message);
+                    if builder.row_count == batch_size {
+                        break;
+                    }
+                }
+            }
+            // Progress is reported once per batch rather than every `PROGRESS_INTERVAL` messages
+            self.messages_decoded += messages_decoded;
+            if let Some(callback) = self.options.progress.as_ref().filter(|_| messages_decoded > 0) {
+                Python::with_gil(|py| callback.call1(py, (self.messages_decoded,)))?;
+            }
+
+            let messages_read = (builder.message_count - self.messages_seen) as usize;
+            self.messages_left = self.messages_left.saturating_sub(messages_read);
+            self.messages_seen = builder.message_count;
+            self.accumulators = std::mem::take(&mut builder.accumulators);
+            self.rows_read += builder.row_count;
+            if builder.row_count == 0 {
+                return Ok(None);
+            }
+            Ok(Some(PyDataFrame(builder.finish()?)))
+        })
+    }
+}
+
+/// Iterator over the record messages of a .fit file, yielding a Polars DataFrame
+/// of at most `chunk_size` records at a time
+#[pyclass]
+pub struct RecordBatchReader {
+    messages: MessageBatchReader,
+}
+
+#[pymethods]
+impl RecordBatchReader {
+    #[new]
+    #[pyo3(signature = (file_path, field_mapping = None, chunk_size = 65536, **options))]
+    fn new(file_path: &str, field_mapping: Option<HashMap<String, String>>, chunk_size: usize, options: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        if chunk_size == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("chunk_size must be positive"));
+        }
+        let messages = MessageBatchReader::new(file_path, "record", field_mapping, chunk_size, options)?;
+        Ok(RecordBatchReader { messages })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyDataFrame>> {
+        self.messages.__next__(py)
+    }
+}
+
+/// Internal function to read generic messages from the bytes of a FIT file
+/// Called without the GIL held, so it must not touch Python objects
+fn read_generic_messages(data: &[u8], message_type: &str, field_mapping: Option<&HashMap<String, String>>, options: &ReadOptions) -> PyResult<PyDataFrame> {
+    // Parse the FIT file
+    let global_num = parse_message_type(message_type);
+    let fit = open_decoder(data, options)?;
+    let capacity = count_messages(data, global_num).min(options.limit.unwrap_or(usize::MAX));
+    let mut builder = FrameBuilder::new(field_mapping, options, capacity);
 
     // Process each message in the FIT file
-    for message in fit {
+    let mut messages_read = 0;
+    for message in read_messages(fit, options) {
+        let message = message?;
+        messages_read += 1;
+        options.report_progress(messages_read, false)?;
+
         // Only process messages of the specified type
-        if format!("{:?}", message.kind).to_lowercase() == message_type.to_lowercase() {
+        if Some(message.global_num) == global_num {
+            builder.push(&message);
+            if builder.is_full() {
+                break;
+            }
+        }
+    }
+    options.report_progress(messages_read, true)?;
 
-            // Iterate through all data fields in this message
-            for field in &message.values {
-                let raw_field_name = format!("field_{}", field.field_num);
+    Ok(PyDataFrame(builder.finish()?))
+}
 
-                // Apply field mapping if provided
-                let field_name = if let Some(ref mapping) = field_mapping {
-                    mapping.get(&raw_field_name).cloned().unwrap_or(raw_field_name)
-                } else {
-                    raw_field_name
-                };
+/// Name of a message type as returned by `get_message_types`. Types missing from
+/// the FIT profile, e.g. vendor-specific ones, are named by number: `message_<n>`.
+fn message_type_name(global_num: u16) -> String {
+    match match_messagetype(global_num) {
+        MessageType::None => match profile_message(global_num) {
+            Some(message) => message.name.replace('_', ""),
+            None => format!("message_{}", global_num),
+        },
+        kind => format!("{:?}", kind).to_lowercase(),
+    }
+}
+
+/// Count the messages of a type with a quick pass that skips their fields
+fn count_messages(data: &[u8], global_num: Option<u16>) -> usize {
+    match (FitDecoder::new(data), global_num) {
+        (Ok(fit), Some(global_num)) => fit.message_numbers().filter(|n| *n == global_num).count(),
+        _ => 0,
+    }
+}
+
+/// Look up the global message number of a message type name, case-insensitively.
+/// Underscores are ignored, so profile names such as `file_id` match too, as do
+/// the aliases of messages the bundled profile lacks, e.g. `spo2`.
+fn parse_message_type(name: &str) -> Option<u16> {
+    let name = name.to_lowercase();
+    if let Some(global_num) = name.strip_prefix("message_").and_then(|n| n.parse().ok()) {
+        return Some(global_num);
+    }
+    let name = name.replace('_', "");
+    message_numbers().get(&name).copied().or_else(|| message_alias(&name))
+}
+
+/// Global message numbers of the message types in the FIT profile by name, built
+/// on first use
+fn message_numbers() -> &'static HashMap<String, u16> {
+    static NUMBERS: OnceLock<HashMap<String, u16>> = OnceLock::new();
+    NUMBERS.get_or_init(|| {
+        (0..=u16::MAX)
+            .filter(|n| match_messagetype(*n) != MessageType::None || profile_message(*n).is_some())
+            .map(|n| (message_type_name(n), n))
+            .collect()
+    })
+}
+
+/// Accumulates the messages of one type into columns, one row per message
+struct FrameBuilder<'a> {
+    field_mapping: Option<&'a HashMap<String, String>>,
+    options: &'a ReadOptions,
+    /// Columns in order of their first value in the file
+    columns: IndexMap<String, Vec<AnyValue<'static>>>,
+    datetime_columns: HashSet<String>,
+    /// Columns holding enum names, built as Categorical if requested
+    enum_columns: HashSet<String>,
+    row_count: usize,
+    /// Expected number of rows, so each column is allocated once
+    capacity: usize,
+    message_count: u32,
+    message_indices: Vec<u32>,
+    /// Running totals of accumulated components by destination field number
+    accumulators: HashMap<u8, Accumulator>,
+}
 
-                // Initialize column if not exists
-                if !columns.contains_key(&field_name) {
-                    columns.insert(field_name.clone(), Vec::new());
-                    column_order.push(field_name.clone());
+impl<'a> FrameBuilder<'a> {
+    fn new(field_mapping: Option<&'a HashMap<String, String>>, options: &'a ReadOptions, capacity: usize) -> Self {
+        FrameBuilder {
+            field_mapping,
+            options,
+            columns: IndexMap::new(),
+            datetime_columns: HashSet::new(),
+            enum_columns: HashSet::new(),
+            row_count: 0,
+            capacity,
+            message_count: 0,
+            message_indices: Vec::with_capacity(if options.add_index { capacity } else { 0 }),
+            accumulators: HashMap::new(),
+        }
+    }
+
+    /// Whether the builder holds the requested number of rows
+    fn is_full(&self) -> bool {
+        self.options.limit.is_some_and(|limit| self.row_count >= limit)
+    }
+
+    /// Append a message as a new row, unless the builder is full
+    fn push(&mut self, message: &Message) {
+        let options = self.options;
+        if self.is_full() {
+            return;
+        }
+        // Messages are numbered in file order, including those outside the time range
+        let message_index = self.message_count;
+        self.message_count += 1;
+        // Totals accumulate over all messages, including those outside the time range
+        let expanded = self.expand_components(message);
+        if !options.in_time_range(message) {
+            return;
+        }
+        if options.add_index {
+            self.message_indices.push(message_index);
+        }
+
+        // Profile lookups for this message type
+        let profile = MessageProfile::of(message);
+
+        // Iterate through all data fields in this message
+        for field in &message.fields {
+            // Invalid sentinel values and status codes are treated as missing
+            if options.mask_invalid && (!field.is_valid() || profile.is_status(field)) {
+                continue;
+            }
+
+            let field_name = self.column_name(format!("field_{}", field.number));
+
+            // A field that appears twice in one message keeps its first value
+            if !options.wants(&field_name) || self.is_filled(&field_name) {
+                continue;
+            }
+
+            let field_num = field.number as usize;
+            // Timestamps convert losslessly, so only other decoded fields keep a raw value
+            let mut decoded = false;
+            let any_value = match profile.field_type(field_num) {
+                FieldType::Timestamp | FieldType::DateTime | FieldType::LocalDateTime => {
+                    match timestamp_value(field, options) {
+                        Some(any_value) => {
+                            if options.decode_timestamps {
+                                self.datetime_columns.insert(field_name.clone());
+                            }
+                            any_value
+                        }
+                        None => raw_value(field, options.mask_invalid),
+                    }
+                }
+                // Every field the profile types as coordinates is a position, e.g.
+                // lap start positions and session bounding boxes as well as record
+                // positions, but only the record positions may be kept raw
+                FieldType::Coordinates if options.convert_gps || !is_record_position(message, field_num) => match field.value {
+                    Value::I32(v) => {
+                        decoded = true;
+                        AnyValue::Float64(v as f64 * DEGREES_PER_SEMICIRCLE)
+                    }
+                    _ => raw_value(field, options.mask_invalid),
+                },
+                field_type => {
+                    // Scaled fields are decoded to physical units as floats. Byte fields
+                    // pack components, which the profile scale applies to instead.
+                    let scale = profile.scale(field_num).map(f64::from);
+                    let offset = profile.offset(field_num).map(f64::from);
+                    let scaled = field_type != FieldType::Byte && (scale.is_some() || offset.is_some());
+                    if options.apply_scale && scaled {
+                        let (scale, offset) = (scale.unwrap_or(1.0), offset.unwrap_or(0.0));
+                        decoded = true;
+                        scaled_value(field, scale, offset, options.mask_invalid)
+                    } else if options.decode_enums && (is_enum_type(field_type) || profile.enum_names(field_num).is_some()) {
+                        self.enum_columns.insert(field_name.clone());
+                        decoded = true;
+                        let names = profile.enum_names(field_num);
+                        let name = |key: usize| match names {
+                            Some(names) => names.get(key).copied(),
+                            None => match_predefined_field_value(field_type, key),
+                        };
+                        enum_value(field, name, options.mask_invalid)
+                    } else {
+                        raw_value(field, options.mask_invalid)
+                    }
                 }
+            };
 
-                // Convert field value to AnyValue
-                let any_value = match &field.value {
-                    Value::U8(v) => AnyValue::UInt32(*v as u32),
-                    Value::U16(v) => AnyValue::UInt32(*v as u32),
-                    Value::U32(v) => AnyValue::UInt32(*v),
-                    Value::U64(v) => AnyValue::UInt64(*v),
-                    Value::I8(v) => AnyValue::Int32(*v as i32),
-                    Value::I16(v) => AnyValue::Int32(*v as i32),
-                    Value::I32(v) => AnyValue::Int32(*v),
-                    Value::I64(v) => AnyValue::Int64(*v),
-                    Value::F32(v) => AnyValue::Float32(*v),
-                    Value::F64(v) => AnyValue::Float64(*v),
-                    Value::String(v) => AnyValue::StringOwned(v.clone().into()),
-                    Value::Enum(v) => AnyValue::StringOwned(v.to_string().into()),
-                    Value::Time(v) => AnyValue::UInt32(*v), // Time is represented as u32
-                    Value::ArrU8(v) => {
-                        // Convert array to string representation for now
-                        let array_str = v.iter()
-                            .map(|x| x.to_string())
-                            .collect::<Vec<_>>()
-                            .join(",");
-                        AnyValue::StringOwned(format!("[{}]", array_str).into())
-                    },
-                    Value::ArrU16(v) => {
-                        // Convert array to string representation for now
-                        let array_str = v.iter()
-                            .map(|x| x.to_string())
-                            .collect::<Vec<_>>()
-                            .join(",");
-                        AnyValue::StringOwned(format!("[{}]", array_str).into())
-                    },
-                    Value::ArrU32(v) => {
-                        // Convert array to string representation for now
-                        let array_str = v.iter()
-                            .map(|x| x.to_string())
-                            .collect::<Vec<_>>()
-                            .join(",");
-                        AnyValue::StringOwned(format!("[{}]", array_str).into())
-                    },
-                };
+            self.insert(field_name.clone(), any_value);
+            if decoded {
+                self.insert_raw(&field_name, field);
+            }
+        }
 
-                columns.get_mut(&field_name).unwrap().push(any_value);
+        // Subfields reinterpret a field depending on the value of a reference field
+        for field in &message.fields {
+            let Some(subfield) = active_subfield(message, field.number) else {
+                continue;
+            };
+            let field_name = self.column_name(subfield.name.to_string());
+            if !field.is_valid() || !options.wants(&field_name) || self.is_filled(&field_name) {
+                continue;
+            }
+            if options.decode_enums && !subfield.names.is_empty() {
+                self.enum_columns.insert(field_name.clone());
+            }
+            self.insert(field_name.clone(), subfield_value(field, subfield, options));
+            if decodes_subfield(subfield, options) {
+                self.insert_raw(&field_name, field);
+            }
+        }
+
+        // Component fields decode into the fields they pack, unless the message has those itself
+        for (component, values) in expanded {
+            let field_name = self.column_name(format!("field_{}", component.destination));
+            if !options.wants(&field_name) || self.is_filled(&field_name) {
+                continue;
+            }
+            let field_type = profile.field_type(component.destination as usize);
+            if options.decode_enums && is_enum_type(field_type) {
+                self.enum_columns.insert(field_name.clone());
+            }
+            self.insert(field_name, component_value(&component, &values, field_type, options));
+        }
+
+        // Developer fields are namespaced by developer so they never collide with profile
+        // fields, unless they record a profile field the message lacks
+        for developer_field in &message.developer_fields {
+            let field = &developer_field.field;
+            if options.mask_invalid && !field.is_valid() {
+                continue;
+            }
+
+            let description = developer_field.description.as_deref();
+            let native_name = description
+                .and_then(|d| d.native_field.filter(|_| d.native_message.is_none_or(|n| n == message.global_num)))
+                .map(|number| self.column_name(format!("field_{}", number)))
+                .filter(|native_name| !self.is_filled(native_name));
+            let field_name = native_name.unwrap_or_else(|| {
+                let name = description
+                    .and_then(|d| d.name.clone())
+                    .unwrap_or_else(|| format!("field_{}", field.number));
+                self.column_name(format!("dev_{}_{}", developer_field.developer_data_index, name))
+            });
+            if !options.wants(&field_name) || self.is_filled(&field_name) {
+                continue;
+            }
+
+            let scaled = description.filter(|d| options.apply_scale && (d.scale.is_some() || d.offset.is_some()));
+            let any_value = match scaled {
+                Some(d) => scaled_value(
+                    field,
+                    d.scale.map_or(1.0, f64::from),
+                    d.offset.map_or(0.0, f64::from),
+                    options.mask_invalid,
+                ),
+                None => raw_value(field, options.mask_invalid),
+            };
+
+            self.insert(field_name.clone(), any_value);
+            if scaled.is_some() {
+                self.insert_raw(&field_name, field);
+            }
+        }
+
+        // Columns missing from this message get a null in this row
+        self.row_count += 1;
+        for column_data in self.columns.values_mut() {
+            if column_data.len() < self.row_count {
+                column_data.push(AnyValue::Null);
             }
         }
     }
 
-    // Ensure all columns have the same length (fill with nulls if necessary)
-    let max_len = columns.values().map(|v| v.len()).max().unwrap_or(0);
-    for (_, column_data) in columns.iter_mut() {
-        while column_data.len() < max_len {
-            column_data.push(AnyValue::Null);
+    /// Unpack the component fields of a message and update the accumulated totals.
+    /// Values are grouped by destination field, in order of appearance.
+    fn expand_components(&mut self, message: &Message) -> Vec<(Component, Vec<u64>)> {
+        let mut expanded: Vec<(Component, Vec<u64>)> = Vec::new();
+        for field in &message.fields {
+            if let Value::U32(total) = field.value {
+                if field.is_valid() && is_accumulated_total(message.global_num, field.number) {
+                    self.accumulators.insert(field.number, Accumulator::new(total.into()));
+                }
+            }
+            let components = match active_subfield(message, field.number) {
+                Some(subfield) if !subfield.components.is_empty() => subfield.components,
+                _ => components(message.global_num, field.number),
+            };
+            if components.is_empty() || !field.is_valid() {
+                continue;
+            }
+            for (component, raw) in components.iter().zip(unpack(&field.value, components)) {
+                let value = if component.accumulate {
+                    self.accumulators
+                        .entry(component.destination)
+                        .or_insert_with(|| Accumulator::new(raw))
+                        .add(raw, component.bits)
+                } else {
+                    raw
+                };
+                match expanded.iter_mut().find(|(c, _)| c.destination == component.destination) {
+                    Some((_, values)) => values.push(value),
+                    None => expanded.push((*component, vec![value])),
+                }
+            }
         }
+        expanded
     }
 
-    // Create DataFrame
-    let mut df_columns = Vec::new();
-    for col_name in &column_order {
-        if let Some(data) = columns.get(col_name) {
-            let series = Series::from_any_values(col_name.as_str().into(), data, true)
+    /// Apply the field mapping to a raw column name
+    fn column_name(&self, raw_field_name: String) -> String {
+        match self.field_mapping {
+            Some(mapping) => mapping.get(&raw_field_name).cloned().unwrap_or(raw_field_name),
+            None => raw_field_name,
+        }
+    }
+
+    /// Whether a column already holds a value for the current row
+    fn is_filled(&self, field_name: &str) -> bool {
+        self.columns
+            .get(field_name)
+            .is_some_and(|column_data| column_data.len() > self.row_count)
+    }
+
+    /// Add a value to the current row, backfilling nulls for earlier rows of a new column
+    fn insert(&mut self, field_name: String, any_value: AnyValue<'static>) {
+        let row_count = self.row_count;
+        let capacity = self.capacity.max(row_count + 1);
+        self.columns
+            .entry(field_name)
+            .or_insert_with(|| {
+                let mut column_data = Vec::with_capacity(capacity);
+                column_data.resize(row_count, AnyValue::Null);
+                column_data
+            })
+            .push(any_value);
+    }
+
+    /// Add the undecoded value of a decoded field to its `<name>_raw` column, if requested
+    fn insert_raw(&mut self, field_name: &str, field: &Field) {
+        if self.options.keep_raw {
+            self.insert(format!("{}_raw", field_name), raw_value(field, self.options.mask_invalid));
+        }
+    }
+
+    /// Build the DataFrame from the accumulated columns
+    fn finish(mut self) -> PyResult<DataFrame> {
+        let mut df_columns = Vec::new();
+        if self.options.add_index {
+            df_columns.push(Series::new("message_index".into(), &self.message_indices).into());
+        }
+        for (col_name, data) in self.columns.iter_mut() {
+            wrap_scalars_in_lists(data)
                 .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to create series: {}", e)))?;
+            let series = match column_dtype(data) {
+                Some(dtype) => Series::from_any_values_and_dtype(col_name.as_str().into(), data, &dtype, false),
+                None => Series::from_any_values(col_name.as_str().into(), data, true),
+            };
+            let mut series = series
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to create series: {}", e)))?;
+            if self.options.categorical_enums && self.enum_columns.contains(col_name) && series.dtype() == &DataType::String {
+                series = series
+                    .cast(&DataType::Categorical(None, CategoricalOrdering::Physical))
+                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to build categories: {}", e)))?;
+            }
+            if self.datetime_columns.contains(col_name) {
+                series = series
+                    .cast(&DataType::Datetime(TimeUnit::Milliseconds, Some("UTC".into())))
+                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to decode timestamps: {}", e)))?;
+            }
+            let schema_dtype = self.options.schema.as_ref().and_then(|schema| schema.get(col_name));
+            if self.options.all_float && schema_dtype.is_none() && series.dtype().is_numeric() {
+                series = series
+                    .cast(&DataType::Float64)
+                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to create series: {}", e)))?;
+            }
+            if let Some(dtype) = schema_dtype {
+                series = series.strict_cast(dtype).map_err(|e| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "Failed to cast column '{}' to {}: {}",
+                        col_name,
+                        dtype_name(dtype),
+                        e
+                    ))
+                })?;
+            }
             df_columns.push(series.into());
         }
+
+        let df = DataFrame::new(df_columns)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to create DataFrame: {}", e)))?;
+        self.order_by_time(df).map_err(polars_error)
+    }
+
+    /// Sort rows by timestamp and drop repeated timestamps, as requested. Rows
+    /// without a timestamp sort last and are never dropped.
+    fn order_by_time(&self, mut df: DataFrame) -> PolarsResult<DataFrame> {
+        let timestamp = self.column_name(format!("field_{}", TIMESTAMP_FIELD));
+        if !(self.options.sort_by_time || self.options.dedup_time) || df.column(&timestamp).is_err() {
+            return Ok(df);
+        }
+        if self.options.sort_by_time {
+            df = df.sort([timestamp.as_str()], SortMultipleOptions::default().with_maintain_order(true).with_nulls_last(true))?;
+        }
+        if self.options.dedup_time {
+            let times = df.column(&timestamp)?.cast(&DataType::Int64)?;
+            let times: Vec<Option<i64>> = times.i64()?.into_iter().collect();
+            let mut seen = HashSet::new();
+            let mut keep = vec![true; times.len()];
+            let rows: Box<dyn Iterator<Item = usize>> =
+                if self.options.dedup_keep_last { Box::new((0..times.len()).rev()) } else { Box::new(0..times.len()) };
+            for row in rows {
+                if let Some(time) = times[row] {
+                    keep[row] = seen.insert(time);
+                }
+            }
+            let mask: BooleanChunked = keep.into_iter().map(Some).collect();
+            df = df.filter(&mask)?;
+        }
+        Ok(df)
+    }
+}
+
+/// Stack the frames on the union of their columns in first-seen order, each cast
+/// to the promoted dtype of its columns
+fn stack_frames(frames: &[DataFrame], vertical: bool) -> PyResult<DataFrame> {
+    let mut schema: Vec<(PlSmallStr, DataType)> = Vec::new();
+    for df in frames {
+        for column in df.get_columns() {
+            match schema.iter_mut().find(|(name, _)| name == column.name()) {
+                Some((_, dtype)) => *dtype = promote_dtype(dtype.clone(), column.dtype().clone()),
+                None => schema.push((column.name().clone(), column.dtype().clone())),
+            }
+        }
+    }
+    if vertical && frames.iter().any(|df| df.width() != schema.len()) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Vertical concat needs the same columns in every frame, use how='diagonal' to take their union",
+        ));
+    }
+
+    // Categories are stacked as strings and encoded once, instead of remapping the
+    // local categories of every frame
+    let stack_dtype = |dtype: &DataType| if dtype.is_categorical() { DataType::String } else { dtype.clone() };
+    let mut stacked = DataFrame::empty();
+    for df in frames {
+        let columns = schema
+            .iter()
+            .map(|(name, dtype)| match df.column(name) {
+                Ok(column) => column.cast(&stack_dtype(dtype)),
+                Err(_) => Ok(Column::full_null(name.clone(), df.height(), &stack_dtype(dtype))),
+            })
+            .collect::<PolarsResult<Vec<_>>>()
+            .map_err(polars_error)?;
+        let df = DataFrame::new(columns).map_err(polars_error)?;
+        if stacked.width() == 0 {
+            stacked = df;
+        } else {
+            stacked.vstack_mut(&df).map_err(polars_error)?;
+        }
+    }
+    stacked.as_single_chunk_par();
+    for (name, dtype) in &schema {
+        let DataType::Categorical(_, ordering) = dtype else { continue };
+        let categorical = DataType::Categorical(None, *ordering);
+        let column = stacked.column(name).and_then(|column| column.cast(&categorical)).map_err(polars_error)?;
+        stacked.with_column(column).map_err(polars_error)?;
+    }
+    Ok(stacked)
+}
+
+/// Read a FIT file into memory, raising FileNotFoundError with the path if it is missing
+fn read_file(file_path: &str) -> PyResult<Vec<u8>> {
+    let path = existing_file(file_path)?;
+    let data = std::fs::read(path).map_err(|e| read_error(file_path, e))?;
+
+    // Compressed files are detected by extension or by the gzip magic bytes
+    if file_path.ends_with(".gz") || data.starts_with(&GZIP_MAGIC) {
+        decompress_gzip(&data)
+    } else {
+        Ok(data)
+    }
+}
+
+/// Read the first `len` bytes of a FIT file, decompressing only as much as needed
+fn read_file_start(file_path: &str, len: u64) -> PyResult<Vec<u8>> {
+    let path = existing_file(file_path)?;
+    let mut start = Vec::new();
+    std::fs::File::open(path)
+        .and_then(|file| file.take(len).read_to_end(&mut start))
+        .map_err(|e| read_error(file_path, e))?;
+
+    if file_path.ends_with(".gz") || start.starts_with(&GZIP_MAGIC) {
+        start.clear();
+        let file = std::fs::File::open(path).map_err(|e| read_error(file_path, e))?;
+        MultiGzDecoder::new(file)
+            .take(len)
+            .read_to_end(&mut start)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to decompress gzip data: {}", e)))?;
+    }
+    Ok(start)
+}
+
+/// Raise FileNotFoundError with the path unless it is an existing file
+fn existing_file(file_path: &str) -> PyResult<&Path> {
+    let path = Path::new(file_path);
+    if !path.is_file() {
+        let reason = if path.exists() { "Not a file" } else { "No such file" };
+        return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(format!("{}: '{}'", reason, file_path)));
+    }
+    Ok(path)
+}
+
+/// Map an error reading a file to a Python IOError
+fn read_error(file_path: &str, e: std::io::Error) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to read '{}': {}", file_path, e))
+}
+
+/// Map a file write error to a Python exception naming the file
+fn write_error(file_path: &str, e: std::io::Error) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyIOError, _>(format!("Failed to write '{}': {}", file_path, e))
+}
+
+/// Decompress gzip-compressed bytes, passing anything else through unchanged
+fn maybe_decompress(data: &[u8]) -> PyResult<Cow<'_, [u8]>> {
+    if data.starts_with(&GZIP_MAGIC) {
+        decompress_gzip(data).map(Cow::Owned)
+    } else {
+        Ok(Cow::Borrowed(data))
+    }
+}
+
+/// Decompress a gzip stream into memory
+fn decompress_gzip(data: &[u8]) -> PyResult<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    MultiGzDecoder::new(data)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to decompress gzip data: {}", e)))?;
+    Ok(decompressed)
+}
+
+/// Create a decoder over FIT bytes, checking the CRCs first if requested
+fn open_decoder<'a>(data: impl Into<Cow<'a, [u8]>>, options: &ReadOptions) -> PyResult<FitDecoder<'a>> {
+    let fit = FitDecoder::new(data).map_err(decode_error)?.with_encoding(options.encoding);
+    if options.verify_crc {
+        fit.verify_crc().map_err(decode_error)?;
+    }
+    Ok(fit)
+}
+
+/// Map a decoder error to a Python exception
+fn decode_error(e: DecodeError) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to parse FIT data: {}", e))
+}
+
+/// The messages of a decoder as read results. With `truncated_ok`, a malformed
+/// message ends the messages with a warning rather than an error, so the messages
+/// before it are kept.
+fn read_messages<'a>(
+    fit: impl Iterator<Item = Result<Message, DecodeError>> + 'a,
+    options: &'a ReadOptions,
+) -> impl Iterator<Item = PyResult<Message>> + 'a {
+    let mut messages_read = 0;
+    fit.map_while(move |message| match message {
+        Ok(message) => {
+            messages_read += 1;
+            Some(Ok(message))
+        }
+        Err(e) if options.truncated_ok => {
+            let warning = format!("FIT data is truncated or corrupted, keeping the {} messages before it: {}", messages_read, e);
+            Python::with_gil(|py| {
+                PyErr::warn_bound(py, py.get_type_bound::<pyo3::exceptions::PyUserWarning>().as_any(), &warning, 1)
+            })
+            .err()
+            .map(Err)
+        }
+        Err(e) => Some(Err(decode_error(e))),
+    })
+}
+
+/// Convert a field to its raw AnyValue, dropping invalid elements of arrays if masking
+fn raw_value(field: &Field, mask_invalid: bool) -> AnyValue<'static> {
+    match &field.value {
+        Value::Array(values) => {
+            let items: Vec<AnyValue> = values
+                .iter()
+                .filter(|v| !mask_invalid || !field.base_type.is_invalid(v))
+                .map(scalar_value)
+                .collect();
+            list_value(&items)
+        }
+        value => scalar_value(value),
+    }
+}
+
+/// Convert a scalar value, widening small integers to 32 bits
+fn scalar_value(value: &Value) -> AnyValue<'static> {
+    match value {
+        Value::U8(v) => AnyValue::UInt32(*v as u32),
+        Value::U16(v) => AnyValue::UInt32(*v as u32),
+        Value::U32(v) => AnyValue::UInt32(*v),
+        Value::U64(v) => AnyValue::UInt64(*v),
+        Value::I8(v) => AnyValue::Int32(*v as i32),
+        Value::I16(v) => AnyValue::Int32(*v as i32),
+        Value::I32(v) => AnyValue::Int32(*v),
+        Value::I64(v) => AnyValue::Int64(*v),
+        Value::F32(v) => AnyValue::Float32(*v),
+        Value::F64(v) => AnyValue::Float64(*v),
+        Value::String(v) => AnyValue::StringOwned(v.as_str().into()),
+        Value::Array(values) => list_value(&values.iter().map(scalar_value).collect::<Vec<_>>()),
+    }
+}
+
+/// Whether a field is one of the record positions `convert_gps` applies to
+fn is_record_position(message: &Message, number: usize) -> bool {
+    message.global_num == RECORD_MESSAGE && RECORD_POSITION_FIELDS.contains(&number)
+}
+
+/// Convert a FIT timestamp to Unix milliseconds, or keep the raw seconds since the
+/// FIT epoch when not decoding
+fn timestamp_value(field: &Field, options: &ReadOptions) -> Option<AnyValue<'static>> {
+    let Value::U32(v) = field.value else {
+        return None;
+    };
+    if options.decode_timestamps {
+        Some(AnyValue::Int64((v as i64 + FIT_EPOCH_OFFSET as i64) * 1000))
+    } else {
+        Some(AnyValue::UInt32(v))
+    }
+}
+
+/// Whether values of a profile field type are names from a predefined list
+fn is_enum_type(field_type: FieldType) -> bool {
+    !matches!(
+        field_type,
+        FieldType::None
+            | FieldType::Uint8
+            | FieldType::Uint8Z
+            | FieldType::Uint16
+            | FieldType::Uint16Z
+            | FieldType::Uint32
+            | FieldType::Uint32Z
+            | FieldType::Sint8
+            | FieldType::Sint16
+            | FieldType::Sint32
+            | FieldType::Float32
+            | FieldType::Byte
+            | FieldType::String
+            | FieldType::LocaltimeIntoDay
+            // Bit fields and special-valued integers rather than enums
+            | FieldType::MessageIndex
+            | FieldType::DeviceIndex
+            | FieldType::Weight
+            | FieldType::UserLocalId
+            | FieldType::LeftRightBalance
+            | FieldType::LeftRightBalance100
+            | FieldType::FileFlags
+            | FieldType::SportBits0
+            | FieldType::ConnectivityCapabilities
+            | FieldType::WorkoutCapabilities
+            | FieldType::CourseCapabilities
+    )
+}
+
+/// Look up the profile name of an enum value, falling back to the numeric string
+fn enum_value(field: &Field, names: impl Fn(usize) -> Option<&'static str>, mask_invalid: bool) -> AnyValue<'static> {
+    let key = match field.value {
+        Value::U8(v) => v as usize,
+        Value::U16(v) => v as usize,
+        Value::U32(v) => v as usize,
+        _ => return raw_value(field, mask_invalid),
+    };
+    let name = names(key)
+        .map(str::to_string)
+        .unwrap_or_else(|| key.to_string());
+    AnyValue::StringOwned(name.into())
+}
+
+/// Apply a profile scale and offset (`raw / scale - offset`) to a numeric field
+fn scaled_value(field: &Field, scale: f64, offset: f64, mask_invalid: bool) -> AnyValue<'static> {
+    let decode = |value: &Value| {
+        let raw = match value {
+            Value::U8(v) => *v as f64,
+            Value::U16(v) => *v as f64,
+            Value::U32(v) => *v as f64,
+            Value::U64(v) => *v as f64,
+            Value::I8(v) => *v as f64,
+            Value::I16(v) => *v as f64,
+            Value::I32(v) => *v as f64,
+            Value::I64(v) => *v as f64,
+            Value::F32(v) => *v as f64,
+            Value::F64(v) => *v,
+            _ => return None,
+        };
+        Some(AnyValue::Float64(raw / scale - offset))
+    };
+    match &field.value {
+        Value::Array(values) => {
+            let items: Option<Vec<AnyValue>> = values
+                .iter()
+                .filter(|v| !mask_invalid || !field.base_type.is_invalid(v))
+                .map(decode)
+                .collect();
+            items.map(|items| list_value(&items)).unwrap_or_else(|| raw_value(field, mask_invalid))
+        }
+        value => decode(value).unwrap_or_else(|| raw_value(field, mask_invalid)),
+    }
+}
+
+/// Decode a field as one of its subfields
+fn subfield_value(field: &Field, subfield: &SubField, options: &ReadOptions) -> AnyValue<'static> {
+    if options.apply_scale && subfield_is_scaled(subfield) {
+        scaled_value(field, subfield.scale, subfield.offset, options.mask_invalid)
+    } else if options.decode_enums && !subfield.names.is_empty() {
+        enum_value(field, |key| subfield.names.get(key).copied(), options.mask_invalid)
+    } else {
+        raw_value(field, options.mask_invalid)
     }
+}
+
+/// Whether a subfield has a scale or offset to apply
+fn subfield_is_scaled(subfield: &SubField) -> bool {
+    subfield.scale != 1.0 || subfield.offset != 0.0
+}
+
+/// Whether `subfield_value` decodes a subfield rather than keeping its raw value
+fn decodes_subfield(subfield: &SubField, options: &ReadOptions) -> bool {
+    (options.apply_scale && subfield_is_scaled(subfield)) || (options.decode_enums && !subfield.names.is_empty())
+}
+
+/// Decode the unpacked values of a component as its destination field type, as a
+/// list if a field packs several
+fn component_value(component: &Component, values: &[u64], field_type: FieldType, options: &ReadOptions) -> AnyValue<'static> {
+    let decode = |raw: u64| {
+        if options.apply_scale && (component.scale != 1.0 || component.offset != 0.0) {
+            AnyValue::Float64(raw as f64 / component.scale - component.offset)
+        } else if options.decode_enums && is_enum_type(field_type) {
+            let name = usize::try_from(raw).ok().and_then(|key| match_predefined_field_value(field_type, key));
+            AnyValue::StringOwned(name.map_or_else(|| raw.to_string(), str::to_string).into())
+        } else {
+            u32::try_from(raw).map_or(AnyValue::UInt64(raw), AnyValue::UInt32)
+        }
+    };
+    match values {
+        [value] => decode(*value),
+        values => list_value(&values.iter().map(|value| decode(*value)).collect::<Vec<_>>()),
+    }
+}
+
+/// Build a list value from the elements of an array field
+fn list_value(items: &[AnyValue<'static>]) -> AnyValue<'static> {
+    match Series::from_any_values(PlSmallStr::EMPTY, items, true) {
+        Ok(series) => AnyValue::List(series),
+        Err(_) => AnyValue::Null,
+    }
+}
+
+/// Wrap scalar values in single-element lists if the column also holds arrays,
+/// so a field that is only sometimes multi-valued still yields one List dtype
+fn wrap_scalars_in_lists(data: &mut [AnyValue<'static>]) -> PolarsResult<()> {
+    if !data.iter().any(|v| matches!(v, AnyValue::List(_))) {
+        return Ok(());
+    }
+    for value in data.iter_mut() {
+        if !matches!(value, AnyValue::List(_) | AnyValue::Null) {
+            let scalar = Series::from_any_values(PlSmallStr::EMPTY, std::slice::from_ref(value), true)?;
+            *value = AnyValue::List(scalar);
+        }
+    }
+    Ok(())
+}
+
+/// The dtype all values of a scalar column fit, or None for list and all-null
+/// columns. A field can be defined with different base types in different
+/// definition messages, so its values need not share one dtype.
+fn column_dtype(data: &[AnyValue<'static>]) -> Option<DataType> {
+    let mut dtypes = data
+        .iter()
+        .filter(|value| !value.is_null())
+        .map(|value| value.dtype());
+    let first = dtypes.next()?;
+    if first.is_list() {
+        return None;
+    }
+    Some(dtypes.fold(first, promote_dtype))
+}
+
+/// The narrowest dtype holding values of both dtypes: integers widen to the
+/// larger width and to Int64 for mixed signedness, any float makes Float64,
+/// and anything mixed with strings becomes a String. Null takes the other dtype.
+fn promote_dtype(a: DataType, b: DataType) -> DataType {
+    if a == b || b == DataType::Null {
+        return a;
+    }
+    if a == DataType::Null {
+        return b;
+    }
+    if !(a.is_numeric() && b.is_numeric()) {
+        return DataType::String;
+    }
+    if a.is_float() || b.is_float() {
+        return DataType::Float64;
+    }
+    match (a.is_signed_integer(), b.is_signed_integer()) {
+        // Values are 32 or 64 bits wide, so a mismatch of one sign is 64 bits
+        (true, true) => DataType::Int64,
+        (false, false) => DataType::UInt64,
+        _ if a == DataType::UInt64 || b == DataType::UInt64 => DataType::Float64,
+        _ => DataType::Int64,
+    }
+}
 
-    let df = DataFrame::new(df_columns)
-        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Failed to create DataFrame: {}", e)))?;
+/// Encode a DataFrame of record messages as a FIT file
+fn encode_records(df: &DataFrame) -> PyResult<Vec<u8>> {
+    let columns = df
+        .get_columns()
+        .iter()
+        .filter_map(|column| RecordColumn::new(column.as_materialized_series()).transpose())
+        .collect::<PyResult<Vec<_>>>()?;
+    let mut encoder = FitEncoder::new();
+
+    // The file is created at the first recorded timestamp
+    let mut time_created = BaseType::UInt32.invalid();
+    if let Some(timestamps) = columns.iter().find(|c| c.definition.number == TIMESTAMP_FIELD) {
+        for row in 0..df.height() {
+            let value = timestamps.value(row)?;
+            if !BaseType::UInt32.is_invalid(&value) {
+                time_created = value;
+                break;
+            }
+        }
+    }
+    let file_id = [
+        FieldDefinition { number: 0, base_type: BaseType::Enum, size: 1 },
+        FieldDefinition { number: 1, base_type: BaseType::UInt16, size: 2 },
+        FieldDefinition { number: 2, base_type: BaseType::UInt16, size: 2 },
+        FieldDefinition { number: 4, base_type: BaseType::UInt32, size: 4 },
+    ];
+    encoder.define(0, FILE_ID_MESSAGE, &file_id);
+    encoder.data(
+        0,
+        &file_id,
+        &[Value::U8(FILE_TYPE_ACTIVITY), Value::U16(MANUFACTURER_DEVELOPMENT), Value::U16(0), time_created],
+    );
+
+    let fields: Vec<FieldDefinition> = columns.iter().map(|c| c.definition.clone()).collect();
+    encoder.define(1, RECORD_MESSAGE, &fields);
+    for row in 0..df.height() {
+        let values = columns.iter().map(|c| c.value(row)).collect::<PyResult<Vec<_>>>()?;
+        encoder.data(1, &fields, &values);
+    }
+    Ok(encoder.finish())
+}
+
+/// A DataFrame column and how to encode its values as a record field
+struct RecordColumn {
+    series: Series,
+    definition: FieldDefinition,
+    field_type: FieldType,
+    scale: Option<f64>,
+    offset: Option<f64>,
+    /// Values of the enum field by profile name, for columns of enum names
+    enum_values: HashMap<&'static str, usize>,
+}
+
+impl RecordColumn {
+    /// Plan the encoding of a `field_<n>` column; None for other or empty columns
+    fn new(series: &Series) -> PyResult<Option<Self>> {
+        let Some(number) = series.name().strip_prefix("field_").and_then(|n| n.parse::<u8>().ok()) else {
+            return Ok(None);
+        };
+        let field_type = match_message_field(MessageType::Record)(number as usize);
+        // Categorical enum names are encoded like string ones
+        let series = match series.dtype() {
+            DataType::Categorical(_, _) | DataType::Enum(_, _) => series.cast(&DataType::String).map_err(polars_error)?,
+            DataType::List(inner) if matches!(inner.as_ref(), DataType::Categorical(_, _) | DataType::Enum(_, _)) => {
+                series.cast(&DataType::List(Box::new(DataType::String))).map_err(polars_error)?
+            }
+            _ => series.clone(),
+        };
+        let inner = match series.dtype() {
+            DataType::List(inner) => inner.as_ref(),
+            dtype => dtype,
+        };
+        let base_type = record_base_type(field_type, inner).ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Can't write column '{}' of dtype {} to a FIT file",
+                series.name(),
+                series.dtype()
+            ))
+        })?;
+
+        // Arrays take their longest length and strings their longest text plus a terminator
+        let size = match series.dtype() {
+            DataType::List(_) => {
+                let longest = series.list().map_err(polars_error)?.into_iter().flatten().map(|s| s.len()).max();
+                longest.unwrap_or(0) * base_type.size()
+            }
+            DataType::String if base_type == BaseType::String => {
+                series.str().map_err(polars_error)?.into_iter().flatten().map(|s| s.len() + 1).max().unwrap_or(0)
+            }
+            _ => base_type.size(),
+        };
+        if size == 0 {
+            return Ok(None);
+        }
+        let size = u8::try_from(size).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Column '{}' has values of {} bytes, more than a FIT field holds",
+                series.name(),
+                size
+            ))
+        })?;
+
+        // Looked up once, rather than searching the profile for every value
+        let mut enum_values = HashMap::new();
+        if base_type != BaseType::String && *inner == DataType::String {
+            for key in 0..=u16::MAX as usize {
+                if let Some(name) = match_predefined_field_value(field_type, key) {
+                    enum_values.entry(name).or_insert(key);
+                }
+            }
+        }
+
+        Ok(Some(RecordColumn {
+            series,
+            definition: FieldDefinition { number, base_type, size },
+            field_type,
+            scale: match_message_scale(MessageType::Record)(number as usize).map(f64::from),
+            offset: match_message_offset(MessageType::Record)(number as usize).map(f64::from),
+            enum_values,
+        }))
+    }
+
+    /// The raw value of a row, an array for list columns
+    fn value(&self, row: usize) -> PyResult<Value> {
+        match self.series.get(row).map_err(polars_error)? {
+            AnyValue::List(items) => items.iter().map(|item| self.scalar(item)).collect::<PyResult<_>>().map(Value::Array),
+            value => self.scalar(value),
+        }
+    }
+
+    /// Undo the decoding of a value: timestamps, degrees, scale and offset, and enum
+    /// names are converted back to the raw value stored in the file
+    fn scalar(&self, value: AnyValue) -> PyResult<Value> {
+        let base_type = self.definition.base_type;
+        let raw = match value {
+            AnyValue::Null => return Ok(base_type.invalid()),
+            AnyValue::Datetime(v, unit, _) => {
+                let unix_seconds = match unit {
+                    TimeUnit::Milliseconds => v.div_euclid(1000),
+                    TimeUnit::Microseconds => v.div_euclid(1_000_000),
+                    TimeUnit::Nanoseconds => v.div_euclid(1_000_000_000),
+                };
+                int_value(base_type, unix_seconds as i128 - FIT_EPOCH_OFFSET as i128)
+            }
+            AnyValue::String(v) => self.string_value(v)?,
+            AnyValue::StringOwned(ref v) => self.string_value(v.as_str())?,
+            value if value.is_float() => {
+                let v: f64 = value.extract().unwrap_or(f64::NAN);
+                match self.field_type {
+                    FieldType::Coordinates => float_value(base_type, v / DEGREES_PER_SEMICIRCLE),
+                    _ if self.scale.is_some() || self.offset.is_some() => {
+                        float_value(base_type, (v + self.offset.unwrap_or(0.0)) * self.scale.unwrap_or(1.0))
+                    }
+                    _ => float_value(base_type, v),
+                }
+            }
+            // Undecoded timestamps are already seconds since the FIT epoch
+            value => int_value(base_type, value.extract().unwrap_or(i128::MAX)),
+        };
+        raw.ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Column '{}' has a value that doesn't fit its FIT base type",
+                self.series.name()
+            ))
+        })
+    }
+
+    /// The raw value of a string, or of an enum by its profile name or number
+    fn string_value(&self, v: &str) -> PyResult<Option<Value>> {
+        let base_type = self.definition.base_type;
+        if base_type == BaseType::String {
+            return Ok(Some(Value::String(v.to_string())));
+        }
+        if let Ok(number) = v.parse::<i128>() {
+            return Ok(int_value(base_type, number));
+        }
+        match self.enum_values.get(v) {
+            Some(&key) => Ok(int_value(base_type, key as i128)),
+            None => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Unknown value '{}' in column '{}'",
+                v,
+                self.series.name()
+            ))),
+        }
+    }
+}
+
+/// Base type to write a record field as: the profile's, or one matching the dtype
+/// for fields the profile doesn't list
+fn record_base_type(field_type: FieldType, dtype: &DataType) -> Option<BaseType> {
+    let base_type = match field_type {
+        FieldType::Timestamp | FieldType::DateTime | FieldType::LocalDateTime => BaseType::UInt32,
+        FieldType::Coordinates => BaseType::SInt32,
+        FieldType::Uint8 => BaseType::UInt8,
+        FieldType::Uint8Z => BaseType::UInt8z,
+        FieldType::Uint16 => BaseType::UInt16,
+        FieldType::Uint16Z => BaseType::UInt16z,
+        FieldType::Uint32 => BaseType::UInt32,
+        FieldType::Uint32Z => BaseType::UInt32z,
+        FieldType::Sint8 => BaseType::SInt8,
+        FieldType::Sint16 => BaseType::SInt16,
+        FieldType::Sint32 => BaseType::SInt32,
+        FieldType::Float32 => BaseType::Float32,
+        FieldType::Byte => BaseType::Byte,
+        FieldType::String => BaseType::String,
+        FieldType::DeviceIndex | FieldType::LeftRightBalance => BaseType::UInt8,
+        FieldType::LeftRightBalance100 | FieldType::MessageIndex => BaseType::UInt16,
+        FieldType::None => match dtype {
+            DataType::Boolean | DataType::UInt8 => BaseType::UInt8,
+            DataType::UInt16 => BaseType::UInt16,
+            DataType::UInt32 | DataType::Datetime(_, _) => BaseType::UInt32,
+            DataType::UInt64 => BaseType::UInt64,
+            DataType::Int8 => BaseType::SInt8,
+            DataType::Int16 => BaseType::SInt16,
+            DataType::Int32 => BaseType::SInt32,
+            DataType::Int64 => BaseType::SInt64,
+            DataType::Float32 => BaseType::Float32,
+            DataType::Float64 => BaseType::Float64,
+            DataType::String => BaseType::String,
+            _ => return None,
+        },
+        // The remaining types of record fields are enums
+        _ => BaseType::Enum,
+    };
+    Some(base_type)
+}
+
+/// An integer as a value of a base type, if it is in range
+fn int_value(base_type: BaseType, v: i128) -> Option<Value> {
+    match base_type {
+        BaseType::Enum | BaseType::UInt8 | BaseType::UInt8z | BaseType::Byte => u8::try_from(v).ok().map(Value::U8),
+        BaseType::SInt8 => i8::try_from(v).ok().map(Value::I8),
+        BaseType::UInt16 | BaseType::UInt16z => u16::try_from(v).ok().map(Value::U16),
+        BaseType::SInt16 => i16::try_from(v).ok().map(Value::I16),
+        BaseType::UInt32 | BaseType::UInt32z => u32::try_from(v).ok().map(Value::U32),
+        BaseType::SInt32 => i32::try_from(v).ok().map(Value::I32),
+        BaseType::UInt64 | BaseType::UInt64z => u64::try_from(v).ok().map(Value::U64),
+        BaseType::SInt64 => i64::try_from(v).ok().map(Value::I64),
+        BaseType::Float32 => Some(Value::F32(v as f32)),
+        BaseType::Float64 => Some(Value::F64(v as f64)),
+        BaseType::String => Some(Value::String(v.to_string())),
+    }
+}
+
+/// A float as a value of a base type, rounded for integer types. NaN is missing.
+fn float_value(base_type: BaseType, v: f64) -> Option<Value> {
+    match base_type {
+        BaseType::Float32 => Some(Value::F32(v as f32)),
+        BaseType::Float64 => Some(Value::F64(v)),
+        _ if v.is_nan() => Some(base_type.invalid()),
+        _ => int_value(base_type, v.round() as i128),
+    }
+}
 
-    Ok(PyDataFrame(df))
+/// Map a Polars error to a Python exception
+fn polars_error(e: PolarsError) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string())
 }
@@ -1,14 +1,37 @@
 use pyo3::prelude::*;
 
+mod components;
+mod decoder;
+mod encoder;
 mod expressions;
 mod io;
+mod manufacturers;
+mod profile;
+mod subfields;
 
-use io::{read_recordmesgs, get_message_types, read_data};
+use io::{read_recordmesgs, read_recordmesgs_from_bytes, get_message_types, get_message_counts, get_field_numbers, decode_manufacturer, decode_product, get_schema, read_header, read_data, read_data_by_type, read_data_from_bytes, read_many_data, read_all_messages, concat_frames, write_records_to_fit, write_data_to_parquet, read_data_arrow_ipc, MessageBatchReader, RecordBatchReader};
 
 #[pymodule]
 fn _internal(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(read_recordmesgs, m)?)?;
     m.add_function(wrap_pyfunction!(get_message_types, m)?)?;
+    m.add_function(wrap_pyfunction!(get_message_counts, m)?)?;
+    m.add_function(wrap_pyfunction!(get_field_numbers, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_manufacturer, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_product, m)?)?;
+    m.add_function(wrap_pyfunction!(get_schema, m)?)?;
+    m.add_function(wrap_pyfunction!(read_header, m)?)?;
     m.add_function(wrap_pyfunction!(read_data, m)?)?;
+    m.add_function(wrap_pyfunction!(read_data_by_type, m)?)?;
+    m.add_function(wrap_pyfunction!(read_recordmesgs_from_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(read_data_from_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(read_data_arrow_ipc, m)?)?;
+    m.add_function(wrap_pyfunction!(read_many_data, m)?)?;
+    m.add_function(wrap_pyfunction!(read_all_messages, m)?)?;
+    m.add_function(wrap_pyfunction!(concat_frames, m)?)?;
+    m.add_function(wrap_pyfunction!(write_records_to_fit, m)?)?;
+    m.add_function(wrap_pyfunction!(write_data_to_parquet, m)?)?;
+    m.add_class::<MessageBatchReader>()?;
+    m.add_class::<RecordBatchReader>()?;
     Ok(())
 }
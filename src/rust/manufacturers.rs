@@ -0,0 +1,107 @@
+use fitsdk::{match_predefined_field_value, FieldType};
+
+/// Manufacturers whose product ids are garmin_product values
+const GARMIN_PRODUCT_MANUFACTURERS: &[u16] = &[1, 13, 15, 89];
+const FAVERO_ELECTRONICS: u16 = 263;
+
+/// Common garmin_product values of the FIT profile: watches, bike computers and
+/// sensors, by product id
+const GARMIN_PRODUCTS: &[(u16, &str)] = &[
+    (1, "hrm1"),
+    (2, "axh01"),
+    (3, "axb01"),
+    (4, "axb02"),
+    (5, "hrm2ss"),
+    (6, "dsi_alf02"),
+    (7, "hrm3ss"),
+    (8, "hrm_run_single_byte_product_id"),
+    (9, "bsm"),
+    (10, "bcm"),
+    (11, "axs01"),
+    (12, "hrm_tri_single_byte_product_id"),
+    (13, "hrm4_run_single_byte_product_id"),
+    (14, "fr225_single_byte_product_id"),
+    (15, "gen3_bsm_single_byte_product_id"),
+    (16, "gen3_bcm_single_byte_product_id"),
+    (717, "fr405"),
+    (782, "fr50"),
+    (988, "fr60"),
+    (1018, "fr310xt"),
+    (1036, "edge500"),
+    (1124, "fr110"),
+    (1169, "edge800"),
+    (1325, "edge200"),
+    (1328, "fr910xt"),
+    (1345, "fr610"),
+    (1380, "vector_ss"),
+    (1381, "vector_cp"),
+    (1436, "fr70"),
+    (1482, "fr10"),
+    (1499, "swim"),
+    (1551, "fenix"),
+    (1561, "edge510"),
+    (1567, "edge810"),
+    (1623, "fr620"),
+    (1632, "fr220"),
+    (1736, "edge_touring"),
+    (1743, "hrm_tri"),
+    (1752, "hrm_run"),
+    (1765, "fr920xt"),
+    (1836, "edge1000"),
+    (1837, "vivo_fit"),
+    (1903, "fr15"),
+    (1907, "vivo_active"),
+    (1967, "fenix2"),
+    (1988, "epix"),
+    (2050, "fenix3"),
+    (2067, "edge520"),
+    (2079, "vector_s"),
+    (2147, "edge25"),
+    (2148, "fr25"),
+    (2153, "fr225"),
+    (2156, "fr630"),
+    (2157, "fr230"),
+    (2158, "fr735xt"),
+    (2161, "vector_2"),
+    (2162, "vector_2s"),
+    (2238, "edge20"),
+    (2327, "hrm4_run"),
+    (2337, "vivo_active_hr"),
+    (2413, "fenix3_hr"),
+    (2431, "fr235"),
+    (2503, "fr35"),
+    (2530, "edge_820"),
+    (2544, "fenix5s"),
+    (2593, "running_dynamics_pod"),
+    (2604, "fenix5x"),
+    (2691, "fr935"),
+    (2697, "fenix5"),
+    (2700, "vivoactive3"),
+    (2713, "edge_1030"),
+    (65534, "connect"),
+];
+
+/// favero_product values of the FIT profile
+const FAVERO_PRODUCTS: &[(u16, &str)] = &[(10, "assioma_uno"), (12, "assioma_duo")];
+
+/// Profile name of a manufacturer id, or `manufacturer_<id>` if it has none
+pub fn manufacturer_name(manufacturer: u16) -> String {
+    match_predefined_field_value(FieldType::Manufacturer, manufacturer.into())
+        .map_or_else(|| format!("manufacturer_{}", manufacturer), str::to_string)
+}
+
+/// Profile name of a product id of a manufacturer, or `product_<id>` if it has
+/// none. Only Garmin (with its subsidiaries) and Favero list their products.
+pub fn product_name(manufacturer: u16, product: u16) -> String {
+    let products = if GARMIN_PRODUCT_MANUFACTURERS.contains(&manufacturer) {
+        GARMIN_PRODUCTS
+    } else if manufacturer == FAVERO_ELECTRONICS {
+        FAVERO_PRODUCTS
+    } else {
+        &[]
+    };
+    products
+        .iter()
+        .find(|(id, _)| *id == product)
+        .map_or_else(|| format!("product_{}", product), |(_, name)| name.to_string())
+}
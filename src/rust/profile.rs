@@ -0,0 +1,134 @@
+use fitsdk::{match_message_field, match_message_offset, match_message_scale, FieldType, MessageType};
+
+use crate::decoder::{Field, Message, Value};
+
+/// A message type newer than the bundled FIT profile
+pub struct ProfileMessage {
+    pub global_num: u16,
+    pub name: &'static str,
+    /// Other names the message type is read by, without underscores
+    pub aliases: &'static [&'static str],
+    pub fields: &'static [ProfileField],
+}
+
+pub struct ProfileField {
+    pub number: u8,
+    pub field_type: FieldType,
+    pub scale: Option<f32>,
+    pub offset: Option<i16>,
+    /// Whether negative values are status codes rather than measurements
+    pub negative_is_status: bool,
+    /// Names of the values of an enum field, by value
+    pub names: &'static [&'static str],
+}
+
+const fn field(number: u8, field_type: FieldType) -> ProfileField {
+    ProfileField { number, field_type, scale: None, offset: None, negative_is_status: false, names: &[] }
+}
+
+const fn scaled(field: ProfileField, scale: f32) -> ProfileField {
+    ProfileField { scale: Some(scale), ..field }
+}
+
+const fn named(field: ProfileField, names: &'static [&'static str]) -> ProfileField {
+    ProfileField { names, ..field }
+}
+
+const TIMESTAMP: ProfileField = field(253, FieldType::DateTime);
+
+/// Breaths per minute of wrist-based devices. Negative rates flag a missing
+/// reading: -300 invalid, -200 large motion and -100 off wrist.
+const RESPIRATION_RATE: ProfileMessage = ProfileMessage {
+    global_num: 297,
+    name: "respiration_rate",
+    aliases: &[],
+    fields: &[
+        TIMESTAMP,
+        ProfileField { negative_is_status: true, ..scaled(field(0, FieldType::Sint16), 100.0) },
+    ],
+};
+
+/// Blood oxygen saturation in percent from a pulse oximeter, with the
+/// device's confidence in the reading and how it was taken
+const SPO2_DATA: ProfileMessage = ProfileMessage {
+    global_num: 269,
+    name: "spo2_data",
+    aliases: &["spo2"],
+    fields: &[
+        TIMESTAMP,
+        field(0, FieldType::Uint8),
+        field(1, FieldType::Uint8),
+        named(field(2, FieldType::Uint8), &["off_wrist", "spot_check", "continuous_check", "periodic"]),
+    ],
+};
+
+const MESSAGES: &[ProfileMessage] = &[SPO2_DATA, RESPIRATION_RATE];
+
+/// Profile of a message type the bundled FIT profile lacks
+pub fn profile_message(global_num: u16) -> Option<&'static ProfileMessage> {
+    MESSAGES.iter().find(|message| message.global_num == global_num)
+}
+
+/// Global message number of a message type read by one of its aliases
+pub fn message_alias(name: &str) -> Option<u16> {
+    MESSAGES.iter().find(|message| message.aliases.contains(&name)).map(|message| message.global_num)
+}
+
+/// Profile lookups for the fields of one message, from the bundled FIT profile or
+/// the messages it lacks
+pub struct MessageProfile {
+    kind: MessageType,
+    message: Option<&'static ProfileMessage>,
+}
+
+impl MessageProfile {
+    pub fn of(message: &Message) -> Self {
+        let profile = match message.kind {
+            MessageType::None => profile_message(message.global_num),
+            _ => None,
+        };
+        MessageProfile { kind: message.kind, message: profile }
+    }
+
+    fn field(&self, number: usize) -> Option<&'static ProfileField> {
+        self.message?.fields.iter().find(|field| usize::from(field.number) == number)
+    }
+
+    pub fn field_type(&self, number: usize) -> FieldType {
+        match self.message {
+            Some(_) => self.field(number).map_or(FieldType::None, |field| field.field_type),
+            None => match_message_field(self.kind)(number),
+        }
+    }
+
+    pub fn scale(&self, number: usize) -> Option<f32> {
+        match self.message {
+            Some(_) => self.field(number).and_then(|field| field.scale),
+            None => match_message_scale(self.kind)(number),
+        }
+    }
+
+    pub fn offset(&self, number: usize) -> Option<i16> {
+        match self.message {
+            Some(_) => self.field(number).and_then(|field| field.offset),
+            None => match_message_offset(self.kind)(number),
+        }
+    }
+
+    /// Names of the values of an enum field the bundled FIT profile lacks
+    pub fn enum_names(&self, number: usize) -> Option<&'static [&'static str]> {
+        self.field(number).map(|field| field.names).filter(|names| !names.is_empty())
+    }
+
+    /// Whether a field holds a status code in place of a measurement
+    pub fn is_status(&self, field: &Field) -> bool {
+        let negative = match field.value {
+            Value::I8(v) => v < 0,
+            Value::I16(v) => v < 0,
+            Value::I32(v) => v < 0,
+            Value::I64(v) => v < 0,
+            _ => false,
+        };
+        negative && self.field(field.number.into()).is_some_and(|field| field.negative_is_status)
+    }
+}
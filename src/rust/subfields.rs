@@ -0,0 +1,121 @@
+use crate::components::{Component, GEAR_CHANGE_COMPONENTS, SPORT_POINT_COMPONENTS};
+use crate::decoder::{Message, Value};
+
+/// Interpretation of a field that applies when another field of the message,
+/// the reference field, has one of the given values
+pub struct SubField {
+    pub name: &'static str,
+    pub reference: u8,
+    pub reference_values: &'static [u64],
+    pub scale: f64,
+    pub offset: f64,
+    /// Names of the values of an enum subfield, by value
+    pub names: &'static [&'static str],
+    pub components: &'static [Component],
+}
+
+const fn subfield(name: &'static str, reference: u8, reference_values: &'static [u64]) -> SubField {
+    SubField { name, reference, reference_values, scale: 1.0, offset: 0.0, names: &[], components: &[] }
+}
+
+const fn scaled(subfield: SubField, scale: f64) -> SubField {
+    SubField { scale, ..subfield }
+}
+
+const fn named(subfield: SubField, names: &'static [&'static str]) -> SubField {
+    SubField { names, ..subfield }
+}
+
+/// Event field `event`, which selects the meaning of `data`
+const EVENT: u8 = 0;
+
+const EVENT_DATA_SUBFIELDS: &[SubField] = &[
+    named(subfield("timer_trigger", EVENT, &[0]), &["manual", "auto", "fitness_equipment"]),
+    subfield("course_point_index", EVENT, &[10]),
+    scaled(subfield("battery_level", EVENT, &[11]), 1000.0),
+    scaled(subfield("virtual_partner_speed", EVENT, &[12]), 1000.0),
+    subfield("hr_high_alert", EVENT, &[13]),
+    subfield("hr_low_alert", EVENT, &[14]),
+    scaled(subfield("speed_high_alert", EVENT, &[15]), 1000.0),
+    scaled(subfield("speed_low_alert", EVENT, &[16]), 1000.0),
+    subfield("cad_high_alert", EVENT, &[17]),
+    subfield("cad_low_alert", EVENT, &[18]),
+    subfield("power_high_alert", EVENT, &[19]),
+    subfield("power_low_alert", EVENT, &[20]),
+    scaled(subfield("time_duration_alert", EVENT, &[23]), 1000.0),
+    scaled(subfield("distance_duration_alert", EVENT, &[24]), 100.0),
+    subfield("calorie_duration_alert", EVENT, &[25]),
+    named(subfield("fitness_equipment_state", EVENT, &[27]), &["ready", "in_use", "paused", "unknown"]),
+    SubField { components: SPORT_POINT_COMPONENTS, ..subfield("sport_point", EVENT, &[33]) },
+    SubField { components: GEAR_CHANGE_COMPONENTS, ..subfield("gear_change_data", EVENT, &[42, 43]) },
+    named(
+        subfield("rider_position", EVENT, &[44]),
+        &["seated", "standing", "transition_to_seated", "transition_to_standing"],
+    ),
+    named(
+        subfield("comm_timeout", EVENT, &[47]),
+        &["wildcard_pairing_timeout", "pairing_timeout", "connection_lost", "connection_timeout"],
+    ),
+];
+
+/// Sports whose cycles are strides or strokes: running and walking, then
+/// cycling, swimming, rowing and stand up paddleboarding
+const STRIDE_SPORTS: &[u64] = &[1, 11];
+const STROKE_SPORTS: &[u64] = &[2, 5, 15, 37];
+const RUNNING: &[u64] = &[1];
+
+/// Session and lap subfields, selected by the `sport` field of the message
+const fn cycles_subfields(sport: u8) -> [SubField; 2] {
+    [subfield("total_strides", sport, STRIDE_SPORTS), subfield("total_strokes", sport, STROKE_SPORTS)]
+}
+
+const SESSION_CYCLES_SUBFIELDS: &[SubField] = &cycles_subfields(5);
+const SESSION_AVG_CADENCE_SUBFIELDS: &[SubField] = &[subfield("avg_running_cadence", 5, RUNNING)];
+const SESSION_MAX_CADENCE_SUBFIELDS: &[SubField] = &[subfield("max_running_cadence", 5, RUNNING)];
+const LAP_CYCLES_SUBFIELDS: &[SubField] = &cycles_subfields(25);
+const LAP_AVG_CADENCE_SUBFIELDS: &[SubField] = &[subfield("avg_running_cadence", 25, RUNNING)];
+const LAP_MAX_CADENCE_SUBFIELDS: &[SubField] = &[subfield("max_running_cadence", 25, RUNNING)];
+
+/// Monitoring cycles are steps when walking or running and strokes when
+/// cycling or swimming, selected by the `activity_type` field
+const MONITORING_CYCLES_SUBFIELDS: &[SubField] =
+    &[subfield("steps", 5, &[1, 6]), scaled(subfield("strokes", 5, &[2, 5]), 2.0)];
+
+/// Subfields of a field in the FIT profile
+fn subfields(global_num: u16, field: u8) -> &'static [SubField] {
+    match (global_num, field) {
+        // session: total_cycles, avg_cadence, max_cadence
+        (18, 10) => SESSION_CYCLES_SUBFIELDS,
+        (18, 18) => SESSION_AVG_CADENCE_SUBFIELDS,
+        (18, 19) => SESSION_MAX_CADENCE_SUBFIELDS,
+        // lap: total_cycles, avg_cadence, max_cadence
+        (19, 10) => LAP_CYCLES_SUBFIELDS,
+        (19, 17) => LAP_AVG_CADENCE_SUBFIELDS,
+        (19, 18) => LAP_MAX_CADENCE_SUBFIELDS,
+        // event: data
+        (21, 3) => EVENT_DATA_SUBFIELDS,
+        // monitoring: cycles
+        (55, 3) => MONITORING_CYCLES_SUBFIELDS,
+        _ => &[],
+    }
+}
+
+/// The subfield a field of a message is to be read as, given its reference fields
+pub fn active_subfield(message: &Message, field: u8) -> Option<&'static SubField> {
+    subfields(message.global_num, field).iter().find(|subfield| {
+        message
+            .field(subfield.reference)
+            .and_then(|reference| integer(&reference.value))
+            .is_some_and(|value| subfield.reference_values.contains(&value))
+    })
+}
+
+fn integer(value: &Value) -> Option<u64> {
+    match *value {
+        Value::U8(v) => Some(v.into()),
+        Value::U16(v) => Some(v.into()),
+        Value::U32(v) => Some(v.into()),
+        Value::U64(v) => Some(v),
+        _ => None,
+    }
+}
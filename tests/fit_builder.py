@@ -0,0 +1,245 @@
+"""Helpers for building small synthetic FIT files in tests."""
+
+import struct
+from pathlib import Path
+
+# FIT base types (value of the base type byte in a field definition)
+ENUM = 0x00
+SINT8 = 0x01
+UINT8 = 0x02
+SINT16 = 0x83
+UINT16 = 0x84
+SINT32 = 0x85
+UINT32 = 0x86
+STRING = 0x07
+FLOAT32 = 0x88
+FLOAT64 = 0x89
+UINT8Z = 0x0A
+UINT16Z = 0x8B
+UINT32Z = 0x8C
+BYTE = 0x0D
+SINT64 = 0x8E
+UINT64 = 0x8F
+UINT64Z = 0x90
+
+_FORMATS = {
+    ENUM: "B",
+    SINT8: "b",
+    UINT8: "B",
+    SINT16: "h",
+    UINT16: "H",
+    SINT32: "i",
+    UINT32: "I",
+    FLOAT32: "f",
+    FLOAT64: "d",
+    UINT8Z: "B",
+    UINT16Z: "H",
+    UINT32Z: "I",
+    BYTE: "B",
+    SINT64: "q",
+    UINT64: "Q",
+    UINT64Z: "Q",
+}
+
+# Global message numbers used throughout the tests
+FILE_ID = 0
+SESSION = 18
+LAP = 19
+RECORD = 20
+EVENT = 21
+DEVICE_INFO = 23
+WORKOUT_STEP = 27
+COURSE_POINT = 32
+ACTIVITY = 34
+MONITORING = 55
+LENGTH = 101
+MONITORING_INFO = 103
+GPS_METADATA = 160
+SPO2_DATA = 269
+RESPIRATION_RATE = 297
+FIELD_DESCRIPTION = 206
+DEVELOPER_DATA_ID = 207
+
+# Seconds between the Unix epoch and the FIT epoch (1989-12-31 00:00:00 UTC)
+FIT_EPOCH_OFFSET = 631065600
+
+_CRC_TABLE = [
+    0x0000,
+    0xCC01,
+    0xD801,
+    0x1400,
+    0xF001,
+    0x3C00,
+    0x2800,
+    0xE401,
+    0xA001,
+    0x6C00,
+    0x7800,
+    0xB401,
+    0x5000,
+    0x9C01,
+    0x8801,
+    0x4400,
+]
+
+
+def fit_crc(data: bytes, crc: int = 0) -> int:
+    """Compute the FIT CRC-16 of ``data``."""
+    for byte in data:
+        tmp = _CRC_TABLE[crc & 0xF]
+        crc = (crc >> 4) & 0x0FFF
+        crc = crc ^ tmp ^ _CRC_TABLE[byte & 0xF]
+        tmp = _CRC_TABLE[crc & 0xF]
+        crc = (crc >> 4) & 0x0FFF
+        crc = crc ^ tmp ^ _CRC_TABLE[(byte >> 4) & 0xF]
+    return crc
+
+
+def fit_time(unix_seconds: int) -> int:
+    """Convert Unix seconds to a raw FIT timestamp."""
+    return unix_seconds - FIT_EPOCH_OFFSET
+
+
+class FitBuilder:
+    """Incrementally assemble the records of a FIT file.
+
+    Field definitions are ``(field_number, base_type, size)`` tuples; when
+    ``size`` is omitted it defaults to a single value of the base type.
+    Developer field definitions are ``(field_number, base_type,
+    developer_data_index, size)`` tuples, again with an optional size.
+    """
+
+    def __init__(self, header_size: int = 14, profile_version: int = 2132):
+        self.header_size = header_size
+        self.profile_version = profile_version
+        self._records = bytearray()
+        self._definitions: dict[int, list[tuple[int, int, int]]] = {}
+        self._developer_definitions: dict[int, list[tuple[int, int, int]]] = {}
+
+    def define(
+        self,
+        local_num: int,
+        global_num: int,
+        fields: list[tuple],
+        developer_fields: list[tuple] | None = None,
+    ) -> "FitBuilder":
+        """Write a definition message for ``local_num``."""
+        normalized = [self._normalize(field) for field in fields]
+        # Drop the developer data index so the size sits where _normalize expects it
+        developer = [
+            self._normalize(field[:2] + field[3:])
+            for field in developer_fields or []
+        ]
+
+        header = 0x40 | local_num
+        if developer_fields is not None:
+            header |= 0x20
+        self._records.append(header)
+        self._records += struct.pack("<BBHB", 0, 0, global_num, len(normalized))
+        for number, base_type, size in normalized:
+            self._records += struct.pack("<BBB", number, size, base_type)
+        if developer_fields is not None:
+            self._records.append(len(developer))
+            for (number, _, size), field in zip(
+                developer, developer_fields, strict=True
+            ):
+                self._records += struct.pack("<BBB", number, size, field[2])
+        self._definitions[local_num] = normalized
+        self._developer_definitions[local_num] = developer
+        return self
+
+    @staticmethod
+    def _normalize(field: tuple) -> tuple[int, int, int]:
+        if len(field) == 2:
+            number, base_type = field
+            size = struct.calcsize(_FORMATS.get(base_type, "B"))
+        else:
+            number, base_type, size = field
+        return number, base_type, size
+
+    def data(
+        self,
+        local_num: int,
+        values: list,
+        developer_values: list | None = None,
+    ) -> "FitBuilder":
+        """Write a data message; ``values`` follow the definition order."""
+        self._records.append(local_num)
+        self._records += self._encode(self._definitions[local_num], values)
+        self._records += self._encode(
+            self._developer_definitions[local_num], developer_values or []
+        )
+        return self
+
+    def compressed_data(
+        self, local_num: int, time_offset: int, values: list
+    ) -> "FitBuilder":
+        """Write a data message with a compressed timestamp header.
+
+        Only local messages 0-3 fit in the header; ``time_offset`` is the low
+        5 bits of the message timestamp.
+        """
+        self._records.append(0x80 | (local_num & 0x3) << 5 | time_offset & 0x1F)
+        self._records += self._encode(self._definitions[local_num], values)
+        return self
+
+    def describe_developer_field(
+        self,
+        local_num: int,
+        developer_data_index: int,
+        number: int,
+        base_type: int,
+        name: str,
+        units: str = "",
+        scale: int | None = None,
+        native_field: int | None = None,
+    ) -> "FitBuilder":
+        """Write a field_description message for a developer field.
+
+        ``native_field`` is the record field the developer field stands in for.
+        """
+        fields = [(0, UINT8), (1, UINT8), (2, UINT8), (3, STRING, 32), (8, STRING, 16)]
+        values = [developer_data_index, number, base_type, name, units]
+        if scale is not None:
+            fields.append((6, UINT8))
+            values.append(scale)
+        if native_field is not None:
+            fields += [(14, UINT16), (15, UINT8)]
+            values += [RECORD, native_field]
+        self.define(local_num, FIELD_DESCRIPTION, fields)
+        return self.data(local_num, values)
+
+    @staticmethod
+    def _encode(definitions: list[tuple[int, int, int]], values: list) -> bytes:
+        out = bytearray()
+        for (_, base_type, size), value in zip(definitions, values, strict=True):
+            if base_type == STRING:
+                raw = value.encode() if isinstance(value, str) else value
+                out += raw[:size].ljust(size, b"\x00")
+                continue
+            fmt = _FORMATS[base_type]
+            items = value if isinstance(value, list | tuple) else [value]
+            packed = b"".join(struct.pack("<" + fmt, item) for item in items)
+            assert len(packed) == size, "value does not match field size"
+            out += packed
+        return bytes(out)
+
+    def to_bytes(self) -> bytes:
+        """Return the complete file including header and CRCs."""
+        header = struct.pack(
+            "<BBHI4s",
+            self.header_size,
+            0x20,
+            self.profile_version,
+            len(self._records),
+            b".FIT",
+        )
+        if self.header_size == 14:
+            header += struct.pack("<H", fit_crc(header))
+        body = header + bytes(self._records)
+        return body + struct.pack("<H", fit_crc(body))
+
+    def write(self, path: Path) -> Path:
+        """Write the file to ``path`` and return it."""
+        path.write_bytes(self.to_bytes())
+        return path
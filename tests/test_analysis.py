@@ -0,0 +1,179 @@
+#!/usr/bin/env python3
+"""Tests for the performance analyses of records."""
+
+from datetime import datetime, timedelta, timezone
+
+import pytest
+
+import polarsfit
+from tests.fit_builder import (
+    RECORD,
+    SESSION,
+    UINT8,
+    UINT16,
+    UINT32,
+    FitBuilder,
+    fit_time,
+)
+
+START = 1_700_000_000
+
+
+def build_power(watts: list[int]) -> FitBuilder:
+    """Build 1 Hz records with the given power."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (7, UINT16)])
+    for i, power in enumerate(watts):
+        builder.data(0, [fit_time(START + i), power])
+    return builder
+
+
+def test_power_curve_of_constant_power_is_that_power(tmp_path):
+    """Every duration that fits in the ride averages the constant power."""
+    path = build_power([250] * 120).write(tmp_path / "ride.fit")
+
+    curve = polarsfit.power_curve(str(path))
+
+    assert curve["duration"].to_list() == [1, 5, 15, 30, 60, 300, 600, 1200, 3600]
+    assert curve["power"].to_list() == [250.0] * 5 + [None] * 4
+
+
+def test_power_curve_finds_the_best_effort(tmp_path):
+    """A 10 s surge sets the short durations, the longer ones average down."""
+    path = build_power([100] * 30 + [400] * 10 + [100] * 20).write(
+        tmp_path / "ride.fit"
+    )
+
+    curve = polarsfit.power_curve(str(path), durations=[1, 10, 20, 60, 61])
+
+    assert curve["power"].to_list() == [400.0, 400.0, 250.0, 150.0, None]
+
+
+def test_time_in_zones_weights_samples_by_their_interval(tmp_path):
+    """Zone seconds follow the sample intervals and leave out the pause."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (3, UINT8)])
+    # 1 Hz in zone 0, 2 s intervals in zone 1, a one minute pause, zone 2
+    samples = [(0, 100), (1, 100), (2, 100), (3, 130), (5, 130), (7, 130)]
+    samples += [(67, 160), (68, 160), (69, 160)]
+    for offset, heart_rate in samples:
+        builder.data(0, [fit_time(START + offset), heart_rate])
+    path = builder.write(tmp_path / "run.fit")
+
+    zones = polarsfit.time_in_zones(str(path), boundaries=[120, 150, 180])
+
+    assert zones["zone"].to_list() == [0, 1, 2, 3]
+    assert zones["seconds"].to_list() == [3.0, 4.0, 2.0, 0.0]
+    # Elapsed time without the pause
+    assert zones["seconds"].sum() == 69 - 60
+
+
+def test_detect_stops_merges_micro_stops(tmp_path):
+    """A stop with a short roll in the middle is one stop; a brief one is none."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (6, UINT16)])
+    # Speeds in mm/s: a 20 s stop rolling on for 2 s, later a 5 s stop
+    speeds = [3000] * 30 + [0] * 10 + [1000] * 2 + [0] * 8 + [3000] * 10
+    speeds += [0] * 5 + [3000] * 15
+    for i, speed in enumerate(speeds):
+        builder.data(0, [fit_time(START + i), speed])
+    path = builder.write(tmp_path / "ride.fit")
+
+    stops = polarsfit.detect_stops(str(path))
+
+    assert stops.columns == ["start_time", "end_time", "duration"]
+    assert stops.height == 1
+    start = datetime.fromtimestamp(START, tz=timezone.utc)
+    assert stops["start_time"][0] == start + timedelta(seconds=30)
+    assert stops["end_time"][0] == start + timedelta(seconds=50)
+    assert stops["duration"][0] == 20.0
+
+
+def test_best_efforts_of_an_even_pace_run(tmp_path):
+    """At 4 m/s, 1 km takes 250 s; the run is too short for 5 km."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (5, UINT32)])
+    for i in range(1200):
+        # Distance in cm
+        builder.data(0, [fit_time(START + i), 400 * i])
+    path = builder.write(tmp_path / "run.fit")
+
+    efforts = polarsfit.best_efforts(str(path))
+
+    assert efforts["distance"].to_list() == [1000.0, 5000.0, 10000.0]
+    assert efforts["elapsed_time"].to_list() == [250.0, None, None]
+    assert efforts["pace"][0] == 250.0
+    assert efforts["start_index"][0] == 0
+
+
+def test_decoupling_of_a_drifting_heart_rate(tmp_path):
+    """Heart rate up from 140 to 150 bpm at the same output is 6.67% decoupling."""
+    builder = FitBuilder().define(
+        0, RECORD, [(253, UINT32), (3, UINT8), (6, UINT16), (7, UINT16)]
+    )
+    # A 100 s warm-up and cool-down around two halves of 500 s
+    heart_rates = [100] * 100 + [140] * 500 + [150] * 500 + [100] * 100
+    for i, heart_rate in enumerate(heart_rates):
+        builder.data(0, [fit_time(START + i), heart_rate, 3000, 200])
+    path = builder.write(tmp_path / "ride.fit")
+
+    def decoupling(channel, trim):
+        return polarsfit.decoupling(
+            str(path), channel, warmup_seconds=trim, cooldown_seconds=trim
+        )
+
+    expected = (1 - 140 / 150) * 100
+    assert decoupling("power", 100) == pytest.approx(expected)
+    assert decoupling("speed", 100) == pytest.approx(expected)
+    assert decoupling("power", 0) != pytest.approx(expected)
+    with pytest.raises(ValueError, match="no time"):
+        decoupling("power", 600)
+
+
+def test_estimate_calories_from_constant_power(tmp_path):
+    """An hour at 200 W is 720 kJ of work, burnt at 23% efficiency."""
+    path = build_power([200] * 3600).write(tmp_path / "ride.fit")
+
+    kcal = polarsfit.estimate_calories(str(path))
+
+    assert kcal == pytest.approx(720_000 / 0.23 / 4184)
+    assert kcal == pytest.approx(748.2, abs=0.1)
+    assert polarsfit.estimate_calories(str(path), 0.25) == pytest.approx(
+        720_000 / 0.25 / 4184
+    )
+
+
+def test_estimate_calories_falls_back_to_heart_rate(tmp_path):
+    """Without power, ten minutes at 150 bpm follow the Keytel equation."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (3, UINT8)])
+    for i in range(600):
+        builder.data(0, [fit_time(START + i), 150])
+    path = builder.write(tmp_path / "run.fit")
+
+    kcal = polarsfit.estimate_calories(str(path), weight=70, age=35, sex="male")
+
+    kj_per_minute = -55.0969 + 0.6309 * 150 + 0.1988 * 70 + 0.2017 * 35
+    assert kcal == pytest.approx(kj_per_minute * 10 / 4.184)
+    with pytest.raises(ValueError, match="weight, age and sex"):
+        polarsfit.estimate_calories(str(path))
+
+
+def test_ascent_descent_matches_the_session_totals(tmp_path):
+    """Hysteresis ignores 1.6 m of noise that naive summing counts as climbing."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (78, UINT32)])
+    # Up 60 m at 1 m/s, then down 30 m, zigzagging 0.8 m around the slope
+    altitudes = [i if i <= 60 else 60 - (i - 60) / 2 for i in range(121)]
+    altitudes = [a + (0.8 if i % 2 else -0.8) for i, a in enumerate(altitudes)]
+    for i, altitude in enumerate(altitudes):
+        # enhanced_altitude = raw / 5 - 500
+        builder.data(0, [fit_time(START + i), round((altitude + 500) * 5)])
+    builder.define(1, SESSION, [(253, UINT32), (2, UINT32), (22, UINT16), (23, UINT16)])
+    builder.data(1, [fit_time(START + 121), fit_time(START), 60, 30])
+    path = builder.write(tmp_path / "climb.fit")
+
+    ascent, descent = polarsfit.ascent_descent(str(path))
+
+    session = polarsfit.read_session_summary(str(path))
+    assert ascent == pytest.approx(session["total_ascent"][0], abs=2)
+    assert descent == pytest.approx(session["total_descent"][0], abs=2)
+    naive, _ = polarsfit.ascent_descent(str(path), threshold=0)
+    assert naive > 100
+    assert polarsfit.ascent_descent(
+        str(build_power([200] * 10).write(tmp_path / "flat.fit"))
+    ) == (0.0, 0.0)
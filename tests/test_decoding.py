@@ -0,0 +1,583 @@
+#!/usr/bin/env python3
+"""Decoding tests against small synthetic FIT files."""
+
+from datetime import datetime, timezone
+
+import polars as pl
+import pytest
+from polars.testing import assert_frame_equal
+
+import polarsfit
+from tests.fit_builder import (
+    BYTE,
+    ENUM,
+    EVENT,
+    FILE_ID,
+    FLOAT32,
+    LAP,
+    MONITORING,
+    MONITORING_INFO,
+    RECORD,
+    RESPIRATION_RATE,
+    SESSION,
+    SINT8,
+    SINT16,
+    SINT32,
+    SPO2_DATA,
+    STRING,
+    UINT8,
+    UINT16,
+    UINT32,
+    FitBuilder,
+    fit_time,
+)
+
+START = 1_700_000_000
+
+
+def test_records_stay_aligned_when_fields_are_omitted(tmp_path):
+    """Row N must hold message N even if some records omit a field."""
+    builder = (
+        FitBuilder()
+        .define(0, RECORD, [(253, UINT32), (3, UINT8), (7, UINT16)])
+        .define(1, RECORD, [(253, UINT32), (3, UINT8)])
+    )
+    with_power = {0, 3, 4}
+    for i in range(6):
+        if i in with_power:
+            builder.data(0, [fit_time(START + i), 100 + i, 200 + i])
+        else:
+            builder.data(1, [fit_time(START + i), 100 + i])
+    path = builder.write(tmp_path / "sparse.fit")
+
+    df = polarsfit.read_data(
+        str(path),
+        "record",
+        apply_default_mapping=False,
+        decode_timestamps=False,
+    )
+
+    assert df.height == 6
+    assert df["field_253"].to_list() == [fit_time(START + i) for i in range(6)]
+    assert df["field_3"].to_list() == [100 + i for i in range(6)]
+    assert df["field_7"].to_list() == [
+        200 + i if i in with_power else None for i in range(6)
+    ]
+
+
+def test_decoding_stops_at_the_file_crc(tmp_path):
+    """The trailing CRC is not read as the start of another message."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (3, UINT8)])
+    for i in range(3):
+        builder.data(0, [fit_time(START + i), 100 + i])
+    path = builder.write(tmp_path / "records.fit")
+
+    df = polarsfit.read_data(str(path), "record", apply_default_mapping=False)
+
+    assert df.height == 3
+    assert polarsfit.get_message_types(str(path)) == ["record"]
+
+
+def test_files_without_a_fit_header_raise(tmp_path):
+    """Data without the .FIT signature is rejected rather than read as empty."""
+    path = tmp_path / "not_fit.fit"
+    path.write_bytes(b"\x0e\x20\x54\x08\x00\x00\x00\x00.TXT\x00\x00")
+
+    with pytest.raises(ValueError, match="Not a FIT file"):
+        polarsfit.read_data(str(path), "record")
+
+
+def test_positions_decode_to_full_precision_degrees(tmp_path):
+    """Coordinates of any message type become Float64 degrees."""
+    lat = 600_000_001
+    builder = (
+        FitBuilder()
+        .define(0, LAP, [(253, UINT32), (3, SINT32), (200, SINT32)])
+        .data(0, [fit_time(START), lat, lat])
+    )
+    path = builder.write(tmp_path / "lap.fit")
+
+    df = polarsfit.read_data(str(path), "lap", apply_default_mapping=False)
+
+    assert df["field_3"].dtype == pl.Float64
+    assert abs(df["field_3"][0] - lat * 180 / 2**31) < 1e-9
+    # Int32 fields the profile doesn't type as coordinates are left alone
+    assert df["field_200"].to_list() == [lat]
+
+    # convert_gps only keeps record positions raw
+    raw = polarsfit.read_data(
+        str(path), "lap", apply_default_mapping=False, convert_gps=False
+    )
+    assert_frame_equal(raw, df)
+
+
+def test_array_fields_become_list_columns(tmp_path):
+    """Multi-valued fields keep their values as a List column."""
+    builder = (
+        FitBuilder()
+        .define(0, RECORD, [(253, UINT32), (90, UINT16, 6)])
+        .define(1, RECORD, [(253, UINT32), (90, UINT16)])
+        .data(0, [fit_time(START), [1, 2, 3]])
+        .data(1, [fit_time(START + 1), 7])
+        .data(0, [fit_time(START + 2), [4, 5, 6]])
+    )
+    path = builder.write(tmp_path / "arrays.fit")
+
+    df = polarsfit.read_data(str(path), "record", apply_default_mapping=False)
+
+    assert df["field_90"].dtype == pl.List
+    assert df["field_90"].dtype.inner == pl.UInt32
+    assert df["field_90"].to_list() == [[1, 2, 3], [7], [4, 5, 6]]
+
+
+def test_timestamps_decode_to_utc_datetime(tmp_path):
+    """Timestamp fields become UTC datetimes; invalid values become null."""
+    builder = (
+        FitBuilder()
+        .define(0, RECORD, [(253, UINT32), (3, UINT8)])
+        .data(0, [fit_time(START), 120])
+        .data(0, [0xFFFFFFFF, 121])
+    )
+    path = builder.write(tmp_path / "timestamps.fit")
+
+    df = polarsfit.read_data(str(path), "record", apply_default_mapping=False)
+
+    assert df["field_253"].dtype == pl.Datetime("ms", "UTC")
+    assert df["field_253"].to_list() == [
+        datetime.fromtimestamp(START, tz=timezone.utc),
+        None,
+    ]
+
+    raw = polarsfit.read_data(
+        str(path),
+        "record",
+        apply_default_mapping=False,
+        decode_timestamps=False,
+    )
+    assert raw["field_253"].dtype == pl.UInt32
+    assert raw["field_253"][0] == fit_time(START)
+
+
+def test_scale_and_offset_are_applied(tmp_path):
+    """Speed (scale 1000) and altitude (scale 5, offset 500) are decoded."""
+    builder = (
+        FitBuilder()
+        .define(0, RECORD, [(253, UINT32), (6, UINT16), (2, UINT16)])
+        .data(0, [fit_time(START), 3500, 2600])
+    )
+    path = builder.write(tmp_path / "scaled.fit")
+
+    df = polarsfit.read_data(str(path), "record", apply_default_mapping=False)
+
+    assert df["field_6"].dtype == pl.Float64
+    assert abs(df["field_6"][0] - 3.5) < 1e-9
+    assert abs(df["field_2"][0] - 20.0) < 1e-9
+
+    raw = polarsfit.read_data(
+        str(path), "record", apply_default_mapping=False, apply_scale=False
+    )
+    assert raw["field_6"][0] == 3500
+
+
+def test_positions_convert_from_semicircles_to_degrees(tmp_path):
+    """position_lat/position_long become Float64 degrees."""
+    lat, long = 600_000_000, -150_000_000
+    builder = (
+        FitBuilder()
+        .define(0, RECORD, [(253, UINT32), (0, SINT32), (1, SINT32)])
+        .data(0, [fit_time(START), lat, long])
+        .data(0, [fit_time(START + 1), 0x7FFFFFFF, 0x7FFFFFFF])
+    )
+    path = builder.write(tmp_path / "positions.fit")
+
+    df = polarsfit.read_data(str(path), "record", apply_default_mapping=False)
+
+    assert df["field_0"].dtype == pl.Float64
+    assert abs(df["field_0"][0] - lat * 180 / 2**31) < 1e-6
+    assert abs(df["field_1"][0] - long * 180 / 2**31) < 1e-6
+    assert df["field_0"][1] is None
+
+    raw = polarsfit.read_data(
+        str(path), "record", apply_default_mapping=False, convert_gps=False
+    )
+    assert raw["field_0"].dtype == pl.Int32
+    assert raw["field_0"][0] == lat
+
+
+def test_enums_decode_to_profile_names(tmp_path):
+    """Session sport decodes to its name; unknown values keep the number."""
+    builder = (
+        FitBuilder()
+        .define(0, SESSION, [(253, UINT32), (5, ENUM)])
+        .data(0, [fit_time(START), 2])
+        .data(0, [fit_time(START + 1), 200])
+    )
+    path = builder.write(tmp_path / "enums.fit")
+
+    df = polarsfit.read_data(
+        str(path), "session", apply_default_mapping=False, categorical_enums=False
+    )
+
+    assert df["field_5"].dtype == pl.String
+    assert df["field_5"].to_list() == ["cycling", "200"]
+
+    raw = polarsfit.read_data(
+        str(path), "session", apply_default_mapping=False, decode_enums=False
+    )
+    assert raw["field_5"].to_list() == [2, 200]
+
+
+def test_enum_columns_are_categorical(tmp_path):
+    """Decoded enum names are categories; other strings stay strings."""
+    builder = (
+        FitBuilder()
+        .define(0, SESSION, [(253, UINT32), (5, ENUM), (6, ENUM)])
+        .data(0, [fit_time(START), 2, 7])
+        .data(0, [fit_time(START + 1), 1, 0])
+        .define(1, FILE_ID, [(0, ENUM), (8, STRING, 16)])
+        .data(1, [4, "Edge 540"])
+    )
+    path = builder.write(tmp_path / "categories.fit")
+
+    sessions = polarsfit.read_data(str(path), "session")
+    file_id = polarsfit.read_data(str(path), "file_id")
+
+    assert sessions["sport"].dtype == pl.Categorical
+    assert sessions["sport"].to_list() == ["cycling", "running"]
+    assert sessions["sub_sport"].dtype == pl.Categorical
+    assert file_id["type"].dtype == pl.Categorical
+    assert file_id["product_name"].dtype == pl.String
+
+
+def test_strings_drop_padding_and_survive_invalid_bytes(tmp_path):
+    """Null padding is trimmed and non-UTF-8 names still decode."""
+    builder = (
+        FitBuilder()
+        .define(0, FILE_ID, [(0, ENUM), (8, STRING, 16)])
+        .data(0, [4, "Edge 540"])
+        .data(0, [4, b"Caf\xe9 \xff\x00junk"])
+    )
+    path = builder.write(tmp_path / "strings.fit")
+
+    utf8 = polarsfit.read_data(str(path), "file_id")
+    latin1 = polarsfit.read_data(str(path), "file_id", encoding="latin-1")
+
+    assert utf8["product_name"].to_list() == ["Edge 540", "Caf\ufffd \ufffd"]
+    assert latin1["product_name"].to_list() == ["Edge 540", "Caf\xe9 \xff"]
+    with pytest.raises(ValueError, match="encoding"):
+        polarsfit.read_data(str(path), "file_id", encoding="ascii")
+
+
+def test_developer_fields_get_namespaced_columns(tmp_path):
+    """Developer power sits next to, not on top of, the standard power field."""
+    builder = (
+        FitBuilder()
+        .describe_developer_field(1, 0, 0, UINT16, "Power", "Watts")
+        .define(
+            0,
+            RECORD,
+            [(253, UINT32), (7, UINT16)],
+            developer_fields=[(0, UINT16, 0), (5, UINT8, 0)],
+        )
+        .data(0, [fit_time(START), 250], developer_values=[260, 9])
+        .data(0, [fit_time(START + 1), 251], developer_values=[0xFFFF, 9])
+    )
+    path = builder.write(tmp_path / "developer.fit")
+
+    df = polarsfit.read_data(str(path), "record", apply_default_mapping=False)
+
+    assert df["field_7"].to_list() == [250, 251]
+    assert df["dev_0_Power"].to_list() == [260, None]
+    # Without a field description the field number is used
+    assert df["dev_0_field_5"].to_list() == [9, 9]
+
+
+def test_running_dynamics_get_named_float_columns(tmp_path):
+    """Running dynamics are scaled to mm, ms and %, also from a foot pod app."""
+    builder = (
+        FitBuilder()
+        .describe_developer_field(
+            2, 0, 0, UINT16, "Vertical Oscillation", "mm", scale=10, native_field=39
+        )
+        .describe_developer_field(2, 0, 1, UINT16, "Ground Time", "ms", native_field=41)
+        .define(
+            0,
+            RECORD,
+            [(253, UINT32), (39, UINT16), (41, UINT16), (83, UINT16), (84, UINT16)],
+        )
+        .data(0, [fit_time(START), 952, 2451, 812, 4980])
+        .define(
+            1,
+            RECORD,
+            [(253, UINT32)],
+            developer_fields=[(0, UINT16, 0), (1, UINT16, 0)],
+        )
+        .data(1, [fit_time(START + 1)], developer_values=[948, 248])
+    )
+    path = builder.write(tmp_path / "running.fit")
+
+    df = polarsfit.read_recordmesgs(str(path))
+
+    for column in [
+        "vertical_oscillation",
+        "ground_contact_time",
+        "vertical_ratio",
+        "ground_contact_balance",
+    ]:
+        assert df.schema[column] == pl.Float64
+    assert df["vertical_oscillation"].to_list() == pytest.approx([95.2, 94.8])
+    assert df["vertical_oscillation"].is_between(50, 150).all()
+    assert df["ground_contact_time"].to_list() == pytest.approx([245.1, 248.0])
+    assert df["vertical_ratio"][0] == pytest.approx(8.12)
+    assert df["ground_contact_balance"][0] == pytest.approx(49.8)
+    assert not any(column.startswith("dev_") for column in df.columns)
+
+
+def test_pedal_balance_splits_into_left_and_right(tmp_path):
+    """The balance byte's right flag says which side its percentage is."""
+    fields = [(253, UINT32), (7, UINT16), (30, UINT8), (45, UINT8), (46, UINT8)]
+    builder = FitBuilder().define(0, RECORD, fields)
+    # 52% on the right, 47% on the unflagged (left) side, then no balance
+    builder.data(0, [fit_time(START), 250, 0x80 | 52, 40, 44])
+    builder.data(0, [fit_time(START + 1), 255, 47, 41, 43])
+    builder.data(0, [fit_time(START + 2), 260, 0xFF, 0xFF, 0xFF])
+    path = builder.write(tmp_path / "balance.fit")
+
+    df = polarsfit.read_recordmesgs(str(path))
+
+    assert "left_right_balance" not in df.columns
+    assert df["left_balance_pct"].to_list() == [48.0, 47.0, None]
+    assert df["right_balance_pct"].to_list() == [52.0, 53.0, None]
+    total = df["left_balance_pct"] + df["right_balance_pct"]
+    assert total.drop_nulls().to_list() == pytest.approx([100.0, 100.0])
+    assert df["left_pedal_smoothness"].to_list() == [20.0, 20.5, None]
+    assert df["right_pedal_smoothness"].to_list() == [22.0, 21.5, None]
+
+    raw = polarsfit.read_recordmesgs(str(path), keep_raw=True)
+    assert raw["left_right_balance_raw"].to_list() == [0x80 | 52, 47, None]
+
+
+def test_temperature_converts_to_fahrenheit(tmp_path):
+    """20 and -5 degrees Celsius are 68 and 23 Fahrenheit; 127 is invalid."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (13, SINT8)])
+    for i, celsius in enumerate([20, -5, 0x7F]):
+        builder.data(0, [fit_time(START + i), celsius])
+    path = builder.write(tmp_path / "temperature.fit")
+
+    celsius = polarsfit.read_recordmesgs(str(path))
+    fahrenheit = polarsfit.read_recordmesgs(str(path), temperature_unit="F")
+    unmasked = polarsfit.read_data(
+        str(path), "record", mask_invalid=False, temperature_unit="F"
+    )
+
+    assert celsius["temperature"].to_list() == [20, -5, None]
+    assert fahrenheit.schema["temperature"] == pl.Float64
+    assert fahrenheit["temperature"].to_list() == [68.0, 23.0, None]
+    assert unmasked["temperature"].to_list() == [68.0, 23.0, None]
+    with pytest.raises(ValueError, match="temperature_unit"):
+        polarsfit.read_recordmesgs(str(path), temperature_unit="K")
+
+
+def test_invalid_sentinels_become_null(tmp_path):
+    """0xFFFF in a uint16 field is missing unless masking is turned off."""
+    builder = FitBuilder().define(
+        0, RECORD, [(253, UINT32), (3, UINT8), (7, UINT16)]
+    )
+    builder.data(0, [fit_time(START), 0xFF, 0xFFFF])
+    builder.data(0, [fit_time(START + 1), 121, 0xFFFF])
+    path = builder.write(tmp_path / "invalid.fit")
+
+    df = polarsfit.read_data(str(path), "record", apply_default_mapping=False)
+
+    assert df["field_3"].to_list() == [None, 121]
+    # A field holding only sentinels has no values, so yields no column
+    assert "field_7" not in df.columns
+
+    raw = polarsfit.read_data(
+        str(path), "record", apply_default_mapping=False, mask_invalid=False
+    )
+    assert raw["field_3"].to_list() == [255, 121]
+    assert raw["field_7"].to_list() == [65535, 65535]
+
+
+def test_mixed_base_types_promote_to_one_dtype(tmp_path):
+    """A field redefined with another base type still yields one column."""
+    builder = (
+        FitBuilder()
+        .define(0, RECORD, [(253, UINT32), (3, UINT8), (7, UINT16)])
+        .define(1, RECORD, [(253, UINT32), (3, FLOAT32), (7, SINT32)])
+        .data(0, [fit_time(START), 120, 300])
+        .data(1, [fit_time(START + 1), 121.5, -5])
+    )
+    path = builder.write(tmp_path / "mixed.fit")
+
+    df = polarsfit.read_data(str(path), "record", apply_default_mapping=False)
+
+    assert df["field_3"].dtype == pl.Float64
+    assert df["field_3"].to_list() == [120.0, 121.5]
+    # Unsigned and signed integers widen to a signed type holding both
+    assert df["field_7"].dtype == pl.Int64
+    assert df["field_7"].to_list() == [300, -5]
+
+
+def test_compressed_speed_distance_expands_to_speed_and_distance(tmp_path):
+    """The packed field decodes to speed and to distance summed over rollovers."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (8, BYTE, 3)])
+    for i in range(60):
+        # 12 bits of speed in cm/s, then 12 bits of distance in 1/16 m
+        packed = 500 | (i * 80 % 4096) << 12
+        builder.data(0, [fit_time(START + i), list(packed.to_bytes(3, "little"))])
+    path = builder.write(tmp_path / "compressed.fit")
+
+    df = polarsfit.read_recordmesgs(str(path))
+
+    assert df["speed"].to_list() == [5.0] * 60
+    # Distance passes 4096 / 16 = 256 m and keeps counting
+    assert df["distance"].to_list() == [5.0 * i for i in range(60)]
+
+
+def test_event_data_is_read_as_the_subfield_of_its_event(tmp_path):
+    """The event type selects the name and decoding of the data field."""
+    gears = int.from_bytes(bytes([5, 28, 2, 50]), "little")
+    builder = (
+        FitBuilder()
+        .define(0, EVENT, [(253, UINT32), (0, ENUM), (1, ENUM), (3, UINT32)])
+        # timer start, rear gear change, battery voltage in mV
+        .data(0, [fit_time(START), 0, 0, 0])
+        .data(0, [fit_time(START + 1), 43, 3, gears])
+        .data(0, [fit_time(START + 2), 11, 3, 3700])
+    )
+    path = builder.write(tmp_path / "events.fit")
+
+    df = polarsfit.read_data(str(path), "event")
+
+    assert df["event"].to_list() == ["timer", "rear_gear_change", "battery"]
+    assert df["data"].to_list() == [0, gears, 3700]
+    assert df["timer_trigger"].to_list() == ["manual", None, None]
+    assert df["battery_level"].to_list() == [None, None, 3.7]
+    # Gear change data packs the gear numbers and teeth counts
+    assert df["gear_change_data"].to_list() == [None, gears, None]
+    gear_columns = ["rear_gear_num", "rear_gear", "front_gear_num", "front_gear"]
+    assert df.select(gear_columns).row(1) == (5, 28, 2, 50)
+
+
+def test_monitoring_timestamps_and_totals_are_reconstructed(tmp_path):
+    """16-bit timestamps and counters count on from the last full values."""
+    builder = (
+        FitBuilder()
+        .define(0, MONITORING_INFO, [(253, UINT32)])
+        .data(0, [fit_time(START)])
+        .define(1, MONITORING, [(253, UINT32), (5, ENUM), (3, UINT32)])
+        # Walking, 500 steps so far today
+        .data(1, [fit_time(START + 60), 6, 1000])
+        .define(2, MONITORING, [(26, UINT16), (24, BYTE), (9, UINT16)])
+    )
+    timestamp = fit_time(START + 60)
+    for i in range(1, 6):
+        # Far enough apart that both 16-bit values roll over
+        timestamp += 20_000
+        walking = 6 | 3 << 5
+        builder.data(2, [timestamp & 0xFFFF, walking, (1000 + 30_000 * i) & 0xFFFF])
+    path = builder.write(tmp_path / "monitoring.fit")
+
+    df = polarsfit.read_data(str(path), "monitoring")
+
+    times = df["timestamp"]
+    assert times.is_sorted()
+    assert times.diff().drop_nulls().dt.total_seconds().to_list() == [20_000] * 5
+    assert times[0] == datetime.fromtimestamp(START + 60, tz=timezone.utc)
+    assert df["activity_type"].to_list() == ["walking"] * 6
+    assert df["intensity"].to_list() == [None] + [3] * 5
+    assert df["cycles"].to_list() == [500.0 + 15_000 * i for i in range(6)]
+
+
+def test_compressed_timestamp_headers_count_on_from_the_last_timestamp(tmp_path):
+    """Offsets in the header roll over every 32 seconds onto the last timestamp."""
+    builder = (
+        FitBuilder()
+        .define(0, RECORD, [(253, UINT32), (3, UINT8)])
+        .data(0, [fit_time(START), 100])
+        .define(1, RECORD, [(3, UINT8)])
+    )
+    seconds = [START]
+    for i in range(1, 40):
+        # Records every 3 seconds pass several 5-bit rollovers
+        seconds.append(START + 3 * i)
+        builder.compressed_data(1, fit_time(seconds[-1]), [100 + i])
+    path = builder.write(tmp_path / "compressed_timestamps.fit")
+
+    df = polarsfit.read_recordmesgs(str(path))
+
+    times = df["timestamp"]
+    assert times.diff().drop_nulls().dt.total_seconds().min() > 0
+    assert times.to_list() == [
+        datetime.fromtimestamp(s, tz=timezone.utc) for s in seconds
+    ]
+    assert df["heart_rate"].to_list() == list(range(100, 140))
+
+
+def test_rolling_cycle_counts_accumulate_onto_the_last_total(tmp_path):
+    """8-bit cycle counts wrap every 256 cycles; totals keep counting."""
+    builder = (
+        FitBuilder()
+        .define(0, RECORD, [(253, UINT32), (19, UINT32)])
+        .data(0, [fit_time(START), 1000])
+        .define(1, RECORD, [(253, UINT32), (18, UINT8)])
+    )
+    for i in range(1, 20):
+        builder.data(1, [fit_time(START + i), (1000 + 90 * i) & 0xFF])
+    path = builder.write(tmp_path / "cycles.fit")
+
+    df = polarsfit.read_recordmesgs(str(path))
+
+    totals = df["total_cycles"]
+    assert totals.is_sorted()
+    assert totals.to_list() == [1000 + 90 * i for i in range(20)]
+
+
+def test_respiration_rate_is_scaled_with_status_codes_as_null(tmp_path):
+    """Breaths per minute are scaled; sentinels and negative status codes are null."""
+    builder = FitBuilder().define(0, RESPIRATION_RATE, [(253, UINT32), (0, SINT16)])
+    # Breaths * 100 per minute; -300 invalid, -200 large motion, -100 off wrist
+    rates = [1450, 0x7FFF, 1625, -300, -200, -100, 2000]
+    for i, rate in enumerate(rates):
+        builder.data(0, [fit_time(START + 60 * i), rate])
+    path = builder.write(tmp_path / "respiration.fit")
+
+    df = polarsfit.read_data(str(path), "respiration_rate")
+
+    assert "respirationrate" in polarsfit.get_message_types(str(path))
+    assert df.schema["timestamp"] == pl.Datetime("ms", "UTC")
+    rate = df["respiration_rate"]
+    assert rate.to_list() == [14.5, None, 16.25, None, None, None, 20.0]
+    assert rate.drop_nulls().is_between(4, 60).all()
+    raw = polarsfit.read_data(str(path), "respiration_rate", mask_invalid=False)
+    assert raw["respiration_rate"].to_list()[3:6] == [-3.0, -2.0, -1.0]
+
+
+def test_spo2_readings_are_percentages_with_sentinels_as_null(tmp_path):
+    """Pulse oximeter readings decode by either name, with 0xFF as null."""
+    fields = [(253, UINT32), (0, UINT8), (1, UINT8), (2, ENUM)]
+    builder = FitBuilder().define(0, SPO2_DATA, fields)
+    # Reading in %, confidence and mode: 0 off wrist, 1 spot check, 3 periodic
+    readings = [(97, 90, 3), (0xFF, 0xFF, 0), (88, 60, 3), (72, 30, 1)]
+    for i, reading in enumerate(readings):
+        builder.data(0, [fit_time(START + 60 * i), *reading])
+    path = builder.write(tmp_path / "spo2.fit")
+
+    df = polarsfit.read_data(str(path), "spo2")
+
+    assert "spo2data" in polarsfit.get_message_types(str(path))
+    assert df.schema["timestamp"] == pl.Datetime("ms", "UTC")
+    spo2 = df["reading_spo2"]
+    assert spo2.to_list() == [97, None, 88, 72]
+    assert spo2.drop_nulls().is_between(70, 100).all()
+    assert df["mode"].cast(pl.String).to_list() == [
+        "periodic",
+        "off_wrist",
+        "periodic",
+        "spot_check",
+    ]
+    assert_frame_equal(
+        polarsfit.read_data(str(path), "spo2_data"), df, categorical_as_str=True
+    )
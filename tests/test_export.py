@@ -0,0 +1,166 @@
+#!/usr/bin/env python3
+"""Tests for exporting records to other track formats."""
+
+import json
+import xml.etree.ElementTree as ET
+
+import pytest
+
+import polarsfit
+from tests.fit_builder import (
+    ENUM,
+    LAP,
+    RECORD,
+    SESSION,
+    SINT32,
+    UINT8,
+    UINT16,
+    UINT32,
+    FitBuilder,
+    fit_time,
+)
+
+START = 1_700_000_000
+
+GPX = "{http://www.topografix.com/GPX/1/1}"
+GPXTPX = "{http://www.garmin.com/xmlschemas/TrackPointExtension/v1}"
+TCX = "{http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2}"
+
+# 1 degree in semicircles
+DEGREE = 2**31 / 180
+
+
+def build_ride(records: int = 5) -> FitBuilder:
+    """Build records with a position, altitude, heart rate, cadence and power."""
+    builder = FitBuilder().define(
+        0,
+        RECORD,
+        [
+            (253, UINT32),
+            (0, SINT32),
+            (1, SINT32),
+            (2, UINT16),
+            (3, UINT8),
+            (4, UINT8),
+            (7, UINT16),
+        ],
+    )
+    for i in range(records):
+        builder.data(
+            0,
+            [
+                fit_time(START + i),
+                round((47.0 + i / 1000) * DEGREE),
+                round(8.5 * DEGREE),
+                (500 + 400 + i) * 5,
+                140 + i,
+                85,
+                200 + i,
+            ],
+        )
+    return builder
+
+
+def test_records_to_gpx_writes_a_trackpoint_per_position(tmp_path):
+    """Records without a GPS fix are skipped, the rest become trackpoints."""
+    builder = build_ride(records=5)
+    # No GPS fix yet: positions are the invalid sentinel
+    builder.data(0, [fit_time(START + 5), 0x7FFFFFFF, 0x7FFFFFFF, 0xFFFF, 150, 90, 210])
+    path = builder.write(tmp_path / "ride.fit")
+    out = tmp_path / "ride.gpx"
+
+    polarsfit.records_to_gpx(str(path), str(out))
+
+    root = ET.parse(out).getroot()
+    assert root.tag == f"{GPX}gpx"
+    assert root.get("version") == "1.1"
+    points = root.findall(f"{GPX}trk/{GPX}trkseg/{GPX}trkpt")
+    assert len(points) == 5
+
+    first = points[0]
+    assert float(first.get("lat")) == 47.0
+    assert float(first.get("lon")) == 8.5
+    assert float(first.findtext(f"{GPX}ele")) == 400.0
+    assert first.findtext(f"{GPX}time") == "2023-11-14T22:13:20Z"
+    extensions = first.find(f"{GPX}extensions")
+    assert extensions.findtext(f"{GPX}power") == "200"
+    trackpoint = extensions.find(f"{GPXTPX}TrackPointExtension")
+    assert trackpoint.findtext(f"{GPXTPX}hr") == "140"
+    assert trackpoint.findtext(f"{GPXTPX}cad") == "85"
+
+
+def test_records_to_geojson_writes_a_lon_lat_linestring(tmp_path):
+    """Every record with a GPS fix is a [lon, lat] point of the LineString."""
+    builder = build_ride(records=5)
+    builder.data(0, [fit_time(START + 5), 0x7FFFFFFF, 0x7FFFFFFF, 0xFFFF, 150, 90, 210])
+    builder.define(1, SESSION, [(253, UINT32), (5, ENUM)])
+    builder.data(1, [fit_time(START + 5), 2])
+    path = builder.write(tmp_path / "ride.fit")
+    out = tmp_path / "ride.geojson"
+
+    polarsfit.records_to_geojson(str(path), str(out))
+
+    feature = json.loads(out.read_text())
+    assert feature["type"] == "Feature"
+    assert feature["geometry"]["type"] == "LineString"
+    coordinates = feature["geometry"]["coordinates"]
+    records = polarsfit.read_recordmesgs(str(path))
+    assert len(coordinates) == records["position_lat"].count() == 5
+    assert coordinates[0] == pytest.approx([8.5, 47.0])
+    assert feature["properties"]["sport"] == "cycling"
+    assert feature["properties"]["start_time"] == "2023-11-14T22:13:20Z"
+
+
+def test_activity_to_tcx_wraps_trackpoints_in_laps(tmp_path):
+    """Each lap message is a Lap holding the trackpoints recorded in it."""
+    builder = build_ride(records=6)
+    # Two three second laps; times are in ms and distances in cm
+    builder.define(1, LAP, [(253, UINT32), (2, UINT32), (8, UINT32), (9, UINT32)])
+    builder.data(1, [fit_time(START + 3), fit_time(START), 3000, 1500])
+    builder.data(1, [fit_time(START + 6), fit_time(START + 3), 3000, 1600])
+    builder.define(2, SESSION, [(253, UINT32), (5, ENUM)])
+    builder.data(2, [fit_time(START + 6), 1])
+    path = builder.write(tmp_path / "run.fit")
+    out = tmp_path / "run.tcx"
+
+    polarsfit.activity_to_tcx(str(path), str(out))
+
+    root = ET.parse(out).getroot()
+    assert root.tag == f"{TCX}TrainingCenterDatabase"
+    activities = root.findall(f"{TCX}Activities/{TCX}Activity")
+    assert len(activities) == 1
+    activity = activities[0]
+    assert activity.get("Sport") == "Running"
+    assert activity.findtext(f"{TCX}Id") == "2023-11-14T22:13:20Z"
+
+    laps = activity.findall(f"{TCX}Lap")
+    assert len(laps) == 2
+    assert [lap.get("StartTime") for lap in laps] == [
+        "2023-11-14T22:13:20Z",
+        "2023-11-14T22:13:23Z",
+    ]
+    first = laps[0]
+    # Required lap elements, in schema order
+    assert [child.tag.removeprefix(TCX) for child in first][:3] == [
+        "TotalTimeSeconds",
+        "DistanceMeters",
+        "Calories",
+    ]
+    assert float(first.findtext(f"{TCX}TotalTimeSeconds")) == 3.0
+    assert float(first.findtext(f"{TCX}DistanceMeters")) == 15.0
+    assert first.findtext(f"{TCX}Intensity") == "Active"
+    assert first.findtext(f"{TCX}TriggerMethod") == "Manual"
+
+    tracks = [lap.findall(f"{TCX}Track/{TCX}Trackpoint") for lap in laps]
+    assert [len(points) for points in tracks] == [3, 3]
+    point = tracks[0][0]
+    assert [child.tag.removeprefix(TCX) for child in point] == [
+        "Time",
+        "Position",
+        "AltitudeMeters",
+        "HeartRateBpm",
+        "Cadence",
+    ]
+    assert float(point.findtext(f"{TCX}Position/{TCX}LatitudeDegrees")) == 47.0
+    assert point.findtext(f"{TCX}HeartRateBpm/{TCX}Value") == "140"
+    assert point.findtext(f"{TCX}Cadence") == "85"
@@ -0,0 +1,295 @@
+#!/usr/bin/env python3
+"""Tests for the expression plugins."""
+
+import math
+from datetime import datetime, timedelta, timezone
+
+import polars as pl
+import pytest
+
+import polarsfit
+
+
+def test_semicircle_to_degrees():
+    """Semicircles convert to degrees and the invalid sentinel to null."""
+    df = pl.DataFrame(
+        {"lat": pl.Series([2**30, -(2**30), 0, 0x7FFFFFFF, None], dtype=pl.Int32)}
+    )
+
+    out = df.select(polarsfit.semicircle_to_degrees("lat"))
+
+    assert out.schema["lat"] == pl.Float64
+    assert out["lat"].to_list() == [90.0, -90.0, 0.0, None, None]
+    lazy = df.lazy().select(polarsfit.semicircle_to_degrees(pl.col("lat")))
+    assert lazy.collect()["lat"].to_list() == out["lat"].to_list()
+
+
+def test_haversine_cumulative_along_equator():
+    """A thousandth of a degree of longitude on the equator is about 111 m."""
+    df = pl.DataFrame(
+        {
+            "lat": [0.0, None, 0.0, 0.0],
+            "lon": [0.0, 5.0, 0.001, 0.002],
+        }
+    )
+
+    out = df.select(polarsfit.haversine_cumulative("lat", "lon"))["lat"]
+
+    assert out.dtype == pl.Float64
+    assert out[0] == 0.0
+    # The row without a latitude carries the total forward
+    assert out[1] == 0.0
+    assert abs(out[2] - 111.2) < 1.0
+    assert abs(out[3] - 222.4) < 2.0
+
+
+def test_grade_on_constant_climb():
+    """A steady 5% climb has a 5% grade once a full window is covered."""
+    distance = [float(d) for d in range(0, 100, 2)]
+    df = pl.DataFrame(
+        {"distance": distance, "altitude": [200.0 + 0.05 * d for d in distance]}
+    )
+
+    out = df.select(polarsfit.grade("distance", "altitude", window=10.0))["distance"]
+
+    assert out.dtype == pl.Float64
+    assert out[:5].is_null().all()
+    assert all(abs(g - 5.0) < 1e-9 for g in out[5:])
+
+
+def test_normalized_power():
+    """Steady power normalizes to itself; surges push NP above the mean."""
+    steady = pl.DataFrame({"power": [200] * 120})
+    # Alternate one minute easy, one minute hard
+    surges = pl.DataFrame(
+        {"power": [100 if (i // 60) % 2 == 0 else 300 for i in range(600)]}
+    )
+
+    np_steady = steady.select(polarsfit.normalized_power("power")).item()
+    np_surges = surges.select(polarsfit.normalized_power("power")).item()
+
+    assert abs(np_steady - 200.0) < 1e-9
+    assert np_surges > surges["power"].mean()
+
+
+def test_elevation_gain_ignores_jitter():
+    """Fluctuations below the threshold don't count towards the gain."""
+    sawtooth = pl.DataFrame({"altitude": [0.0, 5.0, 0.0, 5.0, 0.0, 5.0]})
+    jitter = pl.DataFrame({"altitude": [0.0, 2.0, 0.0, 2.0, 0.0, 10.0]})
+
+    gain = polarsfit.elevation_gain("altitude", threshold=3.0)
+
+    assert sawtooth.select(gain).item() == 15.0
+    assert jitter.select(gain).item() == 10.0
+    assert jitter.select(polarsfit.elevation_gain("altitude", 1.0)).item() == 14.0
+    loss = polarsfit.elevation_gain("altitude", threshold=3.0, descent=True)
+    assert sawtooth.select(loss).item() == 10.0
+    assert jitter.select(loss).item() == 0.0
+
+
+def test_hr_zone_buckets_a_ramp():
+    """Each value lands in the zone of the highest boundary it reaches."""
+    df = pl.DataFrame({"hr": [90, 100, 119, 120, 150, 185, None]})
+
+    out = df.select(polarsfit.hr_zone("hr", [100, 120, 140, 160, 180]))["hr"]
+
+    assert out.dtype == pl.UInt8
+    assert out.to_list() == [0, 1, 1, 2, 3, 5, None]
+
+
+def test_pace_from_speed():
+    """3.333 m/s is a 5:00 min/km pace; standing still has no pace."""
+    df = pl.DataFrame({"speed": [3.333, 0.0, None]})
+
+    per_km = df.select(polarsfit.pace("speed"))["speed"]
+    per_mile = df.select(polarsfit.pace("speed", unit="min_per_mile"))["speed"]
+
+    assert abs(per_km[0] - 5.0) < 0.01
+    assert per_km[1:].to_list() == [None, None]
+    assert abs(per_mile[0] - 5.0 * 1.609344) < 0.01
+
+
+def test_rolling_smooth_methods():
+    """Each smoothing method on a short series with a spike and a gap."""
+    df = pl.DataFrame({"x": [1.0, 2.0, 9.0, None, 4.0, 5.0]})
+
+    def smooth(**kwargs):
+        return df.select(polarsfit.rolling_smooth("x", 3, **kwargs))["x"].to_list()
+
+    assert smooth(method="mean") == [1.5, 4.0, 5.5, 6.5, 4.5, 4.5]
+    assert smooth(method="median") == [1.5, 2.0, 5.5, 6.5, 4.5, 4.5]
+    assert smooth(method="ewma", alpha=0.5) == [1.0, 1.5, 5.25, 5.25, 4.625, 4.8125]
+
+
+def test_moving_time_skips_stops_and_gaps():
+    """Stops and pauses count towards elapsed time but not moving time."""
+    start = datetime(2024, 5, 1, tzinfo=timezone.utc)
+    seconds = [0, 1, 2, 3, 4, 5, 60, 61]
+    df = pl.DataFrame(
+        {
+            "timestamp": [start + timedelta(seconds=s) for s in seconds],
+            "speed": [3.0, 3.0, 0.0, 0.2, 3.0, 3.0, 3.0, 3.0],
+        }
+    )
+
+    moving = df.select(polarsfit.is_moving("speed"))["speed"]
+    total = df.select(polarsfit.moving_time("timestamp", "speed")).item()
+
+    assert moving.to_list() == [True, True, False, False, True, True, True, True]
+    assert total == 4.0
+    assert total < seconds[-1] - seconds[0]
+
+
+def test_simplify_track_keeps_the_ends_of_lines_and_the_corners_of_zigzags():
+    """Points on a straight line are dropped, corners off the line are kept."""
+    line = pl.DataFrame(
+        {
+            "lat": [47.0, 47.001, None, 47.002, 47.003],
+            "lon": [8.0, 8.0, None, 8.0, 8.0],
+        }
+    )
+    # Zigzag with a corner every 0.001 degrees and midpoints in between
+    zigzag = pl.DataFrame(
+        {
+            "lat": [0.0, 0.0005, 0.001, 0.0005, 0.0, 0.0005, 0.001],
+            "lon": [0.0, 0.0005, 0.001, 0.0015, 0.002, 0.0025, 0.003],
+        }
+    )
+
+    straight = line.select(polarsfit.simplify_track("lat", "lon", 1.0))["lat"]
+    corners = zigzag.select(polarsfit.simplify_track("lat", "lon", 5.0))["lat"]
+
+    assert straight.to_list() == [True, False, False, False, True]
+    assert corners.to_list() == [True, False, True, False, True, False, True]
+
+
+def test_wprime_balance_depletes_above_cp_and_recovers_below():
+    """A minute above CP uses up W', a minute below recovers part of it."""
+    start = datetime(2024, 5, 1, tzinfo=timezone.utc)
+    df = pl.DataFrame(
+        {
+            "timestamp": [start + timedelta(seconds=s) for s in range(120)],
+            "power": [350] * 60 + [150] * 60,
+        }
+    )
+
+    wbal = df.select(polarsfit.wprime_balance("timestamp", "power", 250, 20000))[
+        "power"
+    ]
+
+    assert wbal.dtype == pl.Float64
+    assert wbal[0] == 20000.0
+    # 59 seconds at 100 W above CP
+    assert wbal[59] == 20000.0 - 59 * 100
+    assert wbal[:60].diff().drop_nulls().max() < 0
+    assert wbal[60:].diff().drop_nulls().min() > 0
+    # Recovery with a time constant of W' / (CP - P) = 200 s
+    assert abs(wbal[119] - (20000.0 - 5900 * math.exp(-60 / 200))) < 1e-6
+
+
+def test_interpolate_gps_fills_gaps_between_fixes():
+    """Nulls between two fixes lie evenly between them, other nulls stay."""
+    df = pl.DataFrame(
+        {
+            "lat": [None, 47.0, None, None, None, 47.4, None, None, 47.9],
+            "lon": [None, 8.0, None, None, None, 8.4, None, None, 8.1],
+        }
+    )
+
+    out = df.select(
+        polarsfit.interpolate_gps("lat"), polarsfit.interpolate_gps("lon", max_gap=2)
+    )
+
+    lat = out["lat"]
+    assert lat[0] is None
+    assert [round(value, 9) for value in lat[1:6]] == [47.0, 47.1, 47.2, 47.3, 47.4]
+    assert all(47.4 < value < 47.9 for value in lat[6:8])
+    # The three missing longitudes are more than max_gap
+    assert out["lon"][2:5].is_null().all()
+    assert [round(value, 9) for value in out["lon"][5:]] == [8.4, 8.3, 8.2, 8.1]
+
+
+def test_bearing_points_along_the_direction_of_travel():
+    """North is 0 degrees and east 90, the first point and standstills null."""
+    df = pl.DataFrame(
+        {
+            "lat": [0.0, 0.001, 0.001, 0.001, 0.0, 10.0, 10.0],
+            "lon": [0.0, 0.0, 0.001, 0.001, 0.001, 179.999, -179.999],
+        }
+    )
+
+    out = df.select(polarsfit.bearing("lat", "lon"))["lat"]
+
+    assert out.dtype == pl.Float64
+    assert out[0] is None
+    assert abs(out[1] - 0.0) < 1e-6
+    assert abs(out[2] - 90.0) < 1e-6
+    assert out[3] is None
+    assert abs(out[4] - 180.0) < 1e-6
+    # Eastwards across the antimeridian
+    assert abs(out[6] - 90.0) < 1e-3
+
+
+def test_vertical_speed_of_a_steady_climb_and_descent():
+    """5 m/s up a 5% grade is 900 m/h, down it -900 m/h, with no spike at a pause."""
+    start = datetime(2024, 5, 1, tzinfo=timezone.utc)
+    seconds = list(range(100)) + list(range(400, 500))
+    altitude = [100.0 + 0.25 * s for s in range(100)]
+    altitude += [altitude[-1] - 0.25 * (s - 400) for s in range(400, 500)]
+    df = pl.DataFrame(
+        {
+            "altitude": altitude,
+            "timestamp": [start + timedelta(seconds=s) for s in seconds],
+        }
+    )
+
+    vam = df.select(polarsfit.vertical_speed("altitude", "timestamp"))["altitude"]
+
+    assert vam.dtype == pl.Float64
+    assert vam[:30].is_null().all()
+    assert all(abs(v - 900.0) < 1e-9 for v in vam[30:100])
+    # The pause leaves the first window after it empty
+    assert vam[100:130].is_null().all()
+    assert all(abs(v + 900.0) < 1e-9 for v in vam[130:])
+
+
+def test_find_peaks_counts_the_periods_of_a_sinusoid():
+    """Ten periods give ten peaks; the ripple on top isn't prominent enough."""
+    ripple = [0.05 if i % 3 == 0 else 0.0 for i in range(200)]
+    df = pl.DataFrame(
+        {"x": [math.sin(i * 2 * math.pi / 20) + r for i, r in enumerate(ripple)]}
+    )
+
+    peaks = df.select(polarsfit.find_peaks("x", min_prominence=0.5))["x"]
+    all_maxima = df.select(polarsfit.find_peaks("x"))["x"]
+    spaced = df.select(polarsfit.find_peaks("x", min_distance=40))["x"]
+
+    assert peaks.dtype == pl.Boolean
+    assert peaks.sum() == 10
+    assert all_maxima.sum() > 10
+    positions = spaced.arg_true()
+    assert 0 < len(positions) < 10
+    assert positions.diff().drop_nulls().min() >= 40
+
+
+def test_grade_adjusted_pace_follows_the_minetti_curve():
+    """Climbs adjust to a faster pace, gentle descents to a slower one."""
+
+    def minetti(i):
+        return 155.4 * i**5 - 30.4 * i**4 - 43.3 * i**3 + 46.3 * i**2 + 19.5 * i + 3.6
+
+    grades = [0.0, 10.0, -10.0, -20.0, -30.0, 80.0]
+    df = pl.DataFrame({"speed": [3.0] * len(grades) + [0.0], "grade": grades + [5.0]})
+
+    gap = df.select(polarsfit.grade_adjusted_pace("speed", "grade"))["speed"]
+    raw = df.select(polarsfit.pace("speed"))["speed"]
+
+    assert gap[0] == pytest.approx(raw[0])
+    assert gap[1] < raw[1]
+    assert gap[2] > raw[2]
+    for row, grade in enumerate(grades):
+        cost = minetti(max(-0.45, min(0.45, grade / 100)))
+        assert gap[row] == pytest.approx(raw[row] * 3.6 / cost)
+    # Descending gets cheaper down to about -20%, then costlier again
+    assert gap[3] > gap[2] and gap[3] > gap[4]
+    assert gap[6] is None
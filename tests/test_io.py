@@ -0,0 +1,1543 @@
+#!/usr/bin/env python3
+"""Tests for the file and bytes entry points."""
+
+import gzip
+import io
+import os
+import subprocess
+import sys
+import threading
+import time
+from datetime import datetime, timedelta, timezone
+
+import polars as pl
+import pytest
+from polars.testing import assert_frame_equal
+
+import polarsfit
+from tests.fit_builder import (
+    ACTIVITY,
+    COURSE_POINT,
+    DEVICE_INFO,
+    ENUM,
+    EVENT,
+    FILE_ID,
+    GPS_METADATA,
+    LAP,
+    LENGTH,
+    RECORD,
+    SESSION,
+    SINT8,
+    SINT32,
+    STRING,
+    UINT8,
+    UINT16,
+    UINT32,
+    UINT32Z,
+    WORKOUT_STEP,
+    FitBuilder,
+    fit_time,
+)
+
+START = 1_700_000_000
+
+
+def build_activity(records: int = 10, laps: int = 2) -> FitBuilder:
+    """Build a small activity with a file_id, records, laps and a session."""
+    builder = (
+        FitBuilder()
+        .define(0, FILE_ID, [(0, ENUM), (1, UINT16), (4, UINT32)])
+        .data(0, [4, 1, fit_time(START)])
+        .define(1, RECORD, [(253, UINT32), (3, UINT8), (6, UINT16)])
+    )
+    for i in range(records):
+        builder.data(1, [fit_time(START + i), 120 + i, 3000 + i])
+    builder.define(2, LAP, [(253, UINT32), (2, UINT32)])
+    for i in range(laps):
+        builder.data(2, [fit_time(START + records), fit_time(START + i)])
+    builder.define(3, SESSION, [(253, UINT32), (5, ENUM)])
+    builder.data(3, [fit_time(START + records), 2])
+    return builder
+
+
+def test_bytes_and_path_give_identical_frames(tmp_path):
+    """Reading the bytes of a file matches reading it from disk."""
+    path = build_activity().write(tmp_path / "activity.fit")
+    data = path.read_bytes()
+
+    assert_frame_equal(
+        polarsfit.read_recordmesgs_from_bytes(data),
+        polarsfit.read_recordmesgs(str(path)),
+    )
+    for message_type in ["record", "lap", "session"]:
+        assert_frame_equal(
+            polarsfit.read_data_from_bytes(data, message_type),
+            polarsfit.read_data(str(path), message_type),
+        )
+
+    # Other bytes-like objects are accepted too
+    assert_frame_equal(
+        polarsfit.read_data_from_bytes(memoryview(data), "record"),
+        polarsfit.read_data(str(path), "record"),
+    )
+
+
+def test_bytes_readers_take_the_options_of_the_path_readers(tmp_path):
+    """Options work the same on bytes and file objects as on paths."""
+    path = build_activity().write(tmp_path / "activity.fit")
+    data = path.read_bytes()
+    options = {
+        "add_index": True,
+        "decode_enums": False,
+        "temperature_unit": "F",
+        "start_time": START + 2,
+        "limit": 5,
+    }
+
+    for message_type in ["record", "lap"]:
+        expected = polarsfit.read_data(str(path), message_type, **options)
+        assert_frame_equal(
+            polarsfit.read_data_from_bytes(data, message_type, **options), expected
+        )
+        assert_frame_equal(
+            polarsfit.read_data_from_fileobj(io.BytesIO(data), message_type, **options),
+            expected,
+        )
+    record_options = {**options, "local_time": True, "double_running_cadence": True}
+    assert_frame_equal(
+        polarsfit.read_recordmesgs_from_bytes(data, **record_options),
+        polarsfit.read_recordmesgs(str(path), **record_options),
+    )
+    # Record options are only taken by the record readers
+    with pytest.raises(TypeError, match="local_time"):
+        polarsfit.read_data_from_bytes(data, "record", local_time=True)
+
+
+def test_file_objects_read_like_paths(tmp_path):
+    """Anything with a read() returning the file's bytes can be read from."""
+    path = build_activity().write(tmp_path / "activity.fit")
+
+    df = polarsfit.read_data_from_fileobj(io.BytesIO(path.read_bytes()), "record")
+
+    assert_frame_equal(df, polarsfit.read_data(str(path), "record"))
+    with open(path, encoding="latin-1") as text, pytest.raises(TypeError, match="binary mode"):
+        polarsfit.read_data_from_fileobj(text, "record")
+
+
+def test_read_all_messages_matches_read_data(tmp_path):
+    """One pass yields a frame per type with the same rows as read_data."""
+    path = build_activity(records=10, laps=3).write(tmp_path / "activity.fit")
+
+    frames = polarsfit.read_all_messages(str(path))
+
+    assert {"record", "lap", "session"} <= frames.keys()
+    assert set(frames) == set(polarsfit.get_message_types(str(path)))
+    for message_type, df in frames.items():
+        assert_frame_equal(df, polarsfit.read_data(str(path), message_type))
+    assert frames["record"].height == 10
+    assert frames["lap"].height == 3
+
+
+def test_read_data_with_a_list_reads_each_type(tmp_path):
+    """A list of message types gives a frame per type, as read one at a time."""
+    path = build_activity().write(tmp_path / "activity.fit")
+
+    frames = polarsfit.read_data(str(path), ["record", "lap"])
+
+    assert list(frames) == ["record", "lap"]
+    for message_type, df in frames.items():
+        assert_frame_equal(df, polarsfit.read_data(str(path), message_type))
+
+
+def test_message_counts(tmp_path):
+    """Counts cover every type and match the number of rows read."""
+    path = build_activity(records=7, laps=2).write(tmp_path / "activity.fit")
+
+    counts = polarsfit.get_message_counts(str(path))
+
+    assert counts["record"] == polarsfit.read_recordmesgs(str(path)).height
+    assert counts["record"] == 7
+    assert counts["lap"] == 2
+    assert counts["session"] == 1
+    assert sorted(counts) == polarsfit.get_message_types(str(path))
+
+
+
+def test_message_type_lookup(tmp_path):
+    """Message types are matched case-insensitively; unknown ones match nothing."""
+    path = build_activity(records=5).write(tmp_path / "activity.fit")
+
+    assert_frame_equal(
+        polarsfit.read_data(str(path), "Record"),
+        polarsfit.read_data(str(path), "record"),
+    )
+    assert polarsfit.read_data(str(path), "not_a_message").is_empty()
+    assert polarsfit.scan_data(str(path), "RECORD").collect().height == 5
+
+
+def test_underscored_message_type_names_match(tmp_path):
+    """Profile names such as file_id match with and without underscores."""
+    path = build_activity(records=5).write(tmp_path / "activity.fit")
+
+    df = polarsfit.read_data(str(path), "file_id")
+
+    assert df.height == 1
+    assert_frame_equal(df, polarsfit.read_data(str(path), "fileid"))
+    assert polarsfit.get_message_counts(str(path))["fileid"] == 1
+
+
+def test_unknown_message_types_are_named_by_number(tmp_path):
+    """Messages outside the profile are listed and read as message_<n>."""
+    builder = build_activity(records=3)
+    builder.define(5, 0xFF01, [(0, UINT16), (1, UINT8)])
+    builder.data(5, [1234, 7]).data(5, [1235, 8])
+    path = builder.write(tmp_path / "vendor.fit")
+
+    assert "message_65281" in polarsfit.get_message_types(str(path))
+    assert polarsfit.get_message_counts(str(path))["message_65281"] == 2
+    df = polarsfit.read_data(str(path), "message_65281")
+    assert df["field_0"].to_list() == [1234, 1235]
+    assert df["field_1"].to_list() == [7, 8]
+    assert_frame_equal(polarsfit.read_all_messages(str(path))["message_65281"], df)
+
+def test_missing_file_raises_file_not_found(tmp_path):
+    """A missing path raises FileNotFoundError naming the path."""
+    missing = tmp_path / "missing.fit"
+
+    with pytest.raises(FileNotFoundError, match="missing.fit"):
+        polarsfit.read_recordmesgs(str(missing))
+    with pytest.raises(FileNotFoundError, match="missing.fit"):
+        polarsfit.read_data(str(missing), "lap")
+    with pytest.raises(FileNotFoundError, match="missing.fit"):
+        polarsfit.get_message_types(str(missing))
+    with pytest.raises(FileNotFoundError):
+        polarsfit.read_data(str(tmp_path), "record")
+
+
+def test_invalid_data_raises_value_error(tmp_path):
+    """Files that are not valid FIT data raise ValueError."""
+    not_fit = tmp_path / "notes.txt"
+    not_fit.write_text("definitely not a FIT file")
+    truncated = tmp_path / "truncated.fit"
+    truncated.write_bytes(build_activity().to_bytes()[:40])
+
+    for path in [not_fit, truncated]:
+        with pytest.raises(ValueError, match="Failed to parse FIT data"):
+            polarsfit.read_data(str(path), "record", truncated_ok=False)
+        with pytest.raises(ValueError, match="Failed to parse FIT data"):
+            polarsfit.get_message_types(str(path))
+    with pytest.raises(ValueError):
+        polarsfit.read_data_from_bytes(b"", "record")
+
+
+def test_truncated_files_keep_the_complete_messages(tmp_path):
+    """A file cut off mid-message reads up to the last complete message."""
+    path = build_activity(records=100).write(tmp_path / "activity.fit")
+    data = path.read_bytes()
+    truncated = tmp_path / "truncated.fit"
+    # Cut off in the middle of the records
+    truncated.write_bytes(data[: len(data) // 2])
+
+    with pytest.warns(UserWarning, match="truncated or corrupted"):
+        df = polarsfit.read_recordmesgs(str(truncated))
+
+    assert 0 < df.height < 100
+    expected = polarsfit.read_recordmesgs(str(path)).head(df.height)
+    assert_frame_equal(df, expected)
+    with pytest.raises(ValueError, match="Unexpected end of data"):
+        polarsfit.read_recordmesgs(str(truncated), truncated_ok=False)
+
+
+def test_gzip_files_are_decompressed(tmp_path):
+    """.fit.gz files and gzipped bytes read the same as the plain file."""
+    path = build_activity().write(tmp_path / "activity.fit")
+    compressed = gzip.compress(path.read_bytes())
+    gz_path = tmp_path / "activity.fit.gz"
+    gz_path.write_bytes(compressed)
+    # Detected by content even without the extension
+    renamed = tmp_path / "activity_gz.fit"
+    renamed.write_bytes(compressed)
+
+    expected = polarsfit.read_recordmesgs(str(path))
+    assert_frame_equal(polarsfit.read_recordmesgs(str(gz_path)), expected)
+    assert_frame_equal(polarsfit.read_recordmesgs(str(renamed)), expected)
+    assert_frame_equal(
+        polarsfit.read_data_from_bytes(compressed, "lap"),
+        polarsfit.read_data(str(path), "lap"),
+    )
+    assert polarsfit.get_message_counts(str(gz_path)) == (
+        polarsfit.get_message_counts(str(path))
+    )
+
+
+def test_verify_crc_detects_corruption(tmp_path):
+    """A flipped byte fails CRC verification but still reads by default."""
+    data = bytearray(build_activity().to_bytes())
+    good = tmp_path / "good.fit"
+    good.write_bytes(bytes(data))
+    # The last byte of the last record: the session sport
+    data[-3] ^= 0x01
+    corrupted = tmp_path / "corrupted.fit"
+    corrupted.write_bytes(bytes(data))
+
+    assert polarsfit.read_data(str(good), "record", verify_crc=True).height == 10
+    with pytest.raises(ValueError, match="CRC mismatch"):
+        polarsfit.read_data(str(corrupted), "session", verify_crc=True)
+    with pytest.raises(ValueError, match="CRC mismatch"):
+        polarsfit.read_all_messages(str(corrupted), verify_crc=True)
+
+    assert polarsfit.read_data(str(corrupted), "record").height == 10
+
+
+def test_verify_crc_checks_the_header(tmp_path):
+    """A corrupted header CRC is reported separately from the file CRC."""
+    data = bytearray(build_activity().to_bytes())
+    data[12] ^= 0xFF
+
+    with pytest.raises(ValueError, match="Header CRC mismatch"):
+        polarsfit.read_data_from_bytes(bytes(data), "record", verify_crc=True)
+
+
+def test_read_many_data_in_parallel(tmp_path):
+    """Reading one file 16 times in parallel yields identical frames."""
+    path = build_activity(records=50).write(tmp_path / "activity.fit")
+    expected = polarsfit.read_data(str(path), "record")
+
+    frames = polarsfit.read_many_data([str(path)] * 16, "record")
+
+    assert len(frames) == 16
+    for df in frames:
+        assert_frame_equal(df, expected)
+
+
+def test_read_many_data_keeps_going_past_bad_files(tmp_path):
+    """One bad file doesn't abort the batch when errors are returned."""
+    good = build_activity(records=5).write(tmp_path / "good.fit")
+    bad = tmp_path / "bad.fit"
+    bad.write_text("not a FIT file")
+    paths = [str(good), str(tmp_path / "missing.fit"), str(bad), str(good)]
+
+    results = polarsfit.read_many_data(paths, "record", on_error="return")
+
+    assert [type(r) for r in results] == [
+        pl.DataFrame,
+        FileNotFoundError,
+        ValueError,
+        pl.DataFrame,
+    ]
+    assert results[3].height == 5
+
+    with pytest.raises(FileNotFoundError, match="missing.fit"):
+        polarsfit.read_many_data(paths, "record")
+
+
+def test_read_many_data_takes_the_options_of_read_data(tmp_path):
+    """Every option of read_data applies to each file of a parallel read."""
+    path = build_activity(records=20).write(tmp_path / "activity.fit")
+    options = {
+        "categorical_enums": False,
+        "keep_raw": True,
+        "all_float": True,
+        "limit": 8,
+        "start_time": START + 3,
+        "temperature_unit": "F",
+        "encoding": "latin-1",
+        "truncated_ok": False,
+    }
+    expected = polarsfit.read_data(str(path), "record", **options)
+
+    frames = polarsfit.read_many_data([str(path)] * 2, "record", **options)
+
+    assert expected.height == 8
+    for df in frames:
+        assert_frame_equal(df, expected)
+
+
+def test_read_many_concat_unions_columns(tmp_path):
+    """Files with differing field sets stack into the union of columns."""
+    with_power = (
+        FitBuilder()
+        .define(0, RECORD, [(253, UINT32), (3, UINT8), (7, UINT16)])
+        .data(0, [fit_time(START), 120, 250])
+        .data(0, [fit_time(START + 1), 121, 251])
+        .write(tmp_path / "with_power.fit")
+    )
+    with_cadence = (
+        FitBuilder()
+        .define(0, RECORD, [(253, UINT32), (3, UINT8), (4, UINT8)])
+        .data(0, [fit_time(START), 130, 90])
+        .write(tmp_path / "with_cadence.fit")
+    )
+    paths = [str(with_power), str(with_cadence)]
+
+    df = polarsfit.read_many_concat(paths, "record")
+
+    assert df.columns[0] == "source"
+    assert {"heart_rate", "power", "cadence"} <= set(df.columns)
+    assert df["source"].to_list() == [paths[0], paths[0], paths[1]]
+    assert df["heart_rate"].to_list() == [120, 121, 130]
+    assert df["power"].to_list() == [250, 251, None]
+    assert df["cadence"].to_list() == [None, None, 90]
+
+    unlabeled = polarsfit.read_many_concat(paths, "record", source_column=None)
+    assert "source" not in unlabeled.columns
+
+
+def test_read_many_concat_takes_the_options_of_read_data(tmp_path):
+    """Options of read_data apply to every file that is stacked."""
+    path = build_activity(records=10).write(tmp_path / "activity.fit")
+    options = {
+        "limit": 4,
+        "end_time": START + 5,
+        "categorical_enums": False,
+        "keep_raw": True,
+    }
+    expected = polarsfit.read_data(str(path), "record", **options)
+
+    df = polarsfit.read_many_concat(
+        [str(path)] * 2, "record", source_column=None, **options
+    )
+
+    assert_frame_equal(df, pl.concat([expected, expected]))
+
+
+def test_read_directory_stacks_matching_files(tmp_path):
+    """Every FIT file of a folder is read, other matches are skipped."""
+    build_activity(records=3).write(tmp_path / "morning.fit")
+    build_activity(records=5).write(tmp_path / "evening.fit")
+    (tmp_path / "notes.fit").write_text("not a FIT file")
+    (tmp_path / "readme.txt").write_text("ignored by the pattern")
+
+    with pytest.warns(UserWarning, match="notes.fit"):
+        df = polarsfit.read_directory(str(tmp_path))
+
+    assert df.height == 8
+    assert df.columns[0] == "source"
+    assert df["source"].unique().sort().to_list() == [
+        str(tmp_path / "evening.fit"),
+        str(tmp_path / "morning.fit"),
+    ]
+    sessions = polarsfit.read_directory(
+        str(tmp_path), "morning.*", message_type="session"
+    )
+    assert sessions.height == 1
+    with pytest.raises(FileNotFoundError):
+        polarsfit.read_directory(str(tmp_path / "missing"))
+
+
+def test_read_directory_takes_the_options_of_read_data(tmp_path):
+    """Every option of read_data applies to each file of a directory."""
+    morning = build_activity(records=6).write(tmp_path / "morning.fit")
+    evening = build_activity(records=8).write(tmp_path / "evening.fit")
+    options = {
+        "categorical_enums": False,
+        "keep_raw": True,
+        "limit": 4,
+        "start_time": START + 1,
+        "temperature_unit": "F",
+    }
+    expected = pl.concat(
+        [
+            polarsfit.read_data(str(evening), "session", **options),
+            polarsfit.read_data(str(morning), "session", **options),
+        ]
+    )
+
+    df = polarsfit.read_directory(
+        str(tmp_path), message_type="session", source_column=None, **options
+    )
+
+    assert df.schema["sport"] == pl.String
+    assert_frame_equal(df, expected)
+
+def test_column_order_follows_first_appearance(tmp_path):
+    """Columns come in the order fields first appear in the file, every run."""
+    path = (
+        FitBuilder()
+        .define(0, RECORD, [(7, UINT16), (253, UINT32), (3, UINT8)])
+        .data(0, [250, fit_time(START), 120])
+        .define(0, RECORD, [(4, UINT8), (253, UINT32), (3, UINT8), (7, UINT16)])
+        .data(0, [90, fit_time(START + 1), 121, 251])
+        .write(tmp_path / "order.fit")
+    )
+    expected = ["power", "timestamp", "heart_rate", "cadence"]
+
+    assert polarsfit.read_data(str(path), "record").columns == expected
+    assert polarsfit.read_data(str(path), "record").columns == expected
+
+    # A fresh process seeds its hash maps differently
+    script = (
+        "import sys, polarsfit;"
+        "print(','.join(polarsfit.read_data(sys.argv[1], 'record').columns))"
+    )
+    result = subprocess.run(
+        [sys.executable, "-c", script, str(path)],
+        capture_output=True,
+        text=True,
+        check=True,
+    )
+    assert result.stdout.strip().split(",") == expected
+
+
+def test_concat_frames_unions_schema():
+    """Disjoint columns are unioned in first-seen order, int and float promote."""
+    first = pl.DataFrame(
+        {"timestamp": [1, 2], "power": [250, 251]},
+        schema={"timestamp": pl.Int64, "power": pl.UInt16},
+    )
+    second = pl.DataFrame(
+        {"timestamp": [3], "cadence": [90], "power": [260.5]},
+        schema={"timestamp": pl.Int64, "cadence": pl.UInt8, "power": pl.Float32},
+    )
+
+    df = polarsfit.concat_frames([first, second])
+
+    assert df.schema == pl.Schema(
+        {"timestamp": pl.Int64, "power": pl.Float64, "cadence": pl.UInt8}
+    )
+    assert df["power"].to_list() == [250.0, 251.0, 260.5]
+    assert df["cadence"].to_list() == [None, None, 90]
+
+    with pytest.raises(ValueError, match="columns"):
+        polarsfit.concat_frames([first, second], how="vertical")
+    with pytest.raises(ValueError, match="how"):
+        polarsfit.concat_frames([first, second], how="horizontal")
+
+
+def test_scan_data_matches_eager_read(tmp_path):
+    """Lazy scans stream batches but collect to the same data."""
+    path = build_activity(records=25).write(tmp_path / "activity.fit")
+    eager = polarsfit.read_data(str(path), "record")
+
+    lazy = polarsfit.scan_data(str(path), "record", batch_size=4)
+
+    assert isinstance(lazy, pl.LazyFrame)
+    assert lazy.collect_schema() == eager.schema
+    assert_frame_equal(lazy.collect(), eager)
+    assert_frame_equal(
+        lazy.filter(pl.col("heart_rate") > 130).select("speed").collect(),
+        eager.filter(pl.col("heart_rate") > 130).select("speed"),
+    )
+    assert lazy.head(6).collect().height == 6
+    assert_frame_equal(
+        polarsfit.scan_recordmesgs(str(path)).collect(),
+        polarsfit.read_recordmesgs(str(path)),
+    )
+
+
+def test_scan_data_takes_the_options_of_read_data(tmp_path):
+    """Every option of read_data applies to a lazy scan."""
+    path = build_activity(records=25).write(tmp_path / "activity.fit")
+    options = {
+        "categorical_enums": False,
+        "keep_raw": True,
+        "add_index": True,
+        "limit": 9,
+        "start_time": START + 3,
+        "temperature_unit": "F",
+        "encoding": "latin-1",
+    }
+    expected = polarsfit.read_data(str(path), "record", **options)
+
+    lazy = polarsfit.scan_data(str(path), "record", batch_size=4, **options)
+
+    assert expected.height == 9
+    assert_frame_equal(lazy.collect(), expected)
+    assert_frame_equal(
+        polarsfit.scan_recordmesgs(str(path), batch_size=4, **options).collect(),
+        expected,
+    )
+
+def test_preallocated_columns_match_across_readers(tmp_path):
+    """Columns sized from a message count pre-pass hold exactly the rows read."""
+    path = build_activity(records=200, laps=5).write(tmp_path / "activity.fit")
+    eager = polarsfit.read_data(str(path), "record")
+
+    assert eager.height == polarsfit.get_message_counts(str(path))["record"]
+    assert_frame_equal(polarsfit.read_all_messages(str(path))["record"], eager)
+    for batch_size in [1, 7, 200, 1000]:
+        lazy = polarsfit.scan_data(str(path), "record", batch_size=batch_size)
+        assert_frame_equal(lazy.collect(), eager)
+
+
+def test_batch_reader_yields_bounded_batches(tmp_path):
+    """The batch reader splits messages into frames of at most batch_size."""
+    from polarsfit._internal import MessageBatchReader
+
+    path = build_activity(records=10).write(tmp_path / "activity.fit")
+
+    heights = [df.height for df in MessageBatchReader(str(path), "record", None, 4)]
+
+    assert heights == [4, 4, 2]
+
+
+def test_record_batch_reader_chunks_concatenate_to_the_records(tmp_path):
+    """Chunks of 100 records put back together are the records read at once."""
+    path = build_activity(records=250).write(tmp_path / "activity.fit")
+
+    chunks = list(polarsfit.RecordBatchReader(str(path), chunk_size=100))
+
+    assert [chunk.height for chunk in chunks] == [100, 100, 50]
+    assert_frame_equal(pl.concat(chunks), polarsfit.read_recordmesgs(str(path)))
+
+
+def test_record_batch_reader_takes_the_options_of_read_recordmesgs(tmp_path):
+    """Record options apply to each chunk as read_recordmesgs applies them."""
+    builder = FitBuilder().define(
+        0, RECORD, [(253, UINT32), (4, UINT8), (13, SINT8), (73, UINT32)]
+    )
+    for i in range(250):
+        builder.data(0, [fit_time(START + i), 80 + i % 10, 20, 3000 + i])
+    builder.define(1, SESSION, [(253, UINT32), (5, ENUM)])
+    builder.data(1, [fit_time(START + 250), 1])
+    builder.define(2, ACTIVITY, [(253, UINT32), (5, UINT32)])
+    builder.data(2, [fit_time(START + 250), fit_time(START + 250 + 3600)])
+    path = builder.write(tmp_path / "run.fit")
+    options = {
+        "add_index": True,
+        "local_time": True,
+        "double_running_cadence": True,
+        "temperature_unit": "F",
+    }
+
+    chunks = list(polarsfit.RecordBatchReader(str(path), chunk_size=100, **options))
+
+    assert [chunk.height for chunk in chunks] == [100, 100, 50]
+    expected = polarsfit.read_recordmesgs(str(path), **options)
+    assert {"speed", "local_time"} <= set(expected.columns)
+    assert_frame_equal(pl.concat(chunks), expected)
+    with pytest.raises(ValueError, match="fill_distance"):
+        polarsfit.RecordBatchReader(str(path), fill_distance=True)
+
+def test_record_batch_reader_limit_spans_all_chunks(tmp_path):
+    """The limit caps the records of all chunks together, not of each chunk."""
+    path = build_activity(records=250).write(tmp_path / "activity.fit")
+
+    for limit, heights in [(250, [100, 100, 50]), (150, [100, 50]), (100, [100])]:
+        chunks = list(
+            polarsfit.RecordBatchReader(str(path), chunk_size=100, limit=limit)
+        )
+
+        assert [chunk.height for chunk in chunks] == heights
+        assert_frame_equal(
+            pl.concat(chunks), polarsfit.read_recordmesgs(str(path), limit=limit)
+        )
+
+
+def test_record_batch_reader_reports_progress_per_chunk(tmp_path):
+    """The callback gets the running message count once for every chunk."""
+    path = build_activity(records=250, laps=0).write(tmp_path / "activity.fit")
+    counts = []
+
+    chunks = list(
+        polarsfit.RecordBatchReader(str(path), chunk_size=100, progress=counts.append)
+    )
+
+    assert len(chunks) == 3
+    # The file_id message precedes the records and the session follows them
+    assert counts == [101, 201, 252]
+
+
+def test_progress_is_reported_every_10000_messages(tmp_path):
+    """The callback gets running message counts and the total at the end."""
+    path = build_activity(records=25_000, laps=0).write(tmp_path / "long.fit")
+    counts = []
+
+    df = polarsfit.read_recordmesgs(str(path), progress=counts.append)
+
+    assert df.height == 25_000
+    # The file_id and session messages count too
+    assert counts == [10_000, 20_000, 25_002]
+
+
+def test_session_summary_uses_stable_names(tmp_path):
+    """The summary decodes session fields into human-readable columns."""
+    builder = (
+        FitBuilder()
+        .define(
+            0,
+            SESSION,
+            [
+                (253, UINT32),
+                (2, UINT32),
+                (5, ENUM),
+                (7, UINT32),
+                (8, UINT32),
+                (9, UINT32),
+                (16, UINT8),
+                (17, UINT8),
+                (124, UINT32),
+            ],
+        )
+        .data(
+            0,
+            [
+                fit_time(START + 3600),
+                fit_time(START),
+                1,
+                3_600_000,
+                3_500_000,
+                1_234_567,
+                150,
+                182,
+                3527,
+            ],
+        )
+    )
+    path = builder.write(tmp_path / "session.fit")
+
+    summary = polarsfit.read_session_summary(str(path))
+
+    assert summary.height == 1
+    row = summary.row(0, named=True)
+    assert row["sport"] == "running"
+    assert abs(row["total_distance"] - 12345.67) < 1e-6
+    assert abs(row["avg_speed"] - 3.527) < 1e-9
+    assert row["avg_heart_rate"] == 150
+    assert row["max_heart_rate"] == 182
+    # Fields the device didn't record are null but still present
+    assert row["avg_power"] is None
+    assert summary["end_time"][0] - summary["start_time"][0] == (
+        timedelta(hours=1)
+    )
+
+
+def test_read_timer_events_pairs_starts_and_stops(tmp_path):
+    """An auto-pause splits the activity into two active segments."""
+    builder = FitBuilder().define(0, EVENT, [(253, UINT32), (0, ENUM), (1, ENUM)])
+    # event 0 is the timer, 42 a gear change; types 0 start, 1 stop,
+    # 3 marker, 4 stop_all
+    events = [(0, 0, 0), (300, 0, 3), (600, 0, 1), (660, 0, 0), (700, 0, 0)]
+    events += [(1200, 0, 4), (1210, 42, 3)]
+    for offset, event, event_type in events:
+        builder.data(0, [fit_time(START + offset), event, event_type])
+    path = builder.write(tmp_path / "paused.fit")
+
+    segments = polarsfit.read_timer_events(str(path))
+
+    start = datetime.fromtimestamp(START, tz=timezone.utc)
+    assert segments.columns == ["start_time", "end_time", "duration"]
+    assert segments.height == 2
+    assert segments["start_time"].to_list() == [
+        start,
+        start + timedelta(seconds=660),
+    ]
+    assert segments["end_time"].to_list() == [
+        start + timedelta(seconds=600),
+        start + timedelta(seconds=1200),
+    ]
+    assert segments["duration"].to_list() == [600.0, 540.0]
+    run = build_activity().write(tmp_path / "run.fit")
+    assert polarsfit.read_timer_events(str(run)).is_empty()
+
+
+def test_local_time_applies_the_activity_offset(tmp_path):
+    """local_time is the UTC timestamp shifted by the recording timezone."""
+    offset = -5 * 3600
+    builder = build_activity(records=3)
+    builder.define(4, ACTIVITY, [(253, UINT32), (5, UINT32)])
+    builder.data(4, [fit_time(START + 3), fit_time(START + 3 + offset)])
+    path = builder.write(tmp_path / "new_york.fit")
+
+    records = polarsfit.read_recordmesgs(str(path), local_time=True)
+
+    assert records.schema["local_time"] == pl.Datetime("ms")
+    expected = [
+        datetime.fromtimestamp(START + i + offset, tz=timezone.utc).replace(
+            tzinfo=None
+        )
+        for i in range(3)
+    ]
+    assert records["local_time"].to_list() == expected
+    raw = polarsfit.read_recordmesgs(
+        str(path), local_time=True, decode_timestamps=False
+    )
+    assert (raw["local_time"] - raw["timestamp"]).to_list() == [offset] * 3
+    assert_frame_equal(
+        polarsfit.read_recordmesgs_from_bytes(path.read_bytes(), local_time=True),
+        records,
+    )
+
+    # Without an activity message the offset is unknown
+    unknown = build_activity(records=3).write(tmp_path / "unknown.fit")
+    records = polarsfit.read_recordmesgs(str(unknown), local_time=True)
+    assert records["local_time"].null_count() == 3
+    assert "local_time" not in polarsfit.read_recordmesgs(str(unknown)).columns
+
+
+def test_double_running_cadence_only_doubles_runs(tmp_path):
+    """Running cadence becomes total steps per minute, cycling cadence stays."""
+
+    def build(sport):
+        builder = FitBuilder().define(
+            0, RECORD, [(253, UINT32), (4, UINT8), (53, UINT8)]
+        )
+        for i in range(3):
+            # 64/128 of a step per minute on top of the whole steps
+            builder.data(0, [fit_time(START + i), 85 + i, 64])
+        builder.define(1, SESSION, [(253, UINT32), (2, UINT32), (5, ENUM)])
+        builder.data(1, [fit_time(START + 3), fit_time(START), sport])
+        return builder
+
+    run = build(sport=1).write(tmp_path / "run.fit")
+    ride = build(sport=2).write(tmp_path / "ride.fit")
+
+    doubled = polarsfit.read_recordmesgs(str(run), double_running_cadence=True)
+    single = polarsfit.read_recordmesgs(str(run))
+    ride_records = polarsfit.read_recordmesgs(str(ride), double_running_cadence=True)
+
+    assert doubled["cadence"].to_list() == [170, 172, 174]
+    assert doubled["fractional_cadence"].to_list() == [1.0, 1.0, 1.0]
+    assert single["cadence"].to_list() == [85, 86, 87]
+    assert ride_records["cadence"].to_list() == [85, 86, 87]
+    assert ride_records["fractional_cadence"].to_list() == [0.5, 0.5, 0.5]
+
+
+def test_fill_distance_reconstructs_distance_from_gps(tmp_path):
+    """A GPS-only track gets a monotonic distance; recorded distance is kept."""
+
+    def build(distance):
+        fields = [(253, UINT32), (0, SINT32), (1, SINT32)]
+        builder = FitBuilder().define(0, RECORD, [*fields, (5, UINT32)])
+        for i in range(10):
+            # Due north by 0.001 degrees, about 111 m, per record; cm distance
+            lat = round((45 + i / 1000) / 180 * 2**31)
+            builder.data(0, [fit_time(START + i), lat, 2**26, distance(i)])
+        return builder
+
+    gps_only = build(lambda i: 0xFFFFFFFF).write(tmp_path / "gps.fit")
+    wheel = build(lambda i: i * 10_000).write(tmp_path / "wheel.fit")
+
+    filled = polarsfit.read_recordmesgs(str(gps_only), fill_distance=True)
+
+    assert filled["distance"][0] == 0.0
+    assert filled["distance"].diff().drop_nulls().min() > 0
+    assert filled["distance"][-1] == pytest.approx(9 * 111.2, rel=0.01)
+    assert polarsfit.read_recordmesgs(str(gps_only))["distance"].null_count() == 10
+    kept = polarsfit.read_recordmesgs(str(wheel), fill_distance=True)
+    assert kept["distance"].to_list() == [i * 100.0 for i in range(10)]
+    forced = polarsfit.read_recordmesgs(str(wheel), fill_distance=True, force=True)
+    assert_frame_equal(forced, filled)
+    raw = polarsfit.read_recordmesgs(
+        str(gps_only), fill_distance=True, convert_gps=False
+    )
+    assert raw["distance"].to_list() == pytest.approx(filled["distance"].to_list())
+
+
+def test_read_course_points_decodes_cue_types(tmp_path):
+    """Course point types come back by name, with positions and distances."""
+    fields = [(1, UINT32), (2, SINT32), (3, SINT32), (4, UINT32), (5, ENUM)]
+    builder = FitBuilder().define(0, COURSE_POINT, [*fields, (6, STRING, 16)])
+    # Types 6 left, 1 summit and 7 right; 2**29 semicircles are 45 degrees
+    cues = [(60, 125_050, 6, "Left onto Main"), (600, 400_000, 1, "Col du Test")]
+    cues += [(700, 410_000, 7, "")]
+    for offset, distance, kind, name in cues:
+        builder.data(
+            0, [fit_time(START + offset), 2**29, 2**26, distance, kind, name]
+        )
+    path = builder.write(tmp_path / "course.fit")
+
+    points = polarsfit.read_course_points(str(path))
+
+    assert points.columns == [
+        "timestamp",
+        "name",
+        "type",
+        "position_lat",
+        "position_long",
+        "distance",
+    ]
+    assert points["type"].to_list() == ["left", "summit", "right"]
+    assert points["name"].to_list() == ["Left onto Main", "Col du Test", None]
+    assert points["distance"].to_list() == [1250.5, 4000.0, 4100.0]
+    assert points["position_lat"].to_list() == [45.0] * 3
+    assert polarsfit.read_course_points(
+        str(build_activity().write(tmp_path / "run.fit"))
+    ).is_empty()
+
+
+def test_read_workout_steps_decodes_targets(tmp_path):
+    """Steps keep their order, with target types by name and scaled durations."""
+    fields = [(254, UINT16), (0, STRING, 16), (1, ENUM), (2, UINT32), (3, ENUM)]
+    fields += [(4, UINT32), (5, UINT32), (6, UINT32), (7, ENUM)]
+    builder = FitBuilder().define(0, WORKOUT_STEP, fields)
+    # 10 min warm-up in HR zone 2, 5 min at 250-300 W, 1 km cool-down at
+    # speed zone 1, then repeat from step 1
+    builder.data(0, [0, "Warm up", 0, 600_000, 1, 2, 0, 0, 2])
+    builder.data(0, [1, "Threshold", 0, 300_000, 4, 0, 250, 300, 0])
+    builder.data(0, [2, "Cool down", 1, 100_000, 0, 1, 0, 0, 3])
+    builder.data(0, [3, "", 6, 1, 2, 3, 0, 0, 0xFF])
+    path = builder.write(tmp_path / "workout.fit")
+
+    steps = polarsfit.read_workout_steps(str(path))
+
+    assert steps.height == 4
+    assert steps["message_index"].to_list() == [0, 1, 2, 3]
+    assert steps["target_type"].to_list() == ["heart_rate", "power", "speed", "open"]
+    assert steps["duration_type"].to_list() == [
+        "time",
+        "time",
+        "distance",
+        "repeat_until_steps_cmplt",
+    ]
+    assert steps["duration_value"].to_list() == [600.0, 300.0, 1000.0, 1.0]
+    assert steps["custom_target_value_high"].to_list() == [0, 300, 0, 0]
+    assert steps["intensity"].to_list() == ["warmup", "active", "cooldown", None]
+    assert polarsfit.read_workout_steps(
+        str(build_activity().write(tmp_path / "run.fit"))
+    ).is_empty()
+
+
+def test_read_lengths_decodes_pool_swim(tmp_path):
+    """Each length gets its stroke by name, the session its pool length."""
+    strokes = [0, 1, 2, 3, 0xFF]
+    builder = FitBuilder().define(
+        0,
+        LENGTH,
+        [
+            (253, UINT32),
+            (2, UINT32),
+            (3, UINT32),
+            (4, UINT32),
+            (5, UINT16),
+            (7, ENUM),
+            (12, ENUM),
+        ],
+    )
+    for i, stroke in enumerate(strokes):
+        active = 0 if stroke == 0xFF else 1
+        builder.data(
+            0,
+            [
+                fit_time(START + 30 * (i + 1)),
+                fit_time(START + 30 * i),
+                30_000,
+                29_500,
+                18 * active,
+                stroke,
+                active,
+            ],
+        )
+    builder.define(1, SESSION, [(253, UINT32), (5, ENUM), (44, UINT16)])
+    builder.data(1, [fit_time(START + 150), 5, 2500])
+    path = builder.write(tmp_path / "pool_swim.fit")
+
+    lengths = polarsfit.read_lengths(str(path))
+
+    assert lengths.height == len(strokes)
+    assert lengths["swim_stroke"].to_list() == [
+        "freestyle",
+        "backstroke",
+        "breaststroke",
+        "butterfly",
+        None,
+    ]
+    assert lengths["length_type"].to_list() == ["active"] * 4 + ["idle"]
+    assert lengths["total_strokes"].to_list() == [18, 18, 18, 18, 0]
+    assert lengths["total_elapsed_time"].to_list() == [30.0] * 5
+    assert (lengths["end_time"] - lengths["start_time"]).to_list() == [
+        timedelta(seconds=30)
+    ] * 5
+    # Fields the device didn't record are null but still present
+    assert lengths["avg_swimming_cadence"].null_count() == len(strokes)
+    summary = polarsfit.read_session_summary(str(path))
+    assert summary["pool_length"].to_list() == [25.0]
+    run = build_activity().write(tmp_path / "run.fit")
+    assert polarsfit.read_lengths(str(run)).is_empty()
+
+
+def test_file_metadata_identifies_the_device(tmp_path):
+    """file_id and device_info are decoded to names."""
+    builder = (
+        FitBuilder()
+        .define(
+            0, FILE_ID, [(0, ENUM), (1, UINT16), (2, UINT16), (3, UINT32Z), (4, UINT32)]
+        )
+        .data(0, [4, 1, 1836, 3902378567, fit_time(START)])
+        .define(1, DEVICE_INFO, [(253, UINT32), (0, UINT8), (2, UINT16), (5, UINT16)])
+        .data(1, [fit_time(START), 0, 1, 320])
+        .data(1, [fit_time(START), 0, 1, 320])
+        .data(1, [fit_time(START), 1, 32, 0xFFFF])
+        .data(1, [fit_time(START), 2, 9999, 0xFFFF])
+    )
+    path = builder.write(tmp_path / "garmin.fit")
+
+    meta = polarsfit.read_file_metadata(str(path))
+
+    assert meta["manufacturer"] == "garmin"
+    assert meta["file_type"] == "activity"
+    assert meta["product"] == 1836
+    assert meta["serial_number"] == 3902378567
+    assert meta["time_created"] == datetime.fromtimestamp(START, tz=timezone.utc)
+    assert abs(meta["software_version"] - 3.2) < 1e-9
+
+    devices = meta["devices"]
+    assert devices.height == 3
+    assert devices["manufacturer"].to_list() == [
+        "garmin",
+        "wahoo_fitness",
+        "manufacturer_9999",
+    ]
+
+
+def test_decode_manufacturer_and_product_names():
+    """Known ids decode to profile names, unknown ones to a placeholder."""
+    assert polarsfit.decode_manufacturer(1) == "garmin"
+    assert polarsfit.decode_manufacturer(32) == "wahoo_fitness"
+    assert polarsfit.decode_manufacturer(23) == "suunto"
+    assert polarsfit.decode_manufacturer(9999) == "manufacturer_9999"
+
+    assert polarsfit.decode_product(1, 1836) == "edge1000"
+    # Dynastream products share Garmin's table
+    assert polarsfit.decode_product(15, 1752) == "hrm_run"
+    assert polarsfit.decode_product(263, 12) == "assioma_duo"
+    assert polarsfit.decode_product(32, 9) == "product_9"
+    assert polarsfit.decode_product(1, 9999) == "product_9999"
+
+
+def test_schema_option_casts_columns(tmp_path):
+    """Columns listed in schema are cast; the rest keep their inferred dtype."""
+    path = build_activity().write(tmp_path / "activity.fit")
+    inferred = polarsfit.read_recordmesgs(str(path))
+
+    df = polarsfit.read_recordmesgs(str(path), schema={"heart_rate": "f64"})
+
+    assert df.schema["heart_rate"] == pl.Float64
+    assert df["heart_rate"].to_list() == [float(120 + i) for i in range(10)]
+    assert df.schema["speed"] == inferred.schema["speed"]
+    with pytest.raises(ValueError, match="Unknown dtype"):
+        polarsfit.read_recordmesgs(str(path), schema={"heart_rate": "decimal"})
+
+
+def test_get_schema_matches_read_data(tmp_path):
+    """get_schema reports the columns read_data returns, by dtype name."""
+    path = build_activity().write(tmp_path / "activity.fit")
+
+    schema = polarsfit.get_schema(str(path), "record")
+
+    assert list(schema) == polarsfit.read_data(str(path), "record").columns
+    assert schema["timestamp"] == "datetime"
+    assert schema["speed"] == "f64"
+    forced = polarsfit.read_data(str(path), "record", schema=schema)
+    assert polarsfit.read_data(str(path), "record").schema == forced.schema
+
+
+def test_get_schema_takes_the_options_of_read_data(tmp_path):
+    """get_schema reports the columns read_data returns for the same options."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (3, UINT8), (13, SINT8)])
+    for i in range(4):
+        builder.data(0, [fit_time(START + i), 120 + i, 20 + i])
+    path = builder.write(tmp_path / "temperature.fit")
+    options = {
+        "fields": ["timestamp", "temperature"],
+        "add_index": True,
+        "decode_timestamps": False,
+        "temperature_unit": "F",
+        "limit": 2,
+    }
+
+    schema = polarsfit.get_schema(str(path), "record", **options)
+
+    df = polarsfit.read_data(str(path), "record", **options)
+    assert list(schema) == df.columns
+    assert schema["temperature"] == "f64"
+    assert df.schema["temperature"] == pl.Float64
+    assert schema["timestamp"] != "datetime"
+
+def test_units_come_from_the_profile(tmp_path):
+    """Columns are returned with their FIT profile units."""
+    path = build_activity().write(tmp_path / "activity.fit")
+
+    df, units = polarsfit.read_data_with_units(str(path), "record")
+
+    assert_frame_equal(df, polarsfit.read_data(str(path), "record"))
+    assert units["heart_rate"] == "bpm"
+    assert units["speed"] == "m/s"
+    # Decoded timestamps are datetimes, not seconds
+    assert "timestamp" not in units
+
+
+def test_resample_records_to_fixed_interval(tmp_path):
+    """A 1 Hz file resampled to 5 s has one row per 5 s window."""
+    path = build_activity(records=20).write(tmp_path / "activity.fit")
+
+    df = polarsfit.resample_records(str(path), 5)
+
+    assert df.height == 4
+    assert df["timestamp"].diff().drop_nulls().unique().to_list() == [
+        timedelta(seconds=5)
+    ]
+    # 1 Hz heart rate 120, 121, ... averages to the middle of each window
+    assert df["heart_rate"].to_list() == [122.0, 127.0, 132.0, 137.0]
+
+
+def test_time_range_skips_messages_outside_it(tmp_path):
+    """Only records within the inclusive time range are decoded."""
+    path = build_activity(records=10).write(tmp_path / "activity.fit")
+    start = datetime.fromtimestamp(START + 3, tz=timezone.utc)
+
+    df = polarsfit.read_recordmesgs(
+        str(path), start_time=start, end_time=START + 6
+    )
+
+    assert df.height == 4
+    assert df["timestamp"].min() == start
+    assert df["timestamp"].max() == start + timedelta(seconds=3)
+    # file_id has no timestamp field, so it is outside any range
+    file_id = polarsfit.read_data(str(path), "fileid", start_time=START)
+    assert file_id.is_empty()
+
+
+def test_add_index_numbers_messages_in_file_order(tmp_path):
+    """message_index is the leading column counting messages from 0."""
+    path = build_activity(records=10).write(tmp_path / "activity.fit")
+
+    df = polarsfit.read_recordmesgs(str(path), add_index=True)
+
+    assert df.columns[0] == "message_index"
+    assert df.schema["message_index"] == pl.UInt32
+    assert df["message_index"].to_list() == list(range(10))
+    assert df["message_index"].diff().drop_nulls().min() > 0
+    # Batches keep counting where the previous one stopped
+    scanned = polarsfit.scan_recordmesgs(str(path), batch_size=3, add_index=True)
+    assert_frame_equal(scanned.collect(), df)
+    windowed = polarsfit.read_recordmesgs(
+        str(path), start_time=START + 5, add_index=True
+    )
+    assert windowed["message_index"].to_list() == list(range(5, 10))
+
+
+@pytest.mark.parametrize("header_size", [12, 14])
+def test_read_header(tmp_path, header_size):
+    """Both header layouts are recognized, with their versions and sizes."""
+    builder = build_activity()
+    builder.header_size = header_size
+    path = builder.write(tmp_path / "activity.fit")
+
+    header = polarsfit.read_header(str(path))
+
+    assert header["header_size"] == header_size
+    assert header["protocol_version"] == 2.0
+    assert header["profile_version"] == 21.32
+    assert header["data_size"] == len(builder.to_bytes()) - header_size - 2
+    assert header["data_type"] == ".FIT"
+    assert (header["header_crc"] is None) == (header_size == 12)
+
+
+def test_records_are_assigned_to_their_laps(tmp_path):
+    """Records split between two laps; records before the first get lap 0."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (3, UINT8)])
+    for i in range(-2, 12):
+        builder.data(0, [fit_time(START + i), 120 + i])
+    # Two five second laps; total_elapsed_time is in ms
+    builder.define(1, LAP, [(253, UINT32), (2, UINT32), (7, UINT32)])
+    builder.data(1, [fit_time(START + 5), fit_time(START), 5000])
+    builder.data(1, [fit_time(START + 10), fit_time(START + 5), 5000])
+    path = builder.write(tmp_path / "laps.fit")
+
+    df = polarsfit.read_records_with_laps(str(path))
+
+    assert df.schema["lap"] == pl.UInt32
+    assert df.height == 14
+    assert df["lap"].to_list() == [0, 0] + [1] * 5 + [2] * 7
+    assert_frame_equal(df.drop("lap"), polarsfit.read_recordmesgs(str(path)))
+
+
+def test_lap_fields_are_copied_onto_their_records(tmp_path):
+    """Every record gets its lap's average power; fields without laps are null."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (7, UINT16)])
+    for i in range(-1, 10):
+        builder.data(0, [fit_time(START + i), 200 + i])
+    fields = [(253, UINT32), (2, UINT32), (7, UINT32), (19, UINT16), (9, UINT32)]
+    builder.define(1, LAP, fields)
+    # Laps of 5 s at 202 W over 25 m and 5 s at 207 W over 30 m; cm distances
+    builder.data(1, [fit_time(START + 5), fit_time(START), 5000, 202, 2500])
+    builder.data(1, [fit_time(START + 10), fit_time(START + 5), 5000, 207, 3000])
+    path = builder.write(tmp_path / "laps.fit")
+
+    df = polarsfit.read_records_with_laps(
+        str(path), lap_fields=["avg_power", "total_distance", "avg_heart_rate"]
+    )
+
+    in_laps = df.filter(pl.col("lap") > 0)
+    assert in_laps.height == 10
+    for lap, power in in_laps.select("lap", "lap_avg_power").rows():
+        assert power == {1: 202, 2: 207}[lap]
+    assert df["lap_total_distance"].to_list() == [None] + [25.0] * 5 + [30.0] * 5
+    assert df["lap_avg_heart_rate"].null_count() == df.height
+    assert_frame_equal(
+        df.drop("lap_avg_power", "lap_total_distance", "lap_avg_heart_rate"),
+        polarsfit.read_records_with_laps(str(path)),
+    )
+    with pytest.raises(ValueError, match="unknown lap fields: lap_power"):
+        polarsfit.read_records_with_laps(str(path), lap_fields=["lap_power"])
+
+
+def test_split_by_session_tags_each_leg_with_its_sport(tmp_path):
+    """A triathlon splits into swim, transitions, bike and run by start time."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (3, UINT8)])
+    for i in range(20):
+        builder.data(0, [fit_time(START + i), 100 + i])
+    builder.define(1, SESSION, [(253, UINT32), (2, UINT32), (5, ENUM)])
+    # Swimming, transition, cycling, transition and running legs
+    legs = [(0, 5), (6, 3), (8, 2), (15, 3), (16, 1)]
+    for offset, sport in legs:
+        builder.data(1, [fit_time(START + 20), fit_time(START + offset), sport])
+    path = builder.write(tmp_path / "triathlon.fit")
+
+    sessions = polarsfit.split_by_session(str(path))
+
+    assert [sport for sport, _ in sessions] == [
+        "swimming",
+        "transition",
+        "cycling",
+        "transition",
+        "running",
+    ]
+    assert [records.height for _, records in sessions] == [6, 2, 7, 1, 4]
+    assert sessions[2][1]["heart_rate"].to_list() == list(range(108, 115))
+    assert_frame_equal(
+        pl.concat(records for _, records in sessions),
+        polarsfit.read_recordmesgs(str(path)),
+    )
+
+
+def test_prefer_enhanced_speed_and_altitude(tmp_path):
+    """Enhanced speed/altitude fill the canonical columns where recorded."""
+    builder = FitBuilder().define(
+        0,
+        RECORD,
+        [(253, UINT32), (6, UINT16), (73, UINT32), (2, UINT16), (78, UINT32)],
+    )
+    # speed = raw / 1000, altitude = raw / 5 - 500
+    builder.data(0, [fit_time(START), 3000, 3500, 3000, 3500])
+    builder.data(0, [fit_time(START + 1), 3100, 0xFFFFFFFF, 3100, 0xFFFFFFFF])
+    path = builder.write(tmp_path / "enhanced.fit")
+
+    df = polarsfit.read_recordmesgs(str(path))
+    legacy = polarsfit.read_recordmesgs(str(path), prefer_enhanced=False)
+
+    assert "enhanced_speed" not in df.columns
+    assert "enhanced_altitude" not in df.columns
+    assert df["speed"][0] == legacy["enhanced_speed"][0] == 3.5
+    assert df["altitude"][0] == legacy["enhanced_altitude"][0] == 200.0
+    # Without an enhanced value the legacy one is kept
+    assert df["speed"][1] == legacy["speed"][1] == 3.1
+    assert df["altitude"][1] == legacy["altitude"][1] == 120.0
+
+
+def test_altitude_source_selects_and_offsets_altitude(tmp_path):
+    """Each source fills altitude; auto falls back from enhanced to baro to GPS."""
+    fields = [(253, UINT32), (0, SINT32), (2, UINT16), (78, UINT32)]
+    builder = FitBuilder().define(0, RECORD, fields)
+    # altitude = raw / 5 - 500, so 3000 is 100 m; the last records have none
+    builder.data(0, [fit_time(START), 2**29, 3000, 3005])
+    builder.data(0, [fit_time(START + 1), 2**29, 3000, 0xFFFFFFFF])
+    builder.data(0, [fit_time(START + 2), 2**29, 0xFFFF, 0xFFFFFFFF])
+    builder.data(0, [fit_time(START + 3), 2**29, 0xFFFF, 0xFFFFFFFF])
+    builder.define(1, GPS_METADATA, [(253, UINT32), (3, UINT32)])
+    for i in range(3):
+        builder.data(1, [fit_time(START + i), 3050 + 5 * i])
+    path = builder.write(tmp_path / "altitude.fit")
+
+    def altitude(**options):
+        records = polarsfit.read_recordmesgs(str(path), **options)
+        assert "enhanced_altitude" not in records.columns
+        return records["altitude"].to_list()
+
+    assert altitude() == [101.0, 100.0, 112.0, None]
+    assert altitude(altitude_source="enhanced") == [101.0, None, None, None]
+    assert altitude(altitude_source="baro") == [100.0, 100.0, None, None]
+    assert altitude(altitude_source="gps") == [110.0, 111.0, 112.0, None]
+    assert altitude(altitude_source="baro", altitude_offset=-5) == [
+        95.0,
+        95.0,
+        None,
+        None,
+    ]
+    with pytest.raises(ValueError, match="altitude_source"):
+        altitude(altitude_source="radar")
+
+    # The GPS altitude is read from the same bytes when reading from memory
+    data = path.read_bytes()
+    for source in ["auto", "gps"]:
+        assert_frame_equal(
+            polarsfit.read_recordmesgs_from_bytes(
+                data, altitude_source=source, altitude_offset=1
+            ),
+            polarsfit.read_recordmesgs(
+                str(path), altitude_source=source, altitude_offset=1
+            ),
+        )
+
+
+def test_gps_altitude_is_read_with_the_record_options(tmp_path):
+    """The GPS altitude filling records is decoded as the records are."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (0, SINT32)])
+    builder.data(0, [fit_time(START), 2**29])
+    builder.data(0, [fit_time(START + 1), 2**29])
+    builder.define(1, GPS_METADATA, [(253, UINT32), (3, UINT32)])
+    builder.data(1, [fit_time(START), 3050])
+    builder.data(1, [fit_time(START + 1), 0xFFFFFFFF])
+    path = builder.write(tmp_path / "gps_altitude.fit")
+
+    masked = polarsfit.read_recordmesgs(str(path), altitude_source="gps")
+    unmasked = polarsfit.read_recordmesgs(
+        str(path), altitude_source="gps", mask_invalid=False
+    )
+
+    assert masked["altitude"].to_list() == [110.0, None]
+    assert unmasked["altitude"].to_list() == [110.0, 0xFFFFFFFF / 5 - 500]
+    # Unscaled altitudes aren't in meters
+    with pytest.raises(ValueError, match="apply_scale"):
+        polarsfit.read_recordmesgs(str(path), apply_scale=False, altitude_offset=1)
+
+@pytest.mark.skipif((os.cpu_count() or 1) < 2, reason="needs two cores")
+def test_reads_release_the_gil(tmp_path):
+    """Two threads reading at once take closer to one read's time than two."""
+    path = build_activity(records=100_000, laps=1).write(tmp_path / "large.fit")
+
+    def read():
+        polarsfit.read_data(str(path), "record")
+
+    def elapsed(threads: int) -> float:
+        workers = [threading.Thread(target=read) for _ in range(threads)]
+        start = time.perf_counter()
+        for worker in workers:
+            worker.start()
+        for worker in workers:
+            worker.join()
+        return time.perf_counter() - start
+
+    read()  # warm up
+    single = min(elapsed(1) for _ in range(3))
+    double = min(elapsed(2) for _ in range(3))
+    assert double < 1.5 * single
+
+
+def test_limit_stops_after_that_many_messages(tmp_path):
+    """A limit keeps the first messages of the type and no more."""
+    path = build_activity(records=100).write(tmp_path / "activity.fit")
+
+    df = polarsfit.read_recordmesgs(str(path), limit=10)
+
+    assert df.height == 10
+    assert_frame_equal(df, polarsfit.read_recordmesgs(str(path)).head(10))
+    frames = polarsfit.read_data(str(path), ["record", "lap"], limit=1)
+    assert [frames["record"].height, frames["lap"].height] == [1, 1]
+
+
+def test_records_sort_and_dedup_by_timestamp(tmp_path):
+    """Out of order and repeated timestamps give sorted, unique times."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (3, UINT8)])
+    offsets = [3, 1, 2, 2, 5, 4, 1]
+    for heart_rate, offset in enumerate(offsets, start=100):
+        builder.data(0, [fit_time(START + offset), heart_rate])
+    path = builder.write(tmp_path / "shuffled.fit")
+
+    def read(**kwargs):
+        return polarsfit.read_recordmesgs(str(path), **kwargs)
+
+    assert read().height == len(offsets)
+    ordered = read(sort_by_time=True)
+    assert ordered["timestamp"].is_sorted()
+    assert ordered["heart_rate"].to_list() == [101, 106, 102, 103, 100, 105, 104]
+
+    first = read(sort_by_time=True, dedup_time=True)
+    last = read(sort_by_time=True, dedup_time=True, dedup_keep="last")
+    assert first["timestamp"].is_unique().all()
+    assert first["heart_rate"].to_list() == [101, 102, 100, 105, 104]
+    assert last["heart_rate"].to_list() == [106, 103, 100, 105, 104]
+    # Without sorting, duplicates are dropped in file order
+    unsorted = read(dedup_time=True)
+    assert unsorted["heart_rate"].to_list() == [100, 101, 102, 104, 105]
+
+
+def test_all_float_casts_numeric_columns(tmp_path):
+    """Every numeric column becomes Float64, invalid values stay null."""
+    builder = build_activity(records=3)
+    builder.data(1, [fit_time(START + 3), 0xFF, 3003])
+    path = builder.write(tmp_path / "activity.fit")
+
+    records = polarsfit.read_recordmesgs(str(path), all_float=True)
+    sessions = polarsfit.read_data(str(path), "session", all_float=True)
+    typed = polarsfit.read_recordmesgs(
+        str(path), all_float=True, schema={"heart_rate": "u8"}
+    )
+
+    numeric = [name for name in records.columns if name != "timestamp"]
+    assert numeric
+    assert all(records.schema[name] == pl.Float64 for name in numeric)
+    assert records.schema["timestamp"] == pl.Datetime("ms", "UTC")
+    assert records["heart_rate"].to_list() == [120.0, 121.0, 122.0, None]
+    assert sessions.schema["sport"] == pl.Categorical
+    assert typed.schema["heart_rate"] == pl.UInt8
+
+
+def test_keep_raw_adds_undecoded_columns(tmp_path):
+    """Scaled and enum fields keep their raw integers next to the decoded ones."""
+    path = build_activity(records=3).write(tmp_path / "activity.fit")
+
+    records = polarsfit.read_recordmesgs(str(path), keep_raw=True)
+    sessions = polarsfit.read_data(str(path), "session", keep_raw=True)
+
+    assert records.columns == ["timestamp", "heart_rate", "speed", "speed_raw"]
+    assert records.schema["speed"] == pl.Float64
+    assert records.schema["speed_raw"].is_unsigned_integer()
+    assert records["speed"].to_list() == [3.0, 3.001, 3.002]
+    assert records["speed_raw"].to_list() == [3000, 3001, 3002]
+    assert sessions["sport"].to_list() == ["cycling"]
+    assert sessions["sport_raw"].to_list() == [2]
+    assert "speed_raw" not in polarsfit.read_recordmesgs(str(path)).columns
+    everything = polarsfit.read_all_messages(str(path), keep_raw=True)
+    assert everything["session"]["sport_raw"].to_list() == [2]
+
+
+def test_fields_restrict_decoded_columns(tmp_path):
+    """Only the requested fields are decoded, next to the optional index."""
+    path = build_activity(records=10).write(tmp_path / "activity.fit")
+    full = polarsfit.read_recordmesgs(str(path))
+
+    df = polarsfit.read_recordmesgs(str(path), fields=["timestamp", "speed"])
+    indexed = polarsfit.read_data(
+        str(path), "record", fields=["speed", "heart_rate"], add_index=True
+    )
+
+    assert_frame_equal(df, full.select("timestamp", "speed"))
+    assert indexed.columns == ["message_index", "heart_rate", "speed"]
+    assert_frame_equal(
+        polarsfit.scan_recordmesgs(str(path)).select("speed").collect(),
+        full.select("speed"),
+    )
+
+
+def test_written_records_read_back_unchanged(tmp_path):
+    """Records written to a FIT file decode to the frame they came from."""
+    path = build_activity(records=20).write(tmp_path / "activity.fit")
+    records = polarsfit.read_recordmesgs(str(path))
+
+    out = tmp_path / "written.fit"
+    polarsfit.write_records_to_fit(records, str(out))
+
+    assert_frame_equal(polarsfit.read_recordmesgs(str(out), verify_crc=True), records)
+    assert set(polarsfit.get_message_types(str(out))) == {"fileid", "record"}
+
+    # Undecoded timestamps are written as the FIT seconds they hold
+    raw = polarsfit.read_recordmesgs(str(path), decode_timestamps=False)
+    polarsfit.write_records_to_fit(raw, str(out))
+    assert_frame_equal(polarsfit.read_recordmesgs(str(out)), records)
+
+
+def test_written_categorical_enums_read_back_unchanged(tmp_path):
+    """Enum columns read as Categorical by default are written by their names."""
+    builder = FitBuilder().define(0, RECORD, [(253, UINT32), (3, UINT8), (42, ENUM)])
+    for i in range(3):
+        builder.data(0, [fit_time(START + i), 120 + i, i % 2])
+    path = builder.write(tmp_path / "activity.fit")
+    records = polarsfit.read_recordmesgs(str(path))
+    assert records.schema["activity_type"] == pl.Categorical
+
+    out = tmp_path / "written.fit"
+    polarsfit.write_records_to_fit(records, str(out))
+
+    assert_frame_equal(
+        polarsfit.read_recordmesgs(str(out)), records, categorical_as_str=True
+    )
+
+    names = polarsfit.read_recordmesgs(str(path), categorical_enums=False)
+    polarsfit.write_records_to_fit(names, str(out))
+    assert_frame_equal(
+        polarsfit.read_recordmesgs(str(out), categorical_enums=False), names
+    )
+
+@pytest.mark.parametrize("compression", ["snappy", "zstd"])
+def test_parquet_conversion_reads_back_as_read_data(tmp_path, compression):
+    """The Parquet file holds the frame read_data returns."""
+    path = build_activity(records=20).write(tmp_path / "activity.fit")
+    out = tmp_path / "records.parquet"
+
+    polarsfit.write_data_to_parquet(
+        str(path), "record", str(out), compression=compression
+    )
+
+    assert_frame_equal(pl.read_parquet(out), polarsfit.read_data(str(path), "record"))
+    with pytest.raises(ValueError, match="compression"):
+        polarsfit.write_data_to_parquet(
+            str(path), "record", str(out), compression="lz4"
+        )
+
+
+def test_arrow_ipc_bytes_load_in_pyarrow(tmp_path):
+    """The IPC stream holds every record, under the read_data column names."""
+    ipc = pytest.importorskip("pyarrow.ipc")
+    path = build_activity(records=20).write(tmp_path / "activity.fit")
+
+    stream = polarsfit.read_data_arrow_ipc(str(path), "record")
+
+    assert isinstance(stream, bytes)
+    table = ipc.open_stream(stream).read_all()
+    records = polarsfit.read_data(str(path), "record")
+    assert table.num_rows == records.height == 20
+    assert table.column_names == records.columns
+
+
+def test_field_info_names_the_fields_present(tmp_path):
+    """Field numbers in the file are reported with their profile name and unit."""
+    builder = FitBuilder().define(
+        0, RECORD, [(253, UINT32), (0, SINT32), (3, UINT8), (250, UINT8)]
+    )
+    builder.data(0, [fit_time(START), 1 << 29, 0xFF, 1])
+    path = builder.write(tmp_path / "fields.fit")
+
+    info = polarsfit.get_field_info(str(path), "record")
+
+    # heart_rate only holds the invalid sentinel, so it isn't reported
+    assert info == [
+        (0, "position_lat", "semicircles"),
+        (250, "field_250", ""),
+        (253, "timestamp", "s"),
+    ]
+    assert polarsfit.get_field_info(str(path), "lap") == []
+
+
+def test_profile_names_apply_to_every_message_type(tmp_path):
+    """Columns are named from the FIT profile unless a mapping overrides them."""
+    builder = (
+        build_activity(records=3)
+        .define(4, DEVICE_INFO, [(253, UINT32), (0, UINT8), (3, UINT32Z)])
+        .data(4, [fit_time(START), 0, 1234])
+    )
+    path = builder.write(tmp_path / "activity.fit")
+
+    records = polarsfit.read_data(str(path), "record")
+    file_id = polarsfit.read_data(str(path), "fileid")
+    devices = polarsfit.read_data(str(path), "deviceinfo", {"field_3": "serial"})
+    raw = polarsfit.read_data(str(path), "deviceinfo", apply_default_mapping=False)
+
+    assert records.columns == ["timestamp", "heart_rate", "speed"]
+    assert file_id.columns == ["type", "manufacturer", "time_created"]
+    assert devices.columns == ["timestamp", "device_index", "serial"]
+    assert raw.columns == ["field_253", "field_0", "field_3"]
+
+
+def test_read_hrv_flattens_rr_intervals(tmp_path):
+    """R-R intervals of all hrv messages form one column without padding."""
+    hrv = 78
+    builder = FitBuilder().define(0, hrv, [(0, UINT16, 10)])
+    # Intervals in ms, padded to five values with the uint16 invalid value
+    builder.data(0, [[800, 810, 820, 0xFFFF, 0xFFFF]])
+    builder.data(0, [[830, 840, 850, 860, 870]])
+    builder.data(0, [[0xFFFF] * 5])
+    builder.data(0, [[880, 0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF]])
+    path = builder.write(tmp_path / "hrv.fit")
+
+    df = polarsfit.read_hrv(str(path))
+
+    assert df.schema == {"rr_interval": pl.Float64}
+    assert df.height == 9
+    assert df["rr_interval"].to_list() == pytest.approx(
+        [0.8, 0.81, 0.82, 0.83, 0.84, 0.85, 0.86, 0.87, 0.88]
+    )
+    assert df["rr_interval"].max() < 65.535
+    without_hrv = build_activity().write(tmp_path / "activity.fit")
+    assert polarsfit.read_hrv(str(without_hrv)).is_empty()